@@ -0,0 +1,65 @@
+//! Arithmetic on screen coordinates for the map, the canvas and popups.
+//!
+//! By default these are plain `u16` operations, which panic on overflow in
+//! debug builds. With the `arith-audit` feature an overflow is recorded
+//! instead, see [`reports`], and the result is clamped so the game keeps
+//! running while the culprit is tracked down.
+
+#[cfg(feature = "arith-audit")]
+use std::{panic::Location, sync::Mutex};
+
+/// Most overflows kept by the audit
+#[cfg(feature = "arith-audit")]
+const MAX_REPORTS: usize = 100;
+
+#[cfg(feature = "arith-audit")]
+static REPORTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[cfg(feature = "arith-audit")]
+#[track_caller]
+fn report(operation: &str, a: u16, b: u16) {
+    let mut reports = REPORTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if reports.len() < MAX_REPORTS {
+        reports.push(format!(
+            "{a} {operation} {b} overflows at {}",
+            Location::caller()
+        ));
+    }
+}
+
+/// Overflows recorded so far, oldest first.
+#[cfg(feature = "arith-audit")]
+pub fn reports() -> Vec<String> {
+    REPORTS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// `a - b`, clamped at `0` under audit.
+#[track_caller]
+pub fn sub(a: u16, b: u16) -> u16 {
+    #[cfg(feature = "arith-audit")]
+    return a.checked_sub(b).unwrap_or_else(|| {
+        report("-", a, b);
+        0
+    });
+
+    #[cfg(not(feature = "arith-audit"))]
+    return a - b;
+}
+
+/// `a + b`, clamped at `u16::MAX` under audit.
+#[track_caller]
+pub fn add(a: u16, b: u16) -> u16 {
+    #[cfg(feature = "arith-audit")]
+    return a.checked_add(b).unwrap_or_else(|| {
+        report("+", a, b);
+        u16::MAX
+    });
+
+    #[cfg(not(feature = "arith-audit"))]
+    return a + b;
+}
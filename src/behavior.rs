@@ -0,0 +1,197 @@
+//! How enemies move. Each [`Behavior`] moves an enemy by a tick; which one
+//! an enemy has is data, a [`Movement`] stored on it, so new movement
+//! patterns mix with any enemy kind and load from level files:
+//!
+//! ```json
+//! "movement": {"type": "zig_zag", "direction": "left"}
+//! ```
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entities::{Direction, Location};
+
+/// What a behavior sees of the world around its enemy
+pub struct Surroundings<'a> {
+    /// The river, banks of every screen line
+    pub map: &'a VecDeque<(u16, u16)>,
+    /// Island splitting each line of the river, if any
+    pub islands: &'a VecDeque<Option<(u16, u16)>>,
+    pub player: &'a Location,
+    pub maxc: u16,
+}
+
+impl Surroundings<'_> {
+    /// Banks of the water `location` is on: the river's, or those of the
+    /// channel on its side of an island
+    pub fn banks(&self, location: &Location) -> Option<(u16, u16)> {
+        let &(left, right) = self.map.get(location.l as usize)?;
+        Some(
+            match self.islands.get(location.l as usize).copied().flatten() {
+                Some((start, _)) if location.c < start => (left, start),
+                Some((_, end)) => (end, right),
+                None => (left, right),
+            },
+        )
+    }
+}
+
+pub trait Behavior {
+    /// Move `location` by a tick. Returns `false` once the enemy is gone
+    /// for good, e.g. off the side of the screen.
+    fn act(&mut self, location: &mut Location, around: &Surroundings) -> bool;
+}
+
+/// Drifts down with the river.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Straight;
+
+impl Behavior for Straight {
+    fn act(&mut self, location: &mut Location, _around: &Surroundings) -> bool {
+        location.l += 1;
+        true
+    }
+}
+
+/// Patrols from bank to bank while drifting down.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ZigZag {
+    pub direction: Direction,
+}
+
+impl Behavior for ZigZag {
+    fn act(&mut self, location: &mut Location, around: &Surroundings) -> bool {
+        location.l += 1;
+        if let Some((left, right)) = around.banks(location) {
+            // Turn around at the banks
+            match self.direction {
+                Direction::Left if location.c > left => location.c -= 1,
+                Direction::Right if location.c + 1 < right => location.c += 1,
+                Direction::Left => self.direction = Direction::Right,
+                Direction::Right => self.direction = Direction::Left,
+            }
+        }
+        true
+    }
+}
+
+/// Drifts down, steering a column a tick toward the player.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Homing;
+
+impl Behavior for Homing {
+    fn act(&mut self, location: &mut Location, around: &Surroundings) -> bool {
+        location.l += 1;
+        let target = around.player.c;
+        if location.c < target {
+            location.c += 1;
+        } else if location.c > target {
+            location.c -= 1;
+        }
+        true
+    }
+}
+
+/// Waits on the bank and dashes across the river once the player is close,
+/// holding its line.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Strafing {
+    pub direction: Direction,
+    pub dashing: bool,
+}
+
+impl Behavior for Strafing {
+    fn act(&mut self, location: &mut Location, around: &Surroundings) -> bool {
+        if self.dashing {
+            match self.direction {
+                Direction::Left if location.c >= 2 => location.c -= 2,
+                Direction::Right => location.c += 2,
+                // Ran off the left edge
+                Direction::Left => return false,
+            }
+        } else {
+            location.l += 1;
+            self.dashing = location.l >= around.player.l;
+        }
+        true
+    }
+}
+
+/// Drifts down, steering toward the middle of the river. Keeps three cells
+/// on screen, for ships.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Centering;
+
+impl Behavior for Centering {
+    fn act(&mut self, location: &mut Location, around: &Surroundings) -> bool {
+        location.l += 1;
+        if let Some((left, right)) = around.banks(location) {
+            let center = left + (right - left) / 2;
+            match location.c.cmp(&center) {
+                std::cmp::Ordering::Less => location.c += 1,
+                std::cmp::Ordering::Greater => location.c -= 1,
+                std::cmp::Ordering::Equal => {}
+            }
+            location.c = location.c.clamp(1, around.maxc - 2);
+        }
+        true
+    }
+}
+
+/// The behavior of an enemy, as stored on it
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Movement {
+    Straight(Straight),
+    ZigZag(ZigZag),
+    Homing(Homing),
+    Strafing(Strafing),
+    Centering(Centering),
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Movement::Straight(Straight)
+    }
+}
+
+impl Movement {
+    pub fn behavior(&mut self) -> &mut dyn Behavior {
+        match self {
+            Movement::Straight(behavior) => behavior,
+            Movement::ZigZag(behavior) => behavior,
+            Movement::Homing(behavior) => behavior,
+            Movement::Strafing(behavior) => behavior,
+            Movement::Centering(behavior) => behavior,
+        }
+    }
+} // end of Movement implementation.
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{Movement, Surroundings};
+    use crate::entities::Location;
+
+    #[test]
+    fn movement_loads_from_data() {
+        let mut movement: Movement = serde_json::from_str(r#"{"type": "homing"}"#).unwrap();
+        let map = VecDeque::from(vec![(10, 30); 24]);
+        let islands = VecDeque::from(vec![None; 24]);
+        let player = Location::new(25, 23);
+        let around = Surroundings {
+            map: &map,
+            islands: &islands,
+            player: &player,
+            maxc: 80,
+        };
+
+        let mut location = Location::new(20, 0);
+        for _ in 0..3 {
+            assert!(movement.behavior().act(&mut location, &around));
+        }
+        assert_eq!((location.c, location.l), (23, 3));
+    }
+}
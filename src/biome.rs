@@ -0,0 +1,170 @@
+//! Biomes: the look of a stretch of river, switched as a whole when a new
+//! stage starts (see [`crate::stage`]) and every [`BIOME_LINES`] lines into a
+//! long one. A biome recolors the river and banks of the theme, scatters
+//! decorations on the banks and may change what spawns.
+//!
+//! Biomes are data, read from `biomes.conf` when there is one, so a new
+//! biome is a new block of lines:
+//!
+//! ```text
+//! [Forest]
+//! bank = dark_green
+//! decorations = ♣♠
+//! spawns = strafer 35
+//! ```
+//!
+//! Colors are crossterm color names; a biome leaves unset ones as the theme
+//! has them. Spawn chances are per mille, over those of the stage being
+//! played. Each stage starts in the biome at its place in the file, moving
+//! on to the next ones as it goes on, and the biomes start over at the end
+//! of the file.
+
+use std::{fs, path::Path};
+
+use crossterm::style::Color;
+
+use crate::{
+    blocks,
+    world::{self, SpawnKind},
+};
+
+pub const BIOMES_FILE: &str = "biomes.conf";
+
+/// Lines of river flown in a stage before it moves on to the next biome,
+/// three sections' worth
+pub const BIOME_LINES: u32 = 1200;
+
+const BUILT_IN: &str = "\
+[Delta]
+
+[Forest]
+bank = dark_green
+decorations = ♣♠
+spawns = strafer 35
+
+[Canyon]
+river = dark_blue
+bank = dark_yellow
+decorations = ▴
+spawns = fuel 6, helicopter 45
+
+[Tundra]
+river = dark_cyan
+bank = grey
+decorations = *·
+spawns = ship 5, obstacle 12
+";
+
+pub struct Biome {
+    pub name: String,
+    pub river: Option<Color>,
+    pub bank: Option<Color>,
+    /// Glyphs scattered on the banks, none if empty
+    pub decorations: Vec<char>,
+    /// Spawn chances changed in this biome, per mille
+    pub spawns: Vec<(SpawnKind, u16)>,
+}
+
+impl Biome {
+    fn new(name: &str) -> Biome {
+        Biome {
+            name: name.to_string(),
+            river: None,
+            bank: None,
+            decorations: Vec::new(),
+            spawns: Vec::new(),
+        }
+    }
+
+    /// The biomes shipped with the game
+    pub fn built_in() -> Vec<Biome> {
+        Biome::parse(BUILT_IN).0
+    }
+
+    /// The biomes of `path`; the built-in ones if it has none.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Vec<Biome>> {
+        let biomes = Biome::parse(&fs::read_to_string(path)?).0;
+        Ok(if biomes.is_empty() {
+            Biome::built_in()
+        } else {
+            biomes
+        })
+    }
+
+    /// Lines of `path` that [`Biome::load`] would ignore.
+    pub fn check(path: impl AsRef<Path>) -> std::io::Result<Vec<String>> {
+        Ok(Biome::parse(&fs::read_to_string(path)?).1)
+    }
+
+    /// The biomes of `config`, and the lines not understood.
+    fn parse(config: &str) -> (Vec<Biome>, Vec<String>) {
+        blocks::parse(config, Biome::new, |biome, key, value| match (key, value) {
+            ("river", color) => Color::try_from(color)
+                .map(|color| biome.river = Some(color))
+                .is_ok(),
+            ("bank", color) => Color::try_from(color)
+                .map(|color| biome.bank = Some(color))
+                .is_ok(),
+            ("decorations", glyphs) => {
+                biome.decorations = glyphs.chars().filter(|ch| *ch != ' ').collect();
+                true
+            }
+            ("spawns", chances) => world::parse_chances(chances)
+                .map(|chances| biome.spawns = chances)
+                .is_some(),
+            _ => false,
+        })
+    }
+
+    /// The decoration, if any, on bank cell `c` of the `line`th line of the
+    /// river. The same cell always gets the same one, so decorations scroll
+    /// with the banks.
+    pub fn decoration(&self, c: u16, line: u32) -> Option<char> {
+        if self.decorations.is_empty() {
+            return None;
+        }
+        let mix = u64::from(line).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ u64::from(c).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        let mix = mix ^ (mix >> 29);
+        (mix % 19 == 0).then(|| self.decorations[(mix >> 40) as usize % self.decorations.len()])
+    }
+
+    /// Chance of `kind` in this biome, if it changes it
+    pub fn spawn_chance(&self, kind: SpawnKind) -> Option<u16> {
+        self.spawns
+            .iter()
+            .find(|(changed, _)| *changed == kind)
+            .map(|(_, chance)| *chance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+
+    use super::Biome;
+    use crate::world::SpawnKind;
+
+    #[test]
+    fn biomes_are_read_in_order() {
+        let config = "[Marsh]\nbank = dark_green\ndecorations = ≈ ‚\nfog = thick\n\
+                      spawns = fuel 3, ship 0\nspawns = frog 5\n\n[Plain]\n";
+        let (biomes, ignored) = Biome::parse(config);
+        assert_eq!(ignored, ["fog = thick", "spawns = frog 5"]);
+        assert_eq!(biomes[0].spawn_chance(SpawnKind::Ship), Some(0));
+        assert_eq!(biomes[0].spawn_chance(SpawnKind::Enemy), None);
+        assert_eq!(biomes.len(), 2);
+        assert_eq!(biomes[0].bank, Some(Color::DarkGreen));
+        assert_eq!(biomes[0].decorations, ['≈', '‚']);
+        assert!(biomes[1].decoration(3, 7).is_none());
+
+        let forest = &Biome::built_in()[1];
+        let decorated = (0..20).flat_map(|c| (0..20).map(move |l| (c, l)));
+        assert!(decorated
+            .clone()
+            .any(|(c, l)| forest.decoration(c, l).is_some()));
+        assert!(decorated
+            .clone()
+            .any(|(c, l)| forest.decoration(c, l).is_none()));
+    }
+}
@@ -0,0 +1,61 @@
+//! The block format shared by `stages.conf`, `biomes.conf` and `packs.conf`:
+//! a `[Name]` line starts a block, the `key = value` lines after it fill it
+//! in, and blank lines and lines starting with `#` are skipped.
+//!
+//! ```text
+//! # the river dressed for winter
+//! [Tundra]
+//! bank = grey
+//! ```
+
+/// The blocks of `config`, and the lines not understood. `new` starts a
+/// block from its name and `set` takes a key and value for it, returning
+/// whether it understood them.
+pub fn parse<T>(
+    config: &str,
+    new: impl Fn(&str) -> T,
+    mut set: impl FnMut(&mut T, &str, &str) -> bool,
+) -> (Vec<T>, Vec<String>) {
+    let mut blocks: Vec<T> = Vec::new();
+    let mut ignored = Vec::new();
+    for line in config.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            blocks.push(new(name.trim()));
+            continue;
+        }
+
+        let understood = match (blocks.last_mut(), trimmed.split_once('=')) {
+            (Some(block), Some((key, value))) => set(block, key.trim(), value.trim()),
+            _ => false,
+        };
+        if !understood {
+            ignored.push(line.to_string());
+        }
+    }
+    (blocks, ignored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn blocks_take_their_lines() {
+        let config =
+            "stray = 1\n# a comment\n[One]\nsize = 3\n\n[ Two ]\nsize = big\ncolor = red\n";
+        let (blocks, ignored) = parse(
+            config,
+            |name| (name.to_string(), 0),
+            |block, key, value| key == "size" && value.parse().map(|size| block.1 = size).is_ok(),
+        );
+        assert_eq!(blocks, [("One".to_string(), 3), ("Two".to_string(), 0)]);
+        assert_eq!(ignored, ["stray = 1", "size = big", "color = red"]);
+    }
+}
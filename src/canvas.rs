@@ -1,14 +1,8 @@
-use std::{
-    fmt::{Display, Write},
-    io::{Stdout, Write as Wt},
-};
+use std::fmt::{Display, Write};
 
 use crossterm::style::{ContentStyle, StyledContent};
 
-use crate::{
-    drawable::Drawable,
-    stout_ext::{AsLocationTuple, StdoutExt},
-};
+use crate::{drawable::Drawable, renderer::Renderer, stout_ext::AsLocationTuple, theme::Theme};
 
 #[derive(Clone, PartialEq, Eq)]
 pub enum Block {
@@ -35,10 +29,20 @@ impl Display for Block {
 }
 
 pub struct Canvas {
+    theme: Theme,
     max_c: u16,
     mac_l: u16,
     table: Vec<Vec<Block>>,
     table_snapshot: Vec<Vec<Block>>,
+    /// The output holds leftovers of another size, blank it first
+    stale: bool,
+    /// Ticks of play so far, drives looping animations
+    clock: u32,
+    /// Glyphs drawn instead of others, see [`crate::pack`]
+    glyphs: Vec<(char, char)>,
+    /// Where the camera looks from, in columns and lines off the screen's
+    /// origin; everything is drawn shifted the other way
+    camera: (i16, i16),
 }
 
 impl Canvas {
@@ -48,26 +52,92 @@ impl Canvas {
             .collect();
 
         Self {
+            theme: Theme::default(),
             max_c,
             mac_l,
             table: table.clone(),
             table_snapshot: table,
+            stale: false,
+            clock: 0,
+            glyphs: Vec::new(),
+            camera: (0, 0),
         }
     }
 
+    /// Styles drawables should use
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Draw the second glyph of each pair wherever the first one is drawn.
+    pub fn set_glyphs(&mut self, glyphs: Vec<(char, char)>) {
+        self.glyphs = glyphs;
+    }
+
+    /// Look from `camera` on the next drawings, `(0, 0)` being the screen.
+    pub fn set_camera(&mut self, camera: (i16, i16)) {
+        self.camera = camera;
+    }
+
+    /// Ticks of play so far, see [`crate::drawable::Animation`]
+    pub fn clock(&self) -> u32 {
+        self.clock
+    }
+
+    /// A tick of play went by.
+    pub fn advance_clock(&mut self) {
+        self.clock = self.clock.wrapping_add(1);
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.max_c, self.mac_l)
+    }
+
+    /// What was drawn last, as plain text: a line per row, styles dropped.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.table {
+            let row: String = line
+                .iter()
+                .map(|block| match block {
+                    Block::Empty => ' ',
+                    Block::Acquired { character, .. } => *character,
+                })
+                .collect();
+            text += row.trim_end();
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Match a resized terminal. The output is cleared and everything drawn
+    /// again on the next [`Canvas::draw_map`], since the terminal content can
+    /// not be trusted.
+    pub fn resize(&mut self, max_c: u16, mac_l: u16) {
+        *self = Canvas {
+            theme: self.theme.clone(),
+            stale: true,
+            clock: self.clock,
+            ..Canvas::new(max_c, mac_l)
+        };
+    }
+
+    /// Draw everything again on the next [`Canvas::draw_map`], after the
+    /// output was written to behind the canvas' back.
+    pub fn invalidate(&mut self) {
+        let (max_c, mac_l) = self.size();
+        self.resize(max_c, mac_l);
+    }
+
     pub fn draw(&mut self, drawable: &impl Drawable) -> &mut Canvas {
         drawable.draw(self);
         self
     }
 
-    pub fn draw_line(
-        &mut self,
-        loc: impl AsLocationTuple,
-        display: impl Into<String>,
-    ) -> &mut Canvas {
-        self.draw_styled_line(loc, display, None)
-    }
-
     pub fn draw_styled_line(
         &mut self,
         loc: impl AsLocationTuple,
@@ -84,10 +154,6 @@ impl Canvas {
         self
     }
 
-    pub fn draw_char(&mut self, loc: impl AsLocationTuple, display: char) -> &mut Canvas {
-        self.draw_styled_char(loc, display, None)
-    }
-
     pub fn draw_styled_char(
         &mut self,
         loc: impl AsLocationTuple,
@@ -95,20 +161,16 @@ impl Canvas {
         style: impl Into<Option<ContentStyle>>,
     ) -> &mut Canvas {
         let (c, l) = loc.as_loc_tuple();
+        let display = self
+            .glyphs
+            .iter()
+            .find(|(from, _)| *from == display)
+            .map_or(display, |(_, to)| *to);
         self.acquire_block(c as usize, l as usize, display, style);
 
         self
     }
 
-    pub fn draw_styled<D: Display>(
-        &mut self,
-        loc: impl AsLocationTuple,
-        content: impl Into<StyledContent<D>>,
-    ) -> &mut Canvas {
-        let content: StyledContent<D> = content.into();
-        self.draw_styled_line(loc, content.content().to_string(), Some(*content.style()))
-    }
-
     pub fn clear_all(&mut self) -> &mut Canvas {
         self.table = (0..self.mac_l)
             .map(|_| (0..self.max_c).map(|_| Block::Empty).collect())
@@ -123,44 +185,132 @@ impl Canvas {
         new_char: char,
         style: impl Into<Option<ContentStyle>>,
     ) {
-        self.table[l][c] = Block::Acquired {
-            style: style.into(),
-            character: new_char,
-        };
+        let (c, l) = (
+            c as i64 - i64::from(self.camera.0),
+            l as i64 - i64::from(self.camera.1),
+        );
+        if c < 0 || l < 0 {
+            return;
+        }
+        // anything off screen is clipped
+        let (c, l) = (c as usize, l as usize);
+        if let Some(block) = self.table.get_mut(l).and_then(|line| line.get_mut(c)) {
+            *block = Block::Acquired {
+                style: style.into(),
+                character: new_char,
+            };
+        }
     }
 
-    fn detect_changes(&self) -> Vec<(usize, usize)> {
-        let mut changes: Vec<(usize, usize)> = vec![];
+    /// Runs of changed blocks since the last [`Canvas::draw_map`], as
+    /// `(line, first column, end column)`.
+    fn detect_changes(&self) -> Vec<(usize, usize, usize)> {
+        let mut changes: Vec<(usize, usize, usize)> = vec![];
         for (l, line) in self.table.iter().enumerate() {
+            let mut run_start = None;
             for (c, block) in line.iter().enumerate() {
-                if block != &self.table_snapshot[l][c] {
-                    changes.push((c, l))
+                match (block != &self.table_snapshot[l][c], run_start) {
+                    (true, None) => run_start = Some(c),
+                    (false, Some(start)) => {
+                        changes.push((l, start, c));
+                        run_start = None;
+                    }
+                    _ => {}
                 }
             }
+
+            if let Some(start) = run_start {
+                changes.push((l, start, line.len()));
+            }
         }
 
         changes
     }
 
-    pub fn draw_map(&mut self, stdout: &mut Stdout) -> Result<(), std::io::Error> {
-        for (c, l) in self.detect_changes() {
-            let block = self.table[l][c].clone();
-            stdout.draw((c as u16, l as u16), &block)?;
-            self.table_snapshot[l][c] = block;
+    /// Send what changed since the last call to `renderer`. Adjacent
+    /// changes on a line go out together, one piece per style.
+    pub fn draw_map(&mut self, renderer: &mut impl Renderer) -> Result<(), std::io::Error> {
+        if self.stale {
+            renderer.clear()?;
+            self.stale = false;
+        }
+
+        for (l, start, end) in self.detect_changes() {
+            let mut column = start;
+            let mut piece = String::new();
+            let mut piece_style = None;
+            for block in &self.table[l][start..end] {
+                let (character, style) = match block {
+                    Block::Empty => (' ', None),
+                    Block::Acquired { style, character } => (*character, *style),
+                };
+                if style != piece_style && !piece.is_empty() {
+                    renderer.draw_styled((column as u16, l as u16), &piece, piece_style)?;
+                    column += piece.chars().count();
+                    piece.clear();
+                }
+                piece_style = style;
+                piece.push(character);
+            }
+            renderer.draw_styled((column as u16, l as u16), &piece, piece_style)?;
+            self.table_snapshot[l][start..end].clone_from_slice(&self.table[l][start..end]);
         }
 
-        stdout.flush()?;
-        Ok(())
+        renderer.flush()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crossterm::style::Stylize;
+    use crossterm::style::{ContentStyle, Stylize};
+
+    use crate::renderer::{CaptureRenderer, Renderer};
 
     #[test]
     fn test_name() {
         let styled_text = "Hello World".red().on_white();
-        println!("{}", styled_text.to_string());
+        println!("{}", styled_text);
+    }
+
+    #[test]
+    fn changes_are_merged_into_runs() {
+        let mut canvas = super::Canvas::new(10, 3);
+        canvas.draw_styled_line((2, 1), "abc", None);
+        canvas.draw_styled_char((9, 1), 'x', None);
+        assert_eq!(canvas.detect_changes(), vec![(1, 2, 5), (1, 9, 10)]);
+    }
+
+    #[test]
+    fn only_changes_are_rendered() {
+        let mut canvas = super::Canvas::new(10, 2);
+        let mut screen = CaptureRenderer::new(10, 2);
+        canvas.draw_styled_line((1, 0), "ab", None);
+        canvas.draw_styled_char((3, 0), 'c', ContentStyle::new().red());
+        canvas.draw_map(&mut screen).unwrap();
+        assert_eq!(screen.line(0), " abc      ");
+
+        screen.clear().unwrap();
+        canvas.draw_styled_char((5, 1), 'd', None);
+        canvas.draw_map(&mut screen).unwrap();
+        assert_eq!(screen.line(0).trim(), "");
+        assert_eq!(screen.line(1), "     d    ");
+    }
+
+    #[test]
+    fn draws_into_any_writer() {
+        let mut canvas = super::Canvas::new(10, 2);
+        canvas.draw_styled_line((1, 0), "abc", None);
+        let mut buffer: Vec<u8> = Vec::new();
+        canvas.draw_map(&mut buffer).unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains("abc"));
+    }
+
+    #[test]
+    fn resize_clips_drawing() {
+        let mut canvas = super::Canvas::new(20, 10);
+        canvas.resize(5, 3);
+        canvas.draw_styled_line((3, 2), "too long", None);
+        canvas.draw_styled_char((10, 8), 'x', None);
+        assert_eq!(canvas.size(), (5, 3));
     }
 }
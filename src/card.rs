@@ -0,0 +1,185 @@
+//! A small summary card of the game shown after the goodbye screen, to save
+//! to a file or, with the `clipboard` feature, copy for sharing:
+//!
+//! ```text
+//! ╔══════════════════════════════╗
+//! ║ RiverRaid Rust    2024-05-17 ║
+//! ║ Score                   1250 ║
+//! ║ Distance                2300 ║
+//! ║ Seed                    1234 ║
+//! ╚══════════════════════════════╝
+//! ```
+
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{canvas::Canvas, drawable::Drawable, locale::Locale};
+
+pub const CARD_FILE: &str = "riverraid-card.txt";
+
+/// Columns inside the frame
+const WIDTH: usize = 30;
+
+pub struct SummaryCard {
+    pub score: u32,
+    /// Lines of river flown
+    pub traveled: u32,
+    pub seed: u64,
+    /// Day the game was played, `YYYY-MM-DD`
+    pub date: String,
+    /// An input macro was played, see [`crate::input_macro`]
+    pub assisted: bool,
+    /// How the score and the distance are written
+    pub locale: Locale,
+}
+
+impl SummaryCard {
+    /// The card, frame included, one string per line
+    pub fn framed(&self) -> Vec<String> {
+        let row = |label: &str, value: String| {
+            format!("{label}{value:>width$}", width = WIDTH - 2 - label.len())
+        };
+        let mut rows = vec![
+            row("RiverRaid Rust", self.date.clone()),
+            row("Score", self.locale.number(self.score)),
+            row("Distance", self.locale.number(self.traveled)),
+            row("Seed", self.seed.to_string()),
+        ];
+        if self.assisted {
+            rows.push(row("Assisted", "yes".to_string()));
+        }
+
+        let mut lines = vec![format!("╔{}╗", "═".repeat(WIDTH))];
+        lines.extend(rows.iter().map(|row| format!("║ {row} ║")));
+        lines.push(format!("╚{}╝", "═".repeat(WIDTH)));
+        lines
+    }
+
+    pub fn to_text(&self) -> String {
+        self.framed().join("\n") + "\n"
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    /// Put the card on the clipboard of the terminal, with an OSC 52 escape
+    /// sequence. Terminals that do not support it ignore it.
+    #[cfg(feature = "clipboard")]
+    pub fn copy(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        write!(out, "\x1b]52;c;{}\x07", base64(self.to_text().as_bytes()))?;
+        out.flush()
+    }
+} // end of SummaryCard implementation.
+
+/// Centered on the screen, framed in the popup style.
+impl Drawable for SummaryCard {
+    fn draw(&self, sc: &mut Canvas) {
+        let (maxc, maxl) = sc.size();
+        let lines = self.framed();
+        let c = (maxc / 2).saturating_sub(WIDTH as u16 / 2 + 1);
+        let l = (maxl / 2).saturating_sub(lines.len() as u16 / 2);
+        let style = sc.theme().popup;
+        for (index, line) in lines.into_iter().enumerate() {
+            sc.draw_styled_line((c, l + index as u16), line, style);
+        }
+    }
+}
+
+/// Today as `YYYY-MM-DD`, in UTC.
+pub fn today() -> String {
+    let (year, month, day) = civil_today();
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Month and day of today, in UTC.
+pub fn month_day() -> (u32, u32) {
+    let (_, month, day) = civil_today();
+    (month, day)
+}
+
+fn civil_today() -> (i64, u32, u32) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    civil_date((seconds / 86_400) as i64)
+}
+
+/// Year, month and day of the `days`th day since 1970-01-01.
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    // days since 0000-03-01, so leap days end the year
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month as u32, day as u32)
+}
+
+#[cfg(feature = "clipboard")]
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .fold(0u32, |group, byte| group << 8 | u32::from(*byte))
+            << (8 * (3 - chunk.len()));
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{civil_date, SummaryCard};
+    use crate::locale::Locale;
+
+    #[test]
+    fn card_lines_line_up() {
+        let card = SummaryCard {
+            score: 1250,
+            traveled: 2300,
+            seed: 1234,
+            date: "2024-05-17".to_string(),
+            assisted: true,
+            locale: Locale::from_tag("en_GB"),
+        };
+        let lines = card.framed();
+        assert_eq!(lines[1], "║ RiverRaid Rust    2024-05-17 ║");
+        assert_eq!(lines[2], "║ Score                  1,250 ║");
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() == lines[0].chars().count()));
+
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(19_723), (2024, 1, 1));
+        assert_eq!(civil_date(19_782), (2024, 2, 29));
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn base64_pads() {
+        assert_eq!(super::base64(b"Ma"), "TWE=");
+        assert_eq!(super::base64(b"Man"), "TWFu");
+        assert_eq!(super::base64(b"M"), "TQ==");
+    }
+}
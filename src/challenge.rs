@@ -0,0 +1,208 @@
+//! Micro-challenges.
+//!
+//! Every [`OFFER_EVERY`] of play the run offers a short goal with a time
+//! limit, announced in a popup: shoot down a few enemies, pick something up,
+//! or keep the trigger still for a while. Meeting it in time pays a bonus,
+//! missing it costs nothing. The goals come round in a fixed order, so two
+//! runs of the same seed are offered the same ones.
+
+use std::{fmt, time::Duration};
+
+use crate::combat_log::CombatEvent;
+
+/// Play between the end of a challenge and the offer of the next one
+pub const OFFER_EVERY: Duration = Duration::from_secs(45);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Goal {
+    /// Shoot down (or bomb) this many enemies
+    Kills(u16),
+    /// Pick up this many supplies: fuel depots refuelled from, shield points
+    /// charged at pads or bomb crates
+    Pickups(u16),
+    /// Fire neither bullets nor missiles until time runs out
+    HoldFire,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Challenge {
+    pub goal: Goal,
+    /// Time given
+    pub time: Duration,
+    /// Score for meeting it
+    pub bonus: u16,
+}
+
+const CHALLENGES: [Challenge; 4] = [
+    Challenge {
+        goal: Goal::Kills(5),
+        time: Duration::from_secs(15),
+        bonus: 150,
+    },
+    Challenge {
+        goal: Goal::HoldFire,
+        time: Duration::from_secs(20),
+        bonus: 100,
+    },
+    Challenge {
+        goal: Goal::Pickups(2),
+        time: Duration::from_secs(25),
+        bonus: 100,
+    },
+    Challenge {
+        goal: Goal::Kills(10),
+        time: Duration::from_secs(30),
+        bonus: 300,
+    },
+];
+
+impl fmt::Display for Challenge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = self.time.as_secs();
+        match self.goal {
+            Goal::Kills(kills) => write!(f, "destroy {kills} enemies in {seconds}s"),
+            Goal::Pickups(pickups) => write!(f, "pick up {pickups} supplies in {seconds}s"),
+            Goal::HoldFire => write!(f, "don't shoot for {seconds}s"),
+        }
+    }
+}
+
+/// What became of a challenge
+#[derive(PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Offered(Challenge),
+    Met(Challenge),
+    Missed(Challenge),
+}
+
+/// The challenge on and how far along it is
+#[derive(Clone, Copy)]
+pub struct Progress {
+    pub challenge: Challenge,
+    /// Kills or pickups so far
+    pub count: u16,
+    /// Play time the challenge ends at
+    pub deadline: Duration,
+}
+
+#[derive(Default)]
+pub struct Challenges {
+    /// Challenges offered so far
+    offered: usize,
+    /// Play time the next challenge is offered at, counting from the start
+    /// of the run until the first one
+    next_offer: Option<Duration>,
+    current: Option<Progress>,
+}
+
+impl Challenges {
+    pub fn current(&self) -> Option<&Progress> {
+        self.current.as_ref()
+    }
+
+    /// Offer a challenge when one is due, or end the current one when its
+    /// time is up, at `play_time`.
+    pub fn tick(&mut self, play_time: Duration) -> Option<Outcome> {
+        match self.current {
+            Some(progress) if play_time >= progress.deadline => {
+                // holding fire only ever ends with the clock
+                let outcome = match progress.challenge.goal {
+                    Goal::HoldFire => Outcome::Met(progress.challenge),
+                    _ => Outcome::Missed(progress.challenge),
+                };
+                Some(self.end(outcome, play_time))
+            }
+            Some(_) => None,
+            None if play_time >= *self.next_offer.get_or_insert(OFFER_EVERY) => {
+                let challenge = CHALLENGES[self.offered % CHALLENGES.len()];
+                self.offered += 1;
+                self.current = Some(Progress {
+                    challenge,
+                    count: 0,
+                    deadline: play_time + challenge.time,
+                });
+                Some(Outcome::Offered(challenge))
+            }
+            None => None,
+        }
+    }
+
+    /// Count `event`, logged at `play_time`, towards the current challenge.
+    pub fn note(&mut self, event: &CombatEvent, play_time: Duration) -> Option<Outcome> {
+        let progress = self.current.as_mut()?;
+        let counted = match (progress.challenge.goal, event) {
+            (Goal::Kills(_), CombatEvent::Kill { .. }) => 1,
+            (Goal::Kills(_), CombatEvent::Bomb { kills }) => *kills as u16,
+            (Goal::Pickups(_), CombatEvent::Pickup(_)) => 1,
+            _ => return None,
+        };
+        progress.count = progress.count.saturating_add(counted);
+        match progress.challenge.goal {
+            Goal::Kills(goal) | Goal::Pickups(goal) if progress.count >= goal => {
+                let outcome = Outcome::Met(progress.challenge);
+                Some(self.end(outcome, play_time))
+            }
+            _ => None,
+        }
+    }
+
+    /// The player fired at `play_time`.
+    pub fn shot(&mut self, play_time: Duration) -> Option<Outcome> {
+        let progress = self.current?;
+        (progress.challenge.goal == Goal::HoldFire)
+            .then(|| self.end(Outcome::Missed(progress.challenge), play_time))
+    }
+
+    fn end(&mut self, outcome: Outcome, play_time: Duration) -> Outcome {
+        self.current = None;
+        self.next_offer = Some(play_time + OFFER_EVERY);
+        outcome
+    }
+} // end of Challenges implementation.
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Challenges, Goal, Outcome, OFFER_EVERY};
+    use crate::combat_log::CombatEvent;
+
+    #[test]
+    fn challenges_are_offered_met_and_missed() {
+        let mut challenges = Challenges::default();
+        let kill = CombatEvent::Kill {
+            kind: "Ship",
+            reward: 10,
+        };
+        let at = |seconds| OFFER_EVERY + Duration::from_secs(seconds);
+
+        assert_eq!(challenges.tick(Duration::ZERO), None);
+        let Some(Outcome::Offered(first)) = challenges.tick(at(0)) else {
+            panic!("no challenge offered");
+        };
+        assert_eq!(first.goal, Goal::Kills(5));
+        assert_eq!(first.to_string(), "destroy 5 enemies in 15s");
+        assert_eq!(
+            challenges.note(&CombatEvent::Bomb { kills: 3 }, at(1)),
+            None
+        );
+        assert_eq!(challenges.note(&kill, at(2)), None);
+        assert_eq!(challenges.note(&kill, at(3)), Some(Outcome::Met(first)));
+        assert!(challenges.current().is_none());
+
+        // the next one waits its turn, then a shot ends it
+        assert_eq!(challenges.tick(at(4)), None);
+        let Some(Outcome::Offered(second)) = challenges.tick(at(48)) else {
+            panic!("no challenge offered");
+        };
+        assert_eq!(second.goal, Goal::HoldFire);
+        assert_eq!(challenges.shot(at(50)), Some(Outcome::Missed(second)));
+
+        // running out of time
+        let Some(Outcome::Offered(third)) = challenges.tick(at(95)) else {
+            panic!("no challenge offered");
+        };
+        assert_eq!(challenges.shot(at(96)), None);
+        assert_eq!(challenges.tick(at(120)), Some(Outcome::Missed(third)));
+    }
+}
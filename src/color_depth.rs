@@ -0,0 +1,193 @@
+//! How many colors the terminal can show, and fitting colors to that.
+//!
+//! Themes may use any [`Color`]; before drawing, [`crate::theme::Theme::fitted`]
+//! swaps every color the terminal can not show for the nearest one it can.
+
+use crossterm::style::Color;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorDepth {
+    /// No colors at all (`NO_COLOR`, dumb terminals): greys only
+    Mono,
+    /// The 16 named colors
+    Ansi16,
+    /// The 256 color palette
+    Ansi256,
+    /// Any RGB color
+    TrueColor,
+}
+
+/// The 16 named colors with their usual xterm values
+const NAMED: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (128, 0, 0)),
+    (Color::DarkGreen, (0, 128, 0)),
+    (Color::DarkYellow, (128, 128, 0)),
+    (Color::DarkBlue, (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan, (0, 128, 128)),
+    (Color::Grey, (192, 192, 192)),
+    (Color::DarkGrey, (128, 128, 128)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (0, 0, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Levels of each channel in the 6x6x6 cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// What the environment says the terminal supports.
+    pub fn detect() -> ColorDepth {
+        ColorDepth::from_env(|name| std::env::var(name).ok())
+    }
+
+    /// What the variables read by `var` say the terminal supports.
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> ColorDepth {
+        if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorDepth::Mono;
+        }
+        if let Some("truecolor" | "24bit") = var("COLORTERM").as_deref() {
+            return ColorDepth::TrueColor;
+        }
+        match var("TERM").as_deref() {
+            Some(term) if term.ends_with("direct") => ColorDepth::TrueColor,
+            Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+            Some("dumb") => ColorDepth::Mono,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorDepth::Mono => "no colors",
+            ColorDepth::Ansi16 => "16 colors",
+            ColorDepth::Ansi256 => "256 colors",
+            ColorDepth::TrueColor => "true color",
+        }
+    }
+
+    /// `color`, or the nearest one the terminal can show.
+    pub fn fit(&self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::TrueColor, _) | (_, Color::Reset) => color,
+            (ColorDepth::Ansi256, Color::Rgb { .. }) => nearest_ansi256(to_rgb(color)),
+            (ColorDepth::Ansi256, _) => color,
+            (ColorDepth::Ansi16, _) => nearest(to_rgb(color), NAMED.iter()),
+            (ColorDepth::Mono, _) => {
+                let greys = [0, 7, 8, 15].map(|index| &NAMED[index]);
+                nearest(to_rgb(color), greys.into_iter())
+            }
+        }
+    }
+} // end of ColorDepth implementation.
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let channel = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2) as u32;
+    // the eye is most sensitive to green and least to blue
+    2 * channel(r1, r2) + 4 * channel(g1, g2) + 3 * channel(b1, b2)
+}
+
+/// The color of `palette` closest to `rgb`
+fn nearest<'a>(
+    rgb: (u8, u8, u8),
+    palette: impl Iterator<Item = &'a (Color, (u8, u8, u8))>,
+) -> Color {
+    palette
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// The entry of the 256 color palette closest to `rgb`, from the color cube
+/// or the grey ramp.
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> Color {
+    let level = |channel: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|index| CUBE_LEVELS[*index].abs_diff(channel))
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+
+    let average = ((u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3) as u8;
+    let step = (average.saturating_sub(3) / 10).min(23);
+    let grey = 8 + 10 * step;
+
+    if distance(rgb, (grey, grey, grey)) < distance(rgb, cube) {
+        Color::AnsiValue(232 + step)
+    } else {
+        Color::AnsiValue(16 + 36 * r as u8 + 6 * g as u8 + b as u8)
+    }
+}
+
+/// RGB value of any color, as xterm shows it
+fn to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::AnsiValue(index @ 0..=15) => NAMED[index as usize].1,
+        Color::AnsiValue(index @ 16..=231) => {
+            let index = index - 16;
+            let level = |value: u8| CUBE_LEVELS[value as usize];
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        Color::AnsiValue(index) => {
+            let grey = 8 + 10 * (index - 232);
+            (grey, grey, grey)
+        }
+        named => NAMED
+            .iter()
+            .find(|(color, _)| *color == named)
+            .map_or((0, 0, 0), |(_, rgb)| *rgb),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+
+    use super::ColorDepth;
+
+    #[test]
+    fn colors_fit_the_terminal() {
+        let env = |vars: &'static [(&str, &str)]| {
+            ColorDepth::from_env(move |name| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(
+            env(&[("COLORTERM", "truecolor"), ("TERM", "xterm")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(env(&[("TERM", "xterm-256color")]), ColorDepth::Ansi256);
+        assert_eq!(
+            env(&[("TERM", "xterm-256color"), ("NO_COLOR", "1")]),
+            ColorDepth::Mono
+        );
+        assert_eq!(env(&[]), ColorDepth::Ansi16);
+
+        let navy = Color::Rgb {
+            r: 20,
+            g: 30,
+            b: 140,
+        };
+        assert_eq!(ColorDepth::TrueColor.fit(navy), navy);
+        assert_eq!(ColorDepth::Ansi256.fit(navy), Color::AnsiValue(18));
+        assert_eq!(ColorDepth::Ansi16.fit(navy), Color::DarkBlue);
+        assert_eq!(
+            ColorDepth::Ansi256.fit(Color::Rgb {
+                r: 100,
+                g: 100,
+                b: 100
+            }),
+            Color::AnsiValue(241)
+        );
+        assert_eq!(ColorDepth::Mono.fit(Color::Blue), Color::DarkGrey);
+        assert_eq!(ColorDepth::Ansi16.fit(Color::AnsiValue(196)), Color::Red);
+    }
+}
@@ -0,0 +1,177 @@
+//! A readable log of what happened in a run, written to its own file in
+//! [`COMBAT_LOG_DIR`] when the run ends (with `combat_log = on` in the
+//! settings):
+//!
+//! ```text
+//! 00:12.4  kill     Helicopter +30
+//! 00:15.0  pickup   fuel depot
+//! 00:31.8  hit      shield took a hit, 2 left
+//! 00:40.2  section  3, Forest
+//! 00:44.9  stage    Patrol, Forest
+//! 00:52.6  death    Enemy
+//! ```
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{entities::DeathCause, locale::Locale};
+
+pub const COMBAT_LOG_DIR: &str = "combat-logs";
+
+/// Something worth a line in the log
+pub enum CombatEvent {
+    /// An enemy of this kind shot down by a shell or a missile, for a reward.
+    /// Bombed enemies are counted by [`CombatEvent::Bomb`], and ramming one
+    /// is a [`CombatEvent::Hit`].
+    Kill {
+        kind: &'static str,
+        reward: u16,
+    },
+    /// A fuel depot shot down
+    DepotShot,
+    /// A fuel depot, a shield point or a bomb picked up
+    Pickup(&'static str),
+    /// A bomb went off, wrecking this many enemies
+    Bomb {
+        kills: usize,
+    },
+    /// The shield took a hit, this many points are left
+    Hit {
+        shield_left: u8,
+    },
+    /// A bridge down, the river goes on into this section and biome
+    Section {
+        section: u16,
+        biome: String,
+    },
+    /// A new stage started, in this biome
+    Stage {
+        name: String,
+        biome: String,
+    },
+    /// Into a tunnel
+    Warp,
+    Death(DeathCause),
+}
+
+impl CombatEvent {
+    fn label(&self) -> &'static str {
+        match self {
+            CombatEvent::Kill { .. } | CombatEvent::DepotShot => "kill",
+            CombatEvent::Pickup(_) => "pickup",
+            CombatEvent::Bomb { .. } => "bomb",
+            CombatEvent::Hit { .. } => "hit",
+            CombatEvent::Section { .. } | CombatEvent::Warp => "section",
+            CombatEvent::Stage { .. } => "stage",
+            CombatEvent::Death(_) => "death",
+        }
+    }
+} // end of CombatEvent implementation.
+
+impl fmt::Display for CombatEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<8} ", self.label())?;
+        match self {
+            CombatEvent::Kill { kind, reward } => write!(f, "{kind} +{reward}"),
+            CombatEvent::DepotShot => write!(f, "fuel depot"),
+            CombatEvent::Pickup(what) => write!(f, "{what}"),
+            CombatEvent::Bomb { kills } => write!(f, "{kills} enemies wrecked"),
+            CombatEvent::Hit { shield_left } => {
+                write!(f, "shield took a hit, {shield_left} left")
+            }
+            CombatEvent::Section { section, biome } => write!(f, "{section}, {biome}"),
+            CombatEvent::Stage { name, biome } => write!(f, "{name}, {biome}"),
+            CombatEvent::Warp => write!(f, "into a tunnel"),
+            CombatEvent::Death(cause) => write!(f, "{cause:?}"),
+        }
+    }
+}
+
+pub struct CombatLog {
+    /// Keep lines at all, see [`crate::settings::Settings::combat_log`]
+    pub enabled: bool,
+    /// How the times of lines are written
+    pub locale: Locale,
+    /// Seconds since the epoch the run started at, names the file
+    started: u64,
+    lines: Vec<String>,
+}
+
+impl CombatLog {
+    pub fn new() -> CombatLog {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        CombatLog {
+            enabled: false,
+            locale: Locale::default(),
+            started,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Log `event`, `at` this much play time into the run.
+    pub fn note(&mut self, at: Duration, event: CombatEvent) {
+        if !self.enabled {
+            return;
+        }
+        self.lines
+            .push(format!("{}  {event}", self.locale.timestamp(at)));
+    }
+
+    /// Where the log of this run goes, in `dir`
+    pub fn path(&self, dir: impl AsRef<Path>) -> PathBuf {
+        dir.as_ref().join(format!("run-{}.log", self.started))
+    }
+
+    /// Write the run so far to its file in `dir`, headed by `header`.
+    /// Nothing is written if logging is off.
+    pub fn save(&self, dir: impl AsRef<Path>, header: &str) -> std::io::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        fs::create_dir_all(&dir)?;
+        let mut text = format!("{header}\n");
+        for line in &self.lines {
+            text += line;
+            text.push('\n');
+        }
+        fs::write(self.path(dir), text)
+    }
+} // end of CombatLog implementation.
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{CombatEvent, CombatLog};
+
+    #[test]
+    fn lines_are_timestamped() {
+        let mut log = CombatLog::new();
+        log.note(Duration::ZERO, CombatEvent::Warp);
+        assert!(log.lines.is_empty());
+
+        log.enabled = true;
+        log.note(
+            Duration::from_millis(72_450),
+            CombatEvent::Kill {
+                kind: "Helicopter",
+                reward: 30,
+            },
+        );
+        log.note(Duration::from_secs(80), CombatEvent::Hit { shield_left: 2 });
+        assert_eq!(log.lines[0], "01:12.4  kill     Helicopter +30");
+        assert_eq!(log.lines[1], "01:20.0  hit      shield took a hit, 2 left");
+
+        let stage = CombatEvent::Stage {
+            name: "Patrol".to_string(),
+            biome: "Forest".to_string(),
+        };
+        log.note(Duration::from_secs(81), stage);
+        assert_eq!(log.lines[2], "01:21.0  stage    Patrol, Forest");
+    }
+}
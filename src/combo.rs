@@ -0,0 +1,75 @@
+//! Kill streaks.
+//!
+//! Every few kills in a row raise a multiplier on the score of the next
+//! ones. A shot that misses ends the streak, and so does going
+//! [`DECAY_TICKS`] without a kill.
+
+/// Ticks a streak lasts without another kill
+pub const DECAY_TICKS: u16 = 60;
+
+/// Kills in a row per step of the multiplier
+const KILLS_PER_STEP: u16 = 3;
+
+const MAX_MULTIPLIER: u16 = 5;
+
+#[derive(Default)]
+pub struct Combo {
+    /// Kills in the current streak
+    kills: u16,
+    /// Ticks left before the streak runs out
+    ticks_left: u16,
+}
+
+impl Combo {
+    /// Score factor of the next kill
+    pub fn multiplier(&self) -> u16 {
+        (1 + self.kills / KILLS_PER_STEP).min(MAX_MULTIPLIER)
+    }
+
+    pub fn ticks_left(&self) -> u16 {
+        self.ticks_left
+    }
+
+    /// Count a kill worth `reward` into the streak. Returns what it scores.
+    pub fn kill(&mut self, reward: u16) -> u16 {
+        self.kills += 1;
+        self.ticks_left = DECAY_TICKS;
+        reward * self.multiplier()
+    }
+
+    /// A shot hit nothing: the streak is over.
+    pub fn miss(&mut self) {
+        *self = Combo::default();
+    }
+
+    /// A tick went by without a kill.
+    pub fn tick(&mut self) {
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+        if self.ticks_left == 0 {
+            self.kills = 0;
+        }
+    }
+} // end of Combo implementation.
+
+#[cfg(test)]
+mod tests {
+    use super::{Combo, DECAY_TICKS};
+
+    #[test]
+    fn streaks_multiply_and_run_out() {
+        let mut combo = Combo::default();
+        let scores: Vec<u16> = (0..7).map(|_| combo.kill(10)).collect();
+        assert_eq!(scores, [10, 10, 20, 20, 20, 30, 30]);
+
+        for _ in 0..DECAY_TICKS {
+            combo.tick();
+        }
+        assert_eq!(combo.multiplier(), 1);
+
+        combo.kill(10);
+        combo.kill(10);
+        combo.kill(10);
+        combo.miss();
+        assert_eq!(combo.kill(10), 10);
+    }
+}
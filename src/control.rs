@@ -0,0 +1,164 @@
+//! Control interface for external tooling, overlays and automated demos,
+//! opened with `--control <path>`. The path is usually a named pipe
+//! (`mkfifo control`), or `/dev/fd/<n>` to read an inherited descriptor;
+//! stdin is taken by the keyboard.
+//!
+//! Each line is a JSON command:
+//!
+//! ```json
+//! {"command": "spawn", "kind": "helicopter"}
+//! {"command": "set_chance", "kind": "fuel", "chance": 5}
+//! {"command": "popup", "text": "Welcome to the demo"}
+//! {"command": "pause"}
+//! {"command": "screenshot"}
+//! ```
+//!
+//! Commands go through the same queue as the ones of a script (see
+//! `src/scripting.rs`): entities are sent down the river like a stage
+//! wave, chances are per mille and override everything else, `pause`
+//! toggles the pause menu and `screenshot` saves the screen as text in
+//! [`SCREENSHOT_DIR`]. Lines that are not a known command are skipped, with
+//! a popup saying so.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader},
+    path::Path,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use serde::Deserialize;
+
+use crate::world::SpawnKind;
+
+/// Where `screenshot` saves the screen, a file per shot
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Commands read ahead of the game; past this, reading waits for the game
+/// to catch up
+const PENDING: usize = 64;
+
+/// Most of an unknown line shown in its popup
+const UNKNOWN_SHOWN: usize = 40;
+
+/// What a script or a tool asked the world to do
+#[derive(Deserialize, PartialEq, Eq, Debug)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    /// Send an entity down the river
+    Spawn { kind: SpawnKind },
+    /// Override the spawn chance of `kind`, per mille
+    SetChance { kind: SpawnKind, chance: u16 },
+    /// Show a line of text
+    Popup { text: String },
+    /// Open or close the pause menu
+    Pause,
+    /// Save the screen as text
+    Screenshot,
+}
+
+impl Command {
+    /// The command on a line of JSON, if it is one.
+    fn parse(line: &str) -> Option<Command> {
+        serde_json::from_str(line).ok()
+    }
+} // end of Command implementation.
+
+/// Open the control interface at `path`. A named pipe is opened for writing
+/// too: opening it does not wait for a tool, and it stays open while tools
+/// come and go during a run.
+pub fn open(path: impl AsRef<Path>) -> io::Result<File> {
+    let path = path.as_ref();
+    OpenOptions::new()
+        .read(true)
+        .write(is_pipe(path))
+        .open(path)
+}
+
+/// Start reading commands from `file`, see [`open`]. The receiver gets them
+/// as they come, and a popup for each line that is not one.
+pub fn listen(file: File) -> Receiver<Command> {
+    let (sender, receiver) = mpsc::sync_channel(PENDING);
+    thread::spawn(move || {
+        for line in BufReader::new(file).lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = Command::parse(&line).unwrap_or_else(|| {
+                let shown: String = line.chars().take(UNKNOWN_SHOWN).collect();
+                Command::Popup {
+                    text: format!("Unknown control command: {shown}"),
+                }
+            });
+            if sender.send(command).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+#[cfg(unix)]
+fn is_pipe(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_pipe(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{listen, open, Command};
+    use crate::world::SpawnKind;
+
+    #[test]
+    fn commands_are_parsed_from_json_lines() {
+        assert_eq!(
+            Command::parse(r#"{"command": "spawn", "kind": "helicopter"}"#),
+            Some(Command::Spawn {
+                kind: SpawnKind::Helicopter
+            })
+        );
+        assert_eq!(
+            Command::parse(r#"{"command": "set_chance", "kind": "fuel", "chance": 5}"#),
+            Some(Command::SetChance {
+                kind: SpawnKind::Fuel,
+                chance: 5
+            })
+        );
+        assert_eq!(
+            Command::parse(r#"{"command": "pause"}"#),
+            Some(Command::Pause)
+        );
+        assert_eq!(
+            Command::parse(r#"{"command": "spawn", "kind": "dragon"}"#),
+            None
+        );
+        assert_eq!(Command::parse("screenshot"), None);
+    }
+
+    #[test]
+    fn unknown_lines_are_reported() {
+        let path = std::env::temp_dir().join(format!("control-{}", std::process::id()));
+        std::fs::write(&path, "{\"command\": \"pause\"}\n\nfly\n").unwrap();
+        let commands: Vec<_> = listen(open(&path).unwrap()).iter().collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            commands,
+            [
+                Command::Pause,
+                Command::Popup {
+                    text: "Unknown control command: fly".to_string()
+                }
+            ]
+        );
+    }
+}
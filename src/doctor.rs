@@ -0,0 +1,206 @@
+//! Self-check of the terminal and the config files.
+//!
+//! `--doctor` prints the full report; the quick, silent part of it runs on
+//! every start and its problems are listed on the welcome screen.
+
+use std::{
+    io::{self, IsTerminal, Stdout, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crossterm::{
+    cursor::{position, MoveToColumn},
+    style::Print,
+    terminal::{size, Clear, ClearType},
+    ExecutableCommand,
+};
+
+use crate::{
+    biome::{Biome, BIOMES_FILE},
+    color_depth::ColorDepth,
+    keymap::{KeyMap, KEYS_FILE},
+    pack::{ContentPack, PACKS_FILE},
+    settings::{Settings, SETTINGS_FILE},
+    stage::{Stage, STAGES_FILE},
+    theme::Theme,
+};
+
+/// Below this the river gets cramped and the banners are cut
+const COMFORTABLE_SIZE: (u16, u16) = (80, 24);
+
+/// Every glyph the game draws, each should take a single column
+const GLYPHS: [char; 39] = [
+    '▲', '△', '╱', '█', '╲', '▒', '☠', '⇋', '✢', '✣', '◄', '■', '►', '☢', '❤', '❂', '═', '░', '◎',
+    '⚡', '⇈', '↟', '⁎', '│', '╎', '↓', '♣', '♠', '▴', '✸', '⇡', '†', '✻', '✗', '◆', '◊', '♧', '⌂',
+    '┅',
+];
+
+pub struct Finding {
+    pub ok: bool,
+    pub check: &'static str,
+    pub detail: String,
+}
+
+impl Finding {
+    fn new(ok: bool, check: &'static str, detail: impl Into<String>) -> Finding {
+        Finding {
+            ok,
+            check,
+            detail: detail.into(),
+        }
+    }
+} // end of Finding implementation.
+
+fn terminal_size() -> Finding {
+    match size() {
+        Ok((c, l)) => Finding::new(
+            c >= COMFORTABLE_SIZE.0 && l >= COMFORTABLE_SIZE.1,
+            "Terminal size",
+            format!(
+                "{c}x{l}, {}x{} or more plays best",
+                COMFORTABLE_SIZE.0, COMFORTABLE_SIZE.1
+            ),
+        ),
+        Err(error) => Finding::new(false, "Terminal size", format!("unknown: {error}")),
+    }
+}
+
+fn color_depth() -> Finding {
+    match ColorDepth::detect() {
+        ColorDepth::Mono => Finding::new(false, "Colors", "no colors, try the monochrome theme"),
+        depth => {
+            let theme = Theme::for_depth(depth).name;
+            Finding::new(
+                true,
+                "Colors",
+                format!("{}, {theme} theme suits", depth.name()),
+            )
+        }
+    }
+}
+
+fn locale() -> Finding {
+    // the first of these that is set wins
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default();
+    let utf8 = locale.to_lowercase().replace('-', "").contains("utf8");
+    let detail = if utf8 {
+        format!("locale {locale}")
+    } else {
+        format!("locale {locale:?}, the glyphs need a UTF-8 one")
+    };
+    Finding::new(utf8, "Unicode", detail)
+}
+
+/// Whether `path` is understood in full; `None` if there is no such file.
+fn config(
+    path: &'static str,
+    check: fn(&'static str) -> io::Result<Vec<String>>,
+) -> Option<Finding> {
+    if !Path::new(path).exists() {
+        return None;
+    }
+    Some(match check(path) {
+        Ok(ignored) if ignored.is_empty() => {
+            Finding::new(true, "Config", format!("{path} is fine"))
+        }
+        Ok(ignored) => Finding::new(
+            false,
+            "Config",
+            format!("{path} ignores: {}", ignored.join(", ")),
+        ),
+        Err(error) => Finding::new(false, "Config", format!("{path} can not be read: {error}")),
+    })
+}
+
+/// Draw every glyph and see how far the cursor moves.
+fn glyph_widths(stdout: &mut Stdout) -> Finding {
+    if !stdout.is_terminal() {
+        return Finding::new(false, "Glyphs", "not a terminal, can not measure");
+    }
+
+    let mut wide = Vec::new();
+    for glyph in GLYPHS {
+        let measured = (|| {
+            stdout.execute(MoveToColumn(0))?.execute(Print(glyph))?;
+            let (column, _) = position()?;
+            stdout
+                .execute(MoveToColumn(0))?
+                .execute(Clear(ClearType::CurrentLine))?;
+            Ok::<u16, io::Error>(column)
+        })();
+        match measured {
+            Ok(1) => {}
+            Ok(_) => wide.push(glyph.to_string()),
+            Err(error) => {
+                return Finding::new(false, "Glyphs", format!("can not measure: {error}"))
+            }
+        }
+    }
+
+    if wide.is_empty() {
+        Finding::new(true, "Glyphs", "all take a single column")
+    } else {
+        Finding::new(
+            false,
+            "Glyphs",
+            format!("{} are not one column wide", wide.join(" ")),
+        )
+    }
+}
+
+fn audio() -> Finding {
+    Finding::new(
+        true,
+        "Audio",
+        "no audio device used, feedback rings the bell or flashes",
+    )
+}
+
+/// `curl` is needed as soon as a URL is set; `None` if none is.
+fn network(settings: &Settings) -> Option<Finding> {
+    settings.webhook.as_ref().or(settings.events_url.as_ref())?;
+    let curl = Command::new("curl")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    Some(match curl {
+        Ok(status) if status.success() => Finding::new(true, "Network", "curl is available"),
+        _ => Finding::new(
+            false,
+            "Network",
+            "curl is missing, webhook and events are off",
+        ),
+    })
+}
+
+/// The checks cheap and quiet enough to run on every start.
+pub fn quick() -> Vec<Finding> {
+    let mut findings = vec![terminal_size(), color_depth(), locale()];
+    findings.extend(config(SETTINGS_FILE, Settings::check));
+    findings.extend(config(KEYS_FILE, KeyMap::check));
+    findings.extend(config(BIOMES_FILE, Biome::check));
+    findings.extend(config(PACKS_FILE, ContentPack::check));
+    findings.extend(config(STAGES_FILE, Stage::check));
+    findings
+}
+
+/// Run every check and print the report.
+pub fn report(stdout: &mut Stdout) -> io::Result<()> {
+    let settings = Settings::load(SETTINGS_FILE).unwrap_or_default();
+
+    let mut findings = quick();
+    findings.push(glyph_widths(stdout));
+    findings.push(audio());
+    findings.extend(network(&settings));
+
+    for finding in &findings {
+        let status = if finding.ok { " ok " } else { "warn" };
+        writeln!(stdout, "[{status}] {}: {}", finding.check, finding.detail)?;
+    }
+    Ok(())
+}
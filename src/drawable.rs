@@ -1,22 +1,191 @@
-use crossterm::style::{ContentStyle, Stylize};
+use crossterm::style::ContentStyle;
 
 use crate::{
     canvas::Canvas,
-    entities::{Bullet, Enemy, EntityStatus, Fuel, Player},
+    entities::{
+        BombCrate, Bridge, Bullet, Enemy, EnemyBullet, EnemyKind, EntityStatus, Fuel, Location,
+        Obstacle, ObstacleKind, Player, RechargePad, Spark, SparkKind, Tunnel,
+    },
+    theme::Theme,
 };
 
 pub trait Drawable {
     fn draw(&self, sc: &mut Canvas);
 }
 
+/// One glyph of an [`Animation`]
+pub struct Frame {
+    pub glyph: char,
+    /// Picks the style from the active theme
+    pub style: fn(&Theme) -> ContentStyle,
+    /// Ticks the frame is shown for
+    pub ticks: u32,
+}
+
+/// Glyphs shown one after another, picked by a clock: the canvas clock
+/// (ticks of play, see [`Canvas::clock`]) for things that loop, or the age
+/// of a [`Spark`] for one-shot effects.
+pub struct Animation {
+    pub frames: &'static [Frame],
+    /// Start over after the last frame, instead of holding it
+    pub looping: bool,
+}
+
+/// Rotor of a helicopter
+const HELICOPTER: Animation = Animation {
+    frames: &[
+        Frame {
+            glyph: '✢',
+            style: |theme| theme.helicopter,
+            ticks: 2,
+        },
+        Frame {
+            glyph: '✣',
+            style: |theme| theme.helicopter,
+            ticks: 2,
+        },
+    ],
+    looping: true,
+};
+
+/// A fuel depot glinting now and then
+const FUEL: Animation = Animation {
+    frames: &[
+        Frame {
+            glyph: '❤',
+            style: |theme| theme.fuel,
+            ticks: 8,
+        },
+        Frame {
+            glyph: '❤',
+            style: |theme| theme.fuel_glint,
+            ticks: 2,
+        },
+    ],
+    looping: true,
+};
+
+/// Flash either side of the plane's nose as it fires
+const MUZZLE_FLASH: Animation = Animation {
+    frames: &[Frame {
+        glyph: '⁎',
+        style: |theme| theme.bullet,
+        ticks: 1,
+    }],
+    looping: false,
+};
+
+/// Cell a bullet just flew through, fading out on its last tick
+const TRAIL: Animation = Animation {
+    frames: &[
+        Frame {
+            glyph: '│',
+            style: |theme| theme.bullet,
+            ticks: 2,
+        },
+        Frame {
+            glyph: '╎',
+            style: |theme| theme.bullet_trail,
+            ticks: 1,
+        },
+    ],
+    looping: false,
+};
+
+/// The gun jammed
+const JAMMED: Animation = Animation {
+    frames: &[Frame {
+        glyph: '✗',
+        style: |theme| theme.enemy_bullet,
+        ticks: 1,
+    }],
+    looping: false,
+};
+
+/// Debris of an explosion, see [`Spark::burst`]
+const DEBRIS: Animation = Animation {
+    frames: &[
+        Frame {
+            glyph: '*',
+            style: |theme| theme.explosion,
+            ticks: 2,
+        },
+        Frame {
+            glyph: '·',
+            style: |theme| theme.explosion,
+            ticks: 1,
+        },
+    ],
+    looping: false,
+};
+
+/// Smoke an explosion leaves behind
+const SMOKE: Animation = Animation {
+    frames: &[
+        Frame {
+            glyph: '▓',
+            style: |theme| theme.smoke,
+            ticks: 3,
+        },
+        Frame {
+            glyph: '░',
+            style: |theme| theme.smoke,
+            ticks: 3,
+        },
+    ],
+    looping: false,
+};
+
+impl Animation {
+    /// The frame shown `tick` ticks in.
+    pub fn frame(&self, tick: u32) -> &Frame {
+        let total: u32 = self.frames.iter().map(|frame| frame.ticks).sum();
+        let mut tick = if self.looping {
+            tick % total.max(1)
+        } else {
+            tick
+        };
+        for frame in self.frames {
+            if tick < frame.ticks {
+                return frame;
+            }
+            tick -= frame.ticks;
+        }
+        &self.frames[self.frames.len() - 1]
+    }
+
+    /// Draw the frame shown `tick` ticks in at `location`.
+    pub fn draw(&self, sc: &mut Canvas, location: &Location, tick: u32) {
+        let frame = self.frame(tick);
+        let style = (frame.style)(sc.theme());
+        sc.draw_styled_char((location.c, location.l), frame.glyph, style);
+    }
+} // end of Animation implementation.
+
 impl Drawable for Enemy {
     fn draw(&self, sc: &mut Canvas) {
         match self.status {
-            EntityStatus::Alive => {
-                sc.draw_styled_char(self, '☠', ContentStyle::new().red().on_blue());
-            }
+            EntityStatus::Alive => match self.kind {
+                EnemyKind::Static => {
+                    sc.draw_styled_char(self, '☠', sc.theme().enemy);
+                }
+                EnemyKind::Strafer if self.is_dashing() => {
+                    sc.draw_styled_char(self, '⇋', sc.theme().enemy);
+                }
+                EnemyKind::Strafer => {
+                    sc.draw_styled_char(self, '⇋', sc.theme().enemy_on_bank);
+                }
+                EnemyKind::Ship => {
+                    if let Some(sprite) = self.sprite() {
+                        sprite.draw(sc, &self.location, sc.theme().enemy);
+                    }
+                }
+                EnemyKind::Helicopter => {
+                    HELICOPTER.draw(sc, &self.location, sc.clock());
+                }
+            },
             EntityStatus::DeadBody => {
-                sc.draw_styled(self, '☢'.red().on_blue());
+                sc.draw_styled_char(self, '☢', sc.theme().enemy);
             }
             EntityStatus::Dead => {}
         };
@@ -27,25 +196,138 @@ impl Drawable for Fuel {
     fn draw(&self, sc: &mut Canvas) {
         match self.status {
             EntityStatus::Alive => {
-                sc.draw_styled_char(self, '❤', ContentStyle::new().yellow().on_blue());
+                FUEL.draw(sc, &self.location, sc.clock());
             }
             EntityStatus::DeadBody => {
-                sc.draw_styled(self, '❂'.yellow().on_blue());
+                sc.draw_styled_char(self, '❂', sc.theme().fuel);
             }
             EntityStatus::Dead => {}
         };
     }
 }
 
+impl Drawable for Bridge {
+    fn draw(&self, sc: &mut Canvas) {
+        let style = match self.status {
+            EntityStatus::Alive => sc.theme().bridge,
+            EntityStatus::DeadBody => sc.theme().bridge_wreck,
+            EntityStatus::Dead => return,
+        };
+        self.sprite().draw(sc, &self.location, style);
+    }
+}
+
+impl Drawable for Tunnel {
+    fn draw(&self, sc: &mut Canvas) {
+        sc.draw_styled_char(self, '◎', sc.theme().tunnel);
+    }
+}
+
+impl Drawable for RechargePad {
+    fn draw(&self, sc: &mut Canvas) {
+        sc.draw_styled_char(self, '⚡', sc.theme().pad);
+    }
+}
+
+impl Drawable for BombCrate {
+    fn draw(&self, sc: &mut Canvas) {
+        sc.draw_styled_char(self, '✸', sc.theme().bomb);
+    }
+}
+
+impl Drawable for Obstacle {
+    fn draw(&self, sc: &mut Canvas) {
+        let glyph = match self.kind {
+            ObstacleKind::Rock => '◆',
+            ObstacleKind::Buoy => '◊',
+        };
+        sc.draw_styled_char(self, glyph, sc.theme().obstacle);
+    }
+}
+
+/// The whole screen lit up by a bomb going off.
+pub struct BombFlash;
+
+impl Drawable for BombFlash {
+    fn draw(&self, sc: &mut Canvas) {
+        let (maxc, maxl) = sc.size();
+        let style = sc.theme().bomb_flash;
+        for l in 0..maxl {
+            sc.draw_styled_line((0, l), " ".repeat(maxc as usize), style);
+        }
+    }
+}
+
 impl Drawable for Bullet {
     fn draw(&self, sc: &mut Canvas) {
-        sc.draw_styled_char(self, '⇈', ContentStyle::new().cyan().on_blue())
-            .draw_styled_char((self.location.c, self.location.l - 1), '↟', ContentStyle::new().cyan().on_blue());
+        let style = sc.theme().bullet;
+        if self.is_missile() {
+            sc.draw_styled_char(self, '⇡', style);
+            return;
+        }
+        sc.draw_styled_char(self, '⇈', style).draw_styled_char(
+            (self.location.c, self.location.l - 1),
+            '↟',
+            style,
+        );
+    }
+}
+
+impl Drawable for Spark {
+    fn draw(&self, sc: &mut Canvas) {
+        let animation = match self.kind {
+            SparkKind::MuzzleFlash => &MUZZLE_FLASH,
+            SparkKind::Trail => &TRAIL,
+            SparkKind::Jammed => &JAMMED,
+            SparkKind::Debris => &DEBRIS,
+            SparkKind::Smoke => &SMOKE,
+        };
+        let age = u32::from(self.age);
+        match self.kind {
+            SparkKind::MuzzleFlash => {
+                let Location { c, l } = self.location;
+                animation.draw(sc, &Location::new(c.saturating_sub(1), l), age);
+                animation.draw(sc, &Location::new(c + 1, l), age);
+            }
+            _ => animation.draw(sc, &self.location, age),
+        }
+    }
+}
+
+impl Drawable for EnemyBullet {
+    fn draw(&self, sc: &mut Canvas) {
+        sc.draw_styled_char(self, '↓', sc.theme().enemy_bullet);
     }
 }
 
 impl Drawable for Player {
     fn draw(&self, sc: &mut Canvas) {
-        sc.draw_styled(self, '▲'.white().on_blue());
+        // blink while invulnerable
+        if self.invulnerable % 4 < 2 {
+            self.sprite().draw(sc, &self.location, sc.theme().player);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DEBRIS, FUEL, TRAIL};
+    use crate::theme::Theme;
+
+    #[test]
+    fn frames_follow_the_clock() {
+        assert_eq!(DEBRIS.frame(1).glyph, '*');
+        assert_eq!(DEBRIS.frame(2).glyph, '·');
+        // one-shot animations hold their last frame
+        assert_eq!(DEBRIS.frame(40).glyph, '·');
+        assert_eq!(TRAIL.frame(1).glyph, '│');
+        assert_eq!(TRAIL.frame(2).glyph, '╎');
+
+        let theme = Theme::classic();
+        let glint = |tick| (FUEL.frame(tick).style)(&theme) == theme.fuel_glint;
+        assert!(!glint(7));
+        assert!(glint(8));
+        assert!(!glint(10));
+        assert!(glint(19));
     }
 }
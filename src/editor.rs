@@ -0,0 +1,385 @@
+//! Track editor, opened with `--edit-track <file>` instead of a game: pan
+//! through a custom track (see [`crate::track`]), shape the river line by
+//! line and place what spawns on it, then save it to fly it with `--track`.
+//!
+//! The track scrolls like the river, its first line at the bottom:
+//!
+//! ```text
+//! ↑ ↓, PgUp PgDn   pan, by a line or ten     ← →   move the cursor
+//! + -              widen or narrow the river  < >   shift it left or right
+//! n                new line after this one    d     delete this line
+//! e f h            enemy, fuel, helicopter    b     bridge across the line
+//! x                clear the line's markers   s     save
+//! q, Esc           quit
+//! ```
+
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crossterm::{
+    event::{read, Event, KeyCode, KeyEventKind},
+    style::ContentStyle,
+};
+
+use crate::{
+    canvas::Canvas,
+    drawable::Drawable,
+    theme::Theme,
+    track::{Track, TrackLine},
+    world::{SpawnKind, MIN_COLUMNS, MIN_LINES},
+};
+
+/// Narrowest the editor lets the river get
+const MIN_WIDTH: u16 = 3;
+
+/// Lines of a new track
+const NEW_LINES: usize = 200;
+
+/// Lines PgUp and PgDn pan by
+const PAGE: usize = 10;
+
+pub struct Editor {
+    pub track: Track,
+    path: PathBuf,
+    /// Line of the track the cursor is on
+    line: usize,
+    column: u16,
+    /// Line of the track at the bottom of the screen
+    bottom: usize,
+    maxc: u16,
+    maxl: u16,
+    /// Changed since last saved
+    dirty: bool,
+    /// Shown on the status line
+    message: String,
+}
+
+impl Editor {
+    /// Edit the track at `path`, a new straight one if there is none yet.
+    pub fn open(path: PathBuf, maxc: u16, maxl: u16) -> io::Result<Editor> {
+        // laid out like the world, at least as large as it flies in
+        let (maxc, maxl) = (maxc.max(MIN_COLUMNS), maxl.max(MIN_LINES));
+        let track = match Track::from_file(&path) {
+            Ok(track) => track,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Track {
+                lines: vec![
+                    TrackLine {
+                        banks: (maxc / 2 - 10, maxc / 2 + 10),
+                        ..TrackLine::default()
+                    };
+                    NEW_LINES
+                ],
+            },
+            Err(error) => return Err(error),
+        };
+        Ok(Editor {
+            track,
+            path,
+            line: 0,
+            column: maxc / 2,
+            bottom: 0,
+            maxc,
+            maxl,
+            dirty: false,
+            message: String::new(),
+        })
+    }
+
+    pub fn resize(&mut self, maxc: u16, maxl: u16) {
+        (self.maxc, self.maxl) = (maxc.max(MIN_COLUMNS), maxl.max(MIN_LINES));
+        self.column = self.column.min(self.maxc - 1);
+        self.pan(0);
+    }
+
+    /// Lines of track on screen, the last line is for the status
+    fn rows(&self) -> usize {
+        usize::from(self.maxl.saturating_sub(1)).max(1)
+    }
+
+    /// Move the cursor `step` lines up the track, keeping it on screen.
+    fn pan(&mut self, step: isize) {
+        let last = self.track.lines.len() - 1;
+        self.line = self.line.saturating_add_signed(step).min(last);
+        if self.line < self.bottom {
+            self.bottom = self.line;
+        } else if self.line >= self.bottom + self.rows() {
+            self.bottom = self.line + 1 - self.rows();
+        }
+    }
+
+    /// Change the banks of the cursor line to `banks`, if they leave a river
+    /// on screen, or bring a bank off a narrower screen back in. An island
+    /// or a marker that no longer fits goes.
+    fn reshape(&mut self, banks: Option<(u16, u16)>) {
+        let line = &mut self.track.lines[self.line];
+        let Some((left, right)) = banks.filter(|(left, right)| {
+            right.saturating_sub(*left) >= MIN_WIDTH
+                && (*right <= self.maxc || *right < line.banks.1)
+        }) else {
+            return;
+        };
+        line.banks = (left, right);
+        line.island = line
+            .island
+            .filter(|(start, end)| left < *start && *end < right);
+        let spawns = std::mem::take(&mut line.spawns);
+        line.spawns = spawns
+            .into_iter()
+            .filter(|(kind, c)| line.fits(*kind, *c))
+            .collect();
+        self.dirty = true;
+    }
+
+    /// Put a `kind` marker at the cursor, or take the one there away. Markers
+    /// only go where they fit, see [`TrackLine::fits`].
+    fn toggle_marker(&mut self, kind: SpawnKind) {
+        let column = self.column;
+        if !self.track.lines[self.line].fits(kind, column) {
+            self.message = "Markers go on the river".to_string();
+            return;
+        }
+        let spawns = &mut self.track.lines[self.line].spawns;
+        match spawns.iter().position(|spawn| *spawn == (kind, column)) {
+            Some(index) => {
+                spawns.remove(index);
+            }
+            None => spawns.push((kind, column)),
+        }
+        self.dirty = true;
+    }
+
+    fn save(&mut self) {
+        self.message = match self.track.to_file(&self.path) {
+            Ok(()) => {
+                self.dirty = false;
+                format!("Saved to {}", self.path.display())
+            }
+            Err(error) => format!("Could not save: {error}"),
+        };
+    }
+
+    /// Act on `code`. Returns `false` once the editor is to close.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        let (left, right) = self.track.lines[self.line].banks;
+        let quitting = self.message.starts_with("Unsaved");
+        self.message.clear();
+        match code {
+            KeyCode::Up => self.pan(1),
+            KeyCode::Down => self.pan(-1),
+            KeyCode::PageUp => self.pan(PAGE as isize),
+            KeyCode::PageDown => self.pan(-(PAGE as isize)),
+            KeyCode::Left => self.column = self.column.saturating_sub(1),
+            KeyCode::Right => self.column = (self.column + 1).min(self.maxc - 1),
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.reshape(left.checked_sub(1).map(|left| (left, right + 1)));
+            }
+            KeyCode::Char('-') => self.reshape(Some((left + 1, right - 1))),
+            KeyCode::Char('<') | KeyCode::Char(',') => {
+                self.reshape(left.checked_sub(1).map(|left| (left, right - 1)));
+            }
+            KeyCode::Char('>') | KeyCode::Char('.') => self.reshape(Some((left + 1, right + 1))),
+            KeyCode::Char('n') => {
+                let copy = self.track.lines[self.line].clone();
+                self.track.lines.insert(
+                    self.line + 1,
+                    TrackLine {
+                        spawns: Vec::new(),
+                        ..copy
+                    },
+                );
+                self.dirty = true;
+                self.pan(1);
+            }
+            KeyCode::Char('d') if self.track.lines.len() > 1 => {
+                self.track.lines.remove(self.line);
+                self.dirty = true;
+                self.pan(0);
+            }
+            KeyCode::Char('e') => self.toggle_marker(SpawnKind::Enemy),
+            KeyCode::Char('f') => self.toggle_marker(SpawnKind::Fuel),
+            KeyCode::Char('h') => self.toggle_marker(SpawnKind::Helicopter),
+            KeyCode::Char('b') => {
+                let line = &mut self.track.lines[self.line];
+                line.bridge = !line.bridge;
+                self.dirty = true;
+            }
+            KeyCode::Char('x') => {
+                self.track.lines[self.line].spawns.clear();
+                self.dirty = true;
+            }
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('q') | KeyCode::Esc if self.dirty && !quitting => {
+                self.message = "Unsaved changes, quit again to drop them".to_string();
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            _ => {}
+        }
+        true
+    }
+} // end of Editor implementation.
+
+/// Glyph and style of a marker of `kind`
+fn marker(kind: SpawnKind, theme: &Theme) -> (char, ContentStyle) {
+    match kind {
+        SpawnKind::Enemy => ('☠', theme.enemy),
+        SpawnKind::Strafer => ('⇋', theme.enemy_on_bank),
+        SpawnKind::Helicopter => ('H', theme.helicopter),
+        SpawnKind::Ship => ('▬', theme.enemy),
+        SpawnKind::Tunnel => ('◎', theme.tunnel),
+        SpawnKind::Pad => ('⚡', theme.pad),
+        SpawnKind::Fuel => ('F', theme.fuel),
+        SpawnKind::Bomb => ('✸', theme.bomb),
+        SpawnKind::Obstacle => ('◆', theme.obstacle),
+    }
+}
+
+impl Drawable for Editor {
+    fn draw(&self, sc: &mut Canvas) {
+        let theme = sc.theme().clone();
+        let rows = self.rows();
+        for row in 0..rows {
+            let Some(line) = self.track.lines.get(self.bottom + rows - 1 - row) else {
+                continue;
+            };
+            let l = row as u16;
+            let (left, right) = line.banks;
+            let width = |from: u16, to: u16| " ".repeat(to.saturating_sub(from) as usize);
+            sc.draw_styled_line((0, l), width(0, left), theme.bank)
+                .draw_styled_line((left, l), width(left, right), theme.river)
+                .draw_styled_line((right, l), width(right, self.maxc), theme.bank);
+            if let Some((start, end)) = line.island {
+                sc.draw_styled_line((start, l), width(start, end), theme.bank);
+            }
+            if line.bridge {
+                let span = right.saturating_sub(left) as usize;
+                sc.draw_styled_line((left, l), "═".repeat(span), theme.bridge);
+            }
+            for (kind, c) in &line.spawns {
+                let (glyph, style) = marker(*kind, &theme);
+                sc.draw_styled_char((*c, l), glyph, style);
+            }
+        }
+
+        let cursor = (rows - 1 - (self.line - self.bottom)) as u16;
+        sc.draw_styled_char((self.column, cursor), '✛', theme.popup_focus);
+
+        let (left, right) = self.track.lines[self.line].banks;
+        let mut status = format!(
+            "line {}/{}  river {left}-{right}  column {}{}  ",
+            self.line + 1,
+            self.track.lines.len(),
+            self.column,
+            if self.dirty { "  (modified)" } else { "" },
+        );
+        if self.message.is_empty() {
+            status += "+-<> river  efh markers  b bridge  n/d line  s save  q quit";
+        } else {
+            status += &self.message;
+        }
+        sc.draw_styled_line((0, self.maxl - 1), status, theme.hud);
+    }
+}
+
+/// Run the editor on the track at `path` until it is closed.
+pub fn run(
+    stdout: &mut impl Write,
+    path: PathBuf,
+    size: (u16, u16),
+    theme: Theme,
+) -> io::Result<()> {
+    let (maxc, maxl) = size;
+    let mut editor = Editor::open(path, maxc, maxl)?;
+    let mut canvas = Canvas::new(editor.maxc, editor.maxl);
+    canvas.set_theme(theme);
+    loop {
+        canvas.clear_all();
+        canvas.draw(&editor);
+        canvas.draw_map(stdout)?;
+
+        match read()? {
+            Event::Key(event)
+                if event.kind == KeyEventKind::Press && !editor.handle_key(event.code) =>
+            {
+                return Ok(());
+            }
+            Event::Resize(maxc, maxl) => {
+                editor.resize(maxc, maxl);
+                canvas.resize(editor.maxc, editor.maxl);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::Editor;
+    use crate::world::SpawnKind;
+
+    #[test]
+    fn keys_shape_the_track() {
+        let mut editor = Editor::open("no-such.track".into(), 80, 24).unwrap();
+        assert_eq!(editor.track.lines[0].banks, (30, 50));
+
+        for code in [
+            KeyCode::Char('+'),
+            KeyCode::Char('>'),
+            KeyCode::Right,
+            KeyCode::Char('f'),
+        ] {
+            assert!(editor.handle_key(code));
+        }
+        assert_eq!(editor.track.lines[0].banks, (30, 52));
+        assert_eq!(editor.track.lines[0].spawns, [(SpawnKind::Fuel, 41)]);
+
+        // a copy of the banks, without the markers, and the cursor on it
+        editor.handle_key(KeyCode::Char('n'));
+        assert_eq!(editor.track.lines.len(), 201);
+        assert_eq!(editor.track.lines[1].banks, (30, 52));
+        assert!(editor.track.lines[1].spawns.is_empty());
+        for _ in 0..30 {
+            editor.handle_key(KeyCode::Char('-'));
+        }
+        assert_eq!(editor.track.lines[1].banks, (39, 43));
+        assert_eq!(editor.track.lines[0].banks, (30, 52));
+
+        // panning keeps the cursor on screen
+        editor.handle_key(KeyCode::PageUp);
+        editor.handle_key(KeyCode::PageUp);
+        editor.handle_key(KeyCode::PageUp);
+        assert_eq!((editor.line, editor.bottom), (31, 9));
+
+        // quitting asks once when there are unsaved changes
+        assert!(editor.handle_key(KeyCode::Char('q')));
+        assert!(!editor.handle_key(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn the_river_stays_on_screen_and_markers_on_water() {
+        // a terminal too small to fly in is laid out like the world does
+        let mut editor = Editor::open("no-such.track".into(), 8, 3).unwrap();
+        assert_eq!(editor.track.lines[0].banks, (0, 20));
+        editor.resize(4, 2);
+        assert_eq!((editor.maxc, editor.maxl), (20, 10));
+
+        // nothing goes on a bank
+        editor.column = 5;
+        editor.track.lines[0].banks = (10, 30);
+        editor.handle_key(KeyCode::Char('e'));
+        assert!(editor.track.lines[0].spawns.is_empty());
+
+        // a river wider than the screen can still be brought back in
+        editor.resize(40, 24);
+        editor.track.lines[0].banks = (30, 50);
+        editor.handle_key(KeyCode::Char('+'));
+        assert_eq!(editor.track.lines[0].banks, (30, 50));
+        editor.track.lines[0].spawns.push((SpawnKind::Fuel, 49));
+        editor.handle_key(KeyCode::Char('-'));
+        assert_eq!(editor.track.lines[0].banks, (31, 49));
+        assert!(editor.track.lines[0].spawns.is_empty());
+    }
+}
@@ -1,24 +1,58 @@
-#[derive(PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    behavior::{Centering, Movement, Strafing, Straight, ZigZag},
+    sprite::Sprite,
+    world::Region,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum DeathCause {
     Enemy,
     Ground,
     Fuel,
+    Bridge,
+    Obstacle,
 }
 
 #[derive(PartialEq, Eq)]
 pub enum PlayerStatus {
     Dead(DeathCause),
     Alive,
-    Quit,
+    /// Out of fuel: drifting down without guns until refueled or `ticks_left`
+    /// runs out
+    Gliding {
+        ticks_left: u16,
+    },
+    /// Left the game; `abandoned` a run in progress, after confirming
+    Quit {
+        abandoned: bool,
+    },
+    /// A versus race is over, see [`crate::versus`]
+    RaceOver {
+        won: bool,
+    },
+    /// Flew the last stage to its end, see [`crate::stage`]
+    Completed,
 }
 
+/// Stable handle of an entity, see [`crate::World::entity`]. Ids are handed
+/// out by the world in spawn order; `EntityId::default()` is not one yet.
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub struct EntityId(pub u32);
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EntityStatus {
+    #[default]
     Alive,
     DeadBody,
     Dead,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Location {
     pub c: u16,
     pub l: u16,
@@ -43,58 +77,600 @@ impl Location {
             && (other.c > self.c || self.c - other.c <= left)
             && (self.c > other.c || other.c - self.c <= right)
     }
+} // end of Location implementation.
 
-    // check if two locations is point to the same location
-    pub fn hit(&self, other: &Location) -> bool {
-        self.hit_with_margin(other, 0, 0, 0, 0)
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EnemyKind {
+    /// Waits on the bank, then dashes across the river
+    Strafer,
+    Helicopter,
+    /// Three cells wide
+    Ship,
+    /// Kinds unknown to this version load as static enemies.
+    #[serde(other)]
+    Static,
+}
+
+impl EnemyKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyKind::Static => "Enemy",
+            EnemyKind::Strafer => "Strafer",
+            EnemyKind::Helicopter => "Helicopter",
+            EnemyKind::Ship => "Ship",
+        }
     }
-} // end of Location implementation.
+} // end of EnemyKind implementation.
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Enemy {
     pub location: Location,
+    #[serde(default)]
+    pub id: EntityId,
+    #[serde(default)]
     pub status: EntityStatus,
+    pub kind: EnemyKind,
+    /// How it moves, see [`crate::behavior`]
+    #[serde(default)]
+    pub movement: Movement,
+    /// Ticks between two shots, `0` never shoots.
+    pub fire_rate: u16,
+    /// Ticks left until the next shot.
+    pub cooldown: u16,
 }
 
 impl Enemy {
     pub fn new(column: u16, line: u16, status: EntityStatus) -> Enemy {
         Enemy {
             location: Location::new(column, line),
+            id: EntityId::default(),
             status,
+            kind: EnemyKind::Static,
+            movement: Movement::Straight(Straight),
+            fire_rate: 40,
+            cooldown: 10,
+        }
+    }
+
+    pub fn strafer(column: u16, line: u16, direction: Direction) -> Enemy {
+        Enemy {
+            location: Location::new(column, line),
+            id: EntityId::default(),
+            status: EntityStatus::Alive,
+            kind: EnemyKind::Strafer,
+            movement: Movement::Strafing(Strafing {
+                direction,
+                dashing: false,
+            }),
+            fire_rate: 0,
+            cooldown: 0,
+        }
+    }
+
+    pub fn helicopter(column: u16, line: u16, direction: Direction) -> Enemy {
+        Enemy {
+            location: Location::new(column, line),
+            id: EntityId::default(),
+            status: EntityStatus::Alive,
+            kind: EnemyKind::Helicopter,
+            movement: Movement::ZigZag(ZigZag { direction }),
+            fire_rate: 60,
+            cooldown: 20,
+        }
+    }
+
+    pub fn ship(column: u16, line: u16) -> Enemy {
+        Enemy {
+            location: Location::new(column, line),
+            id: EntityId::default(),
+            status: EntityStatus::Alive,
+            kind: EnemyKind::Ship,
+            movement: Movement::Centering(Centering),
+            fire_rate: 0,
+            cooldown: 0,
+        }
+    }
+
+    /// Score for shooting this enemy down
+    pub fn reward(&self) -> u16 {
+        match self.kind {
+            EnemyKind::Static | EnemyKind::Strafer => 10,
+            EnemyKind::Ship => 20,
+            EnemyKind::Helicopter => 30,
+        }
+    }
+
+    /// Dashing across the river, see [`Strafing`]
+    pub fn is_dashing(&self) -> bool {
+        matches!(
+            self.movement,
+            Movement::Strafing(Strafing { dashing: true, .. })
+        )
+    }
+
+    /// Shape of enemies larger than a cell
+    pub fn sprite(&self) -> Option<Sprite> {
+        match self.kind {
+            EnemyKind::Ship => Some(Sprite::new(&["◄■►"], (1, 0))),
+            _ => None,
+        }
+    }
+
+    /// Cells the enemy takes up
+    pub fn hitbox(&self) -> Region {
+        match self.sprite() {
+            Some(sprite) => sprite.bounds(&self.location),
+            None => Region::around(&self.location, 0, 0),
         }
     }
 } // end of Enemy implementation.
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EnemyBullet {
+    pub location: Location,
+}
+
+impl EnemyBullet {
+    pub fn new(column: u16, line: u16) -> EnemyBullet {
+        EnemyBullet {
+            location: Location::new(column, line),
+        }
+    }
+} // end of EnemyBullet implementation.
+
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulletKind {
+    /// Flies straight up
+    #[default]
+    Shell,
+    /// Steers a column a tick toward `target`, the nearest enemy ahead
+    Missile { target: Option<EntityId> },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Bullet {
     pub location: Location,
     pub energy: u16,
+    /// Lines flown per tick
+    #[serde(default = "Bullet::shell_speed")]
+    pub speed: u16,
+    #[serde(default)]
+    pub kind: BulletKind,
+    /// It hit something, so it is no miss when it goes
+    #[serde(default)]
+    pub hit: bool,
 }
 
 impl Bullet {
+    fn shell_speed() -> u16 {
+        2
+    }
+
     pub fn new(column: u16, line: u16, energy: u16) -> Bullet {
         Bullet {
             location: Location::new(column, line),
             energy,
+            speed: Bullet::shell_speed(),
+            kind: BulletKind::Shell,
+            hit: false,
         }
     }
+
+    /// A homing missile, slower than a shell
+    pub fn missile(column: u16, line: u16, energy: u16) -> Bullet {
+        Bullet {
+            speed: 1,
+            kind: BulletKind::Missile { target: None },
+            ..Bullet::new(column, line, energy)
+        }
+    }
+
+    pub fn is_missile(&self) -> bool {
+        matches!(self.kind, BulletKind::Missile { .. })
+    }
 } // end of Bullet implementation.
 
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub enum SparkKind {
+    /// Flash at the nose of the plane as it fires
+    MuzzleFlash,
+    /// Cell a bullet just flew through
+    Trail,
+    /// The gun jammed, too many bullets in the air
+    Jammed,
+    /// Flying off an explosion
+    Debris,
+    /// Left behind by an explosion
+    Smoke,
+}
+
+/// A short-lived visual effect, gone after `lifetime` ticks. Sparks are only
+/// for show and never collide.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Spark {
+    pub location: Location,
+    pub kind: SparkKind,
+    /// Ticks it has been drawn for
+    pub age: u8,
+    pub lifetime: u8,
+    /// Columns and lines moved per tick
+    #[serde(default)]
+    pub velocity: (i16, i16),
+}
+
+impl Spark {
+    pub fn new(column: u16, line: u16, kind: SparkKind) -> Spark {
+        let lifetime = match kind {
+            SparkKind::MuzzleFlash => 1,
+            SparkKind::Trail | SparkKind::Debris => 2,
+            SparkKind::Jammed => 3,
+            SparkKind::Smoke => 5,
+        };
+        Spark {
+            location: Location::new(column, line),
+            kind,
+            age: 0,
+            lifetime,
+            velocity: (0, 0),
+        }
+    }
+
+    /// Debris flying off `location` in every diagonal, smoke in the middle.
+    pub fn burst(location: &Location) -> Vec<Spark> {
+        let Location { c, l } = *location;
+        let mut sparks: Vec<Spark> = [(-1, -1), (1, -1), (-2, 0), (2, 0), (-1, 1), (1, 1)]
+            .into_iter()
+            .map(|velocity| Spark {
+                velocity,
+                ..Spark::new(c, l, SparkKind::Debris)
+            })
+            .collect();
+        sparks.push(Spark::new(c, l, SparkKind::Smoke));
+        sparks
+    }
+
+    /// Age and move by a tick. Returns `false` once the spark is gone, past
+    /// its lifetime or off a `maxc` by `maxl` screen.
+    pub fn update(&mut self, maxc: u16, maxl: u16) -> bool {
+        self.age += 1;
+        let c = self.location.c.checked_add_signed(self.velocity.0);
+        let l = self.location.l.checked_add_signed(self.velocity.1);
+        match (c, l) {
+            (Some(c), Some(l)) if c < maxc && l < maxl && self.age <= self.lifetime => {
+                self.location = Location::new(c, l);
+                true
+            }
+            _ => false,
+        }
+    }
+} // end of Spark implementation.
+
+/// A depot the player refuels from while flying over it.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Fuel {
     pub location: Location,
+    #[serde(default)]
+    pub id: EntityId,
+    #[serde(default)]
     pub status: EntityStatus,
+    /// Gas left in the depot
+    pub stock: u16,
+    /// The player refuelled from it already
+    #[serde(default)]
+    pub tapped: bool,
 }
 
 impl Fuel {
+    /// Gas transferred to the player per tick of overlap
+    pub const REFUEL_RATE: u16 = 20;
+    /// Score for shooting the depot
+    pub const REWARD: u16 = 20;
+
     pub fn new(column: u16, line: u16, status: EntityStatus) -> Fuel {
         Fuel {
             location: Location::new(column, line),
+            id: EntityId::default(),
             status,
+            stock: 200,
+            tapped: false,
         }
     }
 } // end of Fuel implementation.
 
+/// Spans the whole river and has to be shot down to pass.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bridge {
+    /// Left end of the bridge
+    pub location: Location,
+    #[serde(default)]
+    pub id: EntityId,
+    pub width: u16,
+    #[serde(default)]
+    pub status: EntityStatus,
+}
+
+impl Bridge {
+    pub fn new(column: u16, line: u16, width: u16) -> Bridge {
+        Bridge {
+            location: Location::new(column, line),
+            id: EntityId::default(),
+            width,
+            status: EntityStatus::Alive,
+        }
+    }
+
+    pub fn sprite(&self) -> Sprite {
+        let glyph = match self.status {
+            EntityStatus::Alive => '═',
+            _ => '░',
+        };
+        Sprite::bar(glyph, self.width)
+    }
+
+    pub fn hitbox(&self) -> Region {
+        self.sprite().bounds(&self.location)
+    }
+
+    /// Checks if a location is on the bridge, give or take `margin` lines.
+    pub fn covers(&self, other: &Location, margin: u16) -> bool {
+        other.c >= self.location.c
+            && other.c < self.location.c + self.width
+            && other.l.abs_diff(self.location.l) <= margin
+    }
+} // end of Bridge implementation.
+
+/// Entrance of a shortcut that skips a stretch of the river.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tunnel {
+    pub location: Location,
+    #[serde(default)]
+    pub id: EntityId,
+}
+
+impl Tunnel {
+    pub fn new(column: u16, line: u16) -> Tunnel {
+        Tunnel {
+            location: Location::new(column, line),
+            id: EntityId::default(),
+        }
+    }
+} // end of Tunnel implementation.
+
+/// Bank-side pad that recharges the shield of a player hovering next to it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RechargePad {
+    pub location: Location,
+    #[serde(default)]
+    pub id: EntityId,
+    /// Ticks the player has spent next to the pad
+    pub charging: u16,
+}
+
+impl RechargePad {
+    /// Ticks of hovering per recharged shield point
+    pub const TICKS_PER_CHARGE: u16 = 15;
+
+    pub fn new(column: u16, line: u16) -> RechargePad {
+        RechargePad {
+            location: Location::new(column, line),
+            id: EntityId::default(),
+            charging: 0,
+        }
+    }
+} // end of RechargePad implementation.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObstacleKind {
+    Rock,
+    Buoy,
+}
+
+/// A rock or a buoy standing in the river. It scrolls with the map, wrecks
+/// the player on contact and stops bullets without taking damage.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub location: Location,
+    #[serde(default)]
+    pub id: EntityId,
+    pub kind: ObstacleKind,
+}
+
+impl Obstacle {
+    pub fn new(column: u16, line: u16, kind: ObstacleKind) -> Obstacle {
+        Obstacle {
+            location: Location::new(column, line),
+            id: EntityId::default(),
+            kind,
+        }
+    }
+} // end of Obstacle implementation.
+
+/// A rare crate that gives the player a bomb when flown over.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BombCrate {
+    pub location: Location,
+    #[serde(default)]
+    pub id: EntityId,
+}
+
+impl BombCrate {
+    pub fn new(column: u16, line: u16) -> BombCrate {
+        BombCrate {
+            location: Location::new(column, line),
+            id: EntityId::default(),
+        }
+    }
+} // end of BombCrate implementation.
+
 pub struct Player {
     pub location: Location,
     pub status: PlayerStatus,
-    pub gas: u16,
-    pub score: u16,
+    pub gas: u32,
+    pub score: u32,
+    pub lives: u8,
+    /// Ticks left before enemies and the ground can hurt the player again.
+    pub invulnerable: u16,
+    /// Hits from enemies the player can still take
+    pub shield: u8,
+    /// Bombs left, each clears the screen of enemies
+    pub bombs: u8,
+    /// Homing missiles left, refilled at every bridge
+    pub missiles: u8,
+}
+
+impl Player {
+    pub const MAX_SHIELD: u8 = 3;
+    pub const MAX_BOMBS: u8 = 3;
+    pub const MAX_MISSILES: u8 = 3;
+    /// Ticks the player is out of harm's way after respawning
+    pub const RESPAWN_INVULNERABLE: u16 = 30;
+    /// How long the player can glide on an empty tank
+    pub const GLIDE_TICKS: u16 = 60;
+    /// The player drifts one line down every this many ticks while gliding
+    pub const GLIDE_DRIFT: u16 = 4;
+
+    /// The jet, anchored at its body: the nose sticks out a line above
+    /// `location`. Hollow while gliding on an empty tank.
+    pub fn sprite(&self) -> Sprite {
+        match self.status {
+            PlayerStatus::Gliding { .. } => Sprite::new(&[" △ ", "╱▒╲"], (1, 1)),
+            _ => Sprite::new(&[" ▲ ", "╱█╲"], (1, 1)),
+        }
+    }
+
+    /// Still in the air, with or without fuel.
+    pub fn is_flying(&self) -> bool {
+        matches!(
+            self.status,
+            PlayerStatus::Alive | PlayerStatus::Gliding { .. }
+        )
+    }
+
+    /// Let the shield take a hit from an enemy. Returns `false` if there is
+    /// no shield left and the player is done for.
+    pub fn absorb_hit(&mut self) -> bool {
+        if self.shield == 0 {
+            return false;
+        }
+
+        self.shield -= 1;
+        self.invulnerable = 10;
+        true
+    }
+
+    /// Add `points` to the score, which stops at its maximum.
+    pub fn earn(&mut self, points: u16) {
+        self.score = self.score.saturating_add(u32::from(points));
+    }
+
+    /// Put `amount` of fuel in the tank, which stops at its maximum.
+    pub fn refuel(&mut self, amount: u16) {
+        self.gas = self.gas.saturating_add(u32::from(amount));
+    }
+} // end of Player implementation.
+
+/// Any entity but the player, tagged with its kind. This is the format level
+/// files, saves and the network share, and the one the state hash reads:
+///
+/// ```json
+/// {"entity": "enemy", "location": {"c": 10, "l": 0}, "kind": {"type": "ship"},
+///  "movement": {"type": "centering"}, "fire_rate": 0, "cooldown": 0}
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+pub enum EntityRecord {
+    Enemy(Enemy),
+    EnemyBullet(EnemyBullet),
+    Bullet(Bullet),
+    Fuel(Fuel),
+    Bridge(Bridge),
+    Tunnel(Tunnel),
+    Pad(RechargePad),
+    Bomb(BombCrate),
+    Obstacle(Obstacle),
+    /// An entity from a newer version, skipped when loaded
+    #[serde(other)]
+    Unknown,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Bridge, Enemy, EnemyKind, EntityRecord, EntityStatus, Fuel, Location, Obstacle,
+        ObstacleKind, Spark, SparkKind,
+    };
+
+    #[test]
+    fn bursts_fade_away() {
+        let mut sparks = Spark::burst(&Location::new(0, 5));
+        let tick = |sparks: &mut Vec<Spark>| sparks.retain_mut(|spark| spark.update(80, 24));
+
+        // nothing flies off the left edge
+        tick(&mut sparks);
+        assert_eq!(sparks.len(), 4);
+        assert!(sparks.iter().all(|spark| spark.velocity.0 >= 0));
+
+        tick(&mut sparks);
+        tick(&mut sparks);
+        assert!(sparks.iter().all(|spark| spark.kind == SparkKind::Smoke));
+        assert_eq!(sparks.len(), 1);
+
+        for _ in 0..3 {
+            tick(&mut sparks);
+        }
+        assert!(sparks.is_empty());
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let mut ship = Enemy::ship(3, 1);
+        ship.fire_rate = 20;
+        let records = vec![
+            EntityRecord::Enemy(ship),
+            EntityRecord::Fuel(Fuel::new(5, 2, EntityStatus::Alive)),
+            EntityRecord::Bridge(Bridge::new(10, 4, 30)),
+            EntityRecord::Obstacle(Obstacle::new(7, 3, ObstacleKind::Rock)),
+        ];
+        let written = serde_json::to_string(&records).unwrap();
+        assert!(written.starts_with(r#"[{"entity":"enemy","#));
+
+        let read: Vec<EntityRecord> = serde_json::from_str(&written).unwrap();
+        assert!(matches!(&read[0], EntityRecord::Enemy(enemy)
+            if matches!(enemy.kind, EnemyKind::Ship) && enemy.fire_rate == 20));
+        assert!(matches!(&read[3], EntityRecord::Obstacle(rock) if rock.location.c == 7));
+        assert_eq!(serde_json::to_string(&read).unwrap(), written);
+    }
+
+    #[test]
+    fn unknown_kinds_still_load() {
+        let records: Vec<EntityRecord> = serde_json::from_str(
+            r#"[
+                {"entity": "enemy", "location": {"c": 3, "l": 1}, "kind": {"type": "blimp"},
+                 "fire_rate": 0, "cooldown": 0},
+                {"entity": "volcano", "location": {"c": 3, "l": 1}},
+                {"entity": "fuel", "location": {"c": 5, "l": 2}, "stock": 100}
+            ]"#,
+        )
+        .unwrap();
+
+        assert!(matches!(&records[0], EntityRecord::Enemy(enemy)
+            if matches!(enemy.kind, EnemyKind::Static)));
+        assert!(matches!(records[1], EntityRecord::Unknown));
+        assert!(matches!(&records[2], EntityRecord::Fuel(fuel) if fuel.stock == 100));
+
+        let kind: EnemyKind = serde_json::from_str(r#"{"type": "blimp", "size": 3}"#).unwrap();
+        assert!(matches!(kind, EnemyKind::Static));
+        let kind: EnemyKind = serde_json::from_str(r#"{"type": "helicopter"}"#).unwrap();
+        assert!(matches!(kind, EnemyKind::Helicopter));
+    }
 }
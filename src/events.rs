@@ -3,68 +3,401 @@ use crossterm::event::{poll, read, Event, KeyCode, KeyEventKind};
 use std::time::Duration;
 
 use crate::{
-    entities::{Bullet, PlayerStatus},
-    world::World,
+    entities::{Bullet, Location, PlayerStatus, Spark, SparkKind},
+    feedback::FeedbackKind,
+    keymap::Action,
+    menu::{Menu, MenuEvent},
+    settings::SETTINGS_FILE,
+    world::{World, WorldStatus},
 };
 
+/// Pause menu entries
+const RESUME: usize = 0;
+const RESTART: usize = 1;
+const SETTINGS: usize = 2;
+const PHOTO: usize = 3;
+const HISTORY: usize = 4;
+const QUIT: usize = 5;
+/// Only in debug builds
+#[cfg(debug_assertions)]
+const INSPECT: usize = 6;
+
+/// Quit dialog entries
+const CANCEL: usize = 0;
+const CONFIRM: usize = 1;
+
+/// Settings menu entries
+const SOUND: usize = 0;
+const SPEED: usize = 1;
+const THEME: usize = 2;
+const BACK: usize = 3;
+
+fn toggle_pause(world: &mut World) {
+    use WorldStatus::*;
+    world.status = match world.status {
+        Warping { .. } | Settings | Photo | History | RunDetail(_) | ConfirmQuit => return,
+        #[cfg(debug_assertions)]
+        Inspect(_) => return,
+        Fluent => {
+            world.feedback.emit(FeedbackKind::PopupOpen);
+            world.pause_menu.reset();
+            Paused
+        }
+        Paused => {
+            world.feedback.emit(FeedbackKind::PopupClose);
+            Fluent
+        }
+    };
+}
+
+/// Quit at once if there is nothing to lose, otherwise ask first. Asked
+/// already, abandon the run.
+fn request_quit(world: &mut World) {
+    if matches!(world.status, WorldStatus::ConfirmQuit) {
+        world.player.status = PlayerStatus::Quit { abandoned: true };
+    } else if !world.has_progress() {
+        world.player.status = PlayerStatus::Quit { abandoned: false };
+    } else {
+        world.feedback.emit(FeedbackKind::PopupOpen);
+        world.quit_menu.reset();
+        world.quit_from = Some(std::mem::replace(
+            &mut world.status,
+            WorldStatus::ConfirmQuit,
+        ));
+    }
+}
+
+/// Close the quit dialog, back to wherever it was asked from.
+fn cancel_quit(world: &mut World) {
+    world.feedback.emit(FeedbackKind::PopupClose);
+    world.status = world.quit_from.take().unwrap_or(WorldStatus::Paused);
+}
+
+/// Leave the open menu: a run's details go back to the history, settings,
+/// photo mode and the history to the pause menu, the quit dialog to where it
+/// was asked from, the pause menu back to the game.
+fn close_menu(world: &mut World) {
+    match world.status {
+        WorldStatus::RunDetail(_) => world.status = WorldStatus::History,
+        WorldStatus::Settings | WorldStatus::Photo | WorldStatus::History => {
+            world.status = WorldStatus::Paused
+        }
+        WorldStatus::ConfirmQuit => cancel_quit(world),
+        #[cfg(debug_assertions)]
+        WorldStatus::Inspect(_) => world.status = WorldStatus::Paused,
+        _ => toggle_pause(world),
+    }
+}
+
+fn active_menu(world: &mut World) -> Option<&mut Menu> {
+    match world.status {
+        WorldStatus::Paused => Some(&mut world.pause_menu),
+        WorldStatus::Settings => Some(&mut world.settings_menu),
+        WorldStatus::History => Some(&mut world.history_menu),
+        WorldStatus::ConfirmQuit => Some(&mut world.quit_menu),
+        _ => None,
+    }
+}
+
+fn handle_pause_menu(world: &mut World, index: usize) {
+    match index {
+        RESUME => toggle_pause(world),
+        RESTART => world.restart(),
+        SETTINGS => {
+            world.settings_menu.reset();
+            world.status = WorldStatus::Settings;
+        }
+        PHOTO => {
+            world.camera = (0, 0);
+            world.status = WorldStatus::Photo;
+        }
+        HISTORY => {
+            world.load_history();
+            world.status = WorldStatus::History;
+        }
+        QUIT => request_quit(world),
+        #[cfg(debug_assertions)]
+        INSPECT => world.inspect(),
+        _ => {}
+    }
+}
+
+fn handle_settings_menu(world: &mut World, index: usize) {
+    match index {
+        SOUND => world.settings.sound = !world.settings.sound,
+        SPEED => world.settings.next_speed(),
+        THEME => {
+            let next = world.settings.theme(world.color_depth).next();
+            world.settings.theme = Some(next.name.to_string());
+        }
+        BACK => {
+            world.status = WorldStatus::Paused;
+            return;
+        }
+        _ => return,
+    }
+
+    world.apply_settings();
+    // Nowhere to report a failure mid-game; the change still applies to this run.
+    let _ = world.settings.save(SETTINGS_FILE);
+}
+
+/// Every run but the last entry, `Back`, opens its details.
+fn handle_history_menu(world: &mut World, index: usize) {
+    world.status = if index < world.history.len() {
+        WorldStatus::RunDetail(index)
+    } else {
+        WorldStatus::Paused
+    };
+}
+
+fn handle_quit_menu(world: &mut World, index: usize) {
+    match index {
+        CANCEL => cancel_quit(world),
+        CONFIRM => world.player.status = PlayerStatus::Quit { abandoned: true },
+        _ => {}
+    }
+}
+
+fn handle_menu_event(world: &mut World, event: Option<MenuEvent>) {
+    match event {
+        Some(MenuEvent::Moved) => world.feedback.emit(FeedbackKind::MenuMove),
+        Some(MenuEvent::Activated(index)) => {
+            world.feedback.emit(FeedbackKind::MenuSelect);
+            match world.status {
+                WorldStatus::Paused => handle_pause_menu(world, index),
+                WorldStatus::Settings => handle_settings_menu(world, index),
+                WorldStatus::History => handle_history_menu(world, index),
+                WorldStatus::ConfirmQuit => handle_quit_menu(world, index),
+                _ => {}
+            }
+        }
+        None => {}
+    }
+}
+
+/// Keys while a menu is open; the player does not move.
+fn handle_menu_keys(world: &mut World, code: KeyCode, kind: KeyEventKind) {
+    let action = world.keymap.menu_action(code);
+    let Some(menu) = active_menu(world) else {
+        return;
+    };
+
+    match action.and_then(|action| menu.handle_action(action)) {
+        None => match world.keymap.action(code) {
+            Some(Action::Quit) => request_quit(world),
+            Some(Action::Pause) if kind == KeyEventKind::Press => close_menu(world),
+            _ if code == KeyCode::Esc && kind == KeyEventKind::Press => close_menu(world),
+            _ => {}
+        },
+        event => handle_menu_event(world, event),
+    }
+}
+
+/// Actions from sources other than the keyboard while a menu is open.
+fn handle_menu_action(world: &mut World, action: Action) {
+    let Some(menu) = active_menu(world) else {
+        return;
+    };
+
+    let event = match action {
+        Action::MoveUp | Action::MoveDown | Action::Shoot => menu.handle_action(action),
+        Action::Pause => {
+            close_menu(world);
+            return;
+        }
+        Action::Quit => {
+            request_quit(world);
+            return;
+        }
+        Action::MoveLeft
+        | Action::MoveRight
+        | Action::Record
+        | Action::PlayMacro
+        | Action::Bomb
+        | Action::Missile => None,
+    };
+    handle_menu_event(world, event);
+}
+
+/// Apply a game action, whichever input it came from.
+pub fn handle_action(world: &mut World, action: Action) {
+    match world.status {
+        WorldStatus::Paused
+        | WorldStatus::Settings
+        | WorldStatus::History
+        | WorldStatus::ConfirmQuit => {
+            handle_menu_action(world, action);
+            return;
+        }
+        // The moves pan the camera, shooting recenters it
+        WorldStatus::Photo => {
+            match action {
+                Action::MoveUp => world.pan_camera(0, -1),
+                Action::MoveDown => world.pan_camera(0, 1),
+                Action::MoveLeft => world.pan_camera(-2, 0),
+                Action::MoveRight => world.pan_camera(2, 0),
+                Action::Shoot => world.camera = (0, 0),
+                Action::Quit => request_quit(world),
+                _ => close_menu(world),
+            }
+            return;
+        }
+        // Anything but quitting goes back to the previous menu
+        WorldStatus::RunDetail(_) => {
+            match action {
+                Action::Quit => request_quit(world),
+                _ => close_menu(world),
+            }
+            return;
+        }
+        // No steering inside a tunnel
+        WorldStatus::Warping { .. } => {
+            if action == Action::Quit {
+                request_quit(world);
+            }
+            return;
+        }
+        // Moves go to the cursor, anything else back to the pause menu
+        #[cfg(debug_assertions)]
+        WorldStatus::Inspect(_) => {
+            match action {
+                Action::MoveUp => world.move_inspector(0, -1),
+                Action::MoveDown => world.move_inspector(0, 1),
+                Action::MoveLeft => world.move_inspector(-1, 0),
+                Action::MoveRight => world.move_inspector(1, 0),
+                Action::Quit => request_quit(world),
+                _ => close_menu(world),
+            }
+            return;
+        }
+        WorldStatus::Fluent => {}
+    }
+
+    if let Action::MoveUp
+    | Action::MoveDown
+    | Action::MoveLeft
+    | Action::MoveRight
+    | Action::Shoot = action
+    {
+        world.input_macro.record(action);
+    }
+
+    match action {
+        Action::MoveUp
+            // the nose sticks out a line above, keep it and a bullet on screen
+            if world.player.status == PlayerStatus::Alive && world.player.location.l > 2 =>
+        {
+            world.player.location.l -= 1
+        }
+        Action::MoveDown
+            if world.player.is_flying()
+                && world.player.location.l < world.maxl - 1 =>
+        {
+            world.player.location.l += 1
+        }
+        Action::MoveLeft
+            if world.player.is_flying() && world.player.location.c > 1 =>
+        {
+            world.player.location.c -= 1
+        }
+        Action::MoveRight
+            if world.player.is_flying()
+                && world.player.location.c < world.maxc - 1 =>
+        {
+            world.player.location.c += 1
+        }
+        Action::Quit => request_quit(world),
+        Action::Pause => toggle_pause(world),
+        Action::Record => world.input_macro.toggle_recording(),
+        Action::PlayMacro if world.input_macro.play() => world.assisted = true,
+        Action::Bomb => world.drop_bomb(),
+        // too much lead in the air: the gun jams instead of firing
+        Action::Shoot | Action::Missile
+            if world.player.status == PlayerStatus::Alive && world.bullets_capped() =>
+        {
+            let Location { c, l } = world.player.location;
+            world.sparks.push(Spark::new(c, l - 2, SparkKind::Jammed));
+        }
+        Action::Shoot
+            if world.player.status == PlayerStatus::Alive
+                && world.bullets.iter().all(Bullet::is_missile) =>
+        {
+            let new_bullet = Bullet::new(
+                world.player.location.c,
+                world.player.location.l - 2,
+                world.maxl / 4,
+            );
+            world.bullets.push(new_bullet);
+            world.sparks.push(Spark::new(
+                world.player.location.c,
+                world.player.location.l - 2,
+                SparkKind::MuzzleFlash,
+            ));
+            world.note_shot();
+        }
+        // one missile in the air at a time
+        Action::Missile
+            if world.player.status == PlayerStatus::Alive
+                && world.player.missiles > 0
+                && !world.bullets.iter().any(Bullet::is_missile) =>
+        {
+            let Location { c, l } = world.player.location;
+            world.player.missiles -= 1;
+            world.bullets.push(Bullet::missile(c, l - 2, world.maxl));
+            world.sparks.push(Spark::new(c, l - 2, SparkKind::MuzzleFlash));
+            world.note_shot();
+        }
+        _ => {}
+    }
+}
+
 pub fn handle_pressed_keys(world: &mut World) {
     if poll(Duration::from_millis(10)).unwrap() {
         let key = read().unwrap();
 
         while poll(Duration::from_millis(0)).unwrap() {
-            let _ = read();
-        }
-
-        match key {
-            Event::Key(event) => {
-                // I'm reading from keyboard into event
-                match event.code {
-                    KeyCode::Char('w') | KeyCode::Up
-                        if world.player.status == PlayerStatus::Alive
-                            && world.player.location.l > 1 =>
-                    {
-                        world.player.location.l -= 1
-                    }
-                    KeyCode::Char('s') | KeyCode::Down
-                        if world.player.status == PlayerStatus::Alive
-                            && world.player.location.l < world.maxl - 1 =>
-                    {
-                        world.player.location.l += 1
-                    }
-                    KeyCode::Char('a') | KeyCode::Left
-                        if world.player.status == PlayerStatus::Alive
-                            && world.player.location.c > 1 =>
-                    {
-                        world.player.location.c -= 1
-                    }
-                    KeyCode::Char('d') | KeyCode::Right
-                        if world.player.status == PlayerStatus::Alive
-                            && world.player.location.c < world.maxc - 1 =>
-                    {
-                        world.player.location.c += 1
-                    }
-                    KeyCode::Char('q') => world.player.status = PlayerStatus::Quit,
-                    KeyCode::Char('p') if event.kind == KeyEventKind::Press => {
-                        use crate::WorldStatus::*;
-                        world.status = match world.status {
-                            Fluent => Paused,
-                            Paused => Fluent,
-                        };
-                    }
-                    KeyCode::Char(' ') => {
-                        if world.player.status == PlayerStatus::Alive && world.bullets.is_empty() {
-                            let new_bullet = Bullet::new(
-                                world.player.location.c,
-                                world.player.location.l - 1,
-                                world.maxl / 4,
-                            );
-                            world.bullets.push(new_bullet);
-                        }
-                    }
-                    _ => {}
+            // drop extra keys, but not a resize
+            if let Ok(Event::Resize(maxc, maxl)) = read() {
+                world.resize(maxc, maxl);
+            }
+        }
+
+        if let Event::Resize(maxc, maxl) = key {
+            world.resize(maxc, maxl);
+        } else if let Event::Key(event) = key {
+            // I'm reading from keyboard into event
+            if let WorldStatus::Paused
+            | WorldStatus::Settings
+            | WorldStatus::History
+            | WorldStatus::ConfirmQuit = world.status
+            {
+                handle_menu_keys(world, event.code, event.kind);
+            } else if let WorldStatus::Photo | WorldStatus::RunDetail(_) = world.status {
+                match world.keymap.action(event.code) {
+                    Some(action) => handle_action(world, action),
+                    None if event.kind == KeyEventKind::Press => close_menu(world),
+                    None => {}
+                }
+            } else {
+                match world.keymap.action(event.code) {
+                    Some(Action::Pause) if event.kind != KeyEventKind::Press => {}
+                    Some(action) => handle_action(world, action),
+                    None => {}
                 }
             }
-            _ => {}
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    {
+        let actions = world
+            .gamepad
+            .as_mut()
+            .map(|gamepad| gamepad.actions())
+            .unwrap_or_default();
+        for action in actions {
+            handle_action(world, action);
         }
     }
 }
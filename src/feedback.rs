@@ -0,0 +1,268 @@
+//! Feedback pulses: menu navigation, popups, and gameplay moments (a hit
+//! on the shield, a pickup, a death).
+//!
+//! Pulses are played by a [`FeedbackDevice`]: a controller that rumbles
+//! when there is one (`gamepad` feature), the terminal otherwise, ringing its
+//! bell or flashing the screen. Each category has its own volume, set with
+//! `menu_volume`, `popup_volume` and `gameplay_volume` in the settings: it
+//! scales how hard pulses are, `0` mutes the category, and single kinds can
+//! be silenced with `quiet`.
+
+use std::io::{self, Write};
+
+/// Volume of a category unless set, pulses as strong as their kind
+pub const DEFAULT_VOLUME: u8 = 5;
+pub const MAX_VOLUME: u8 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FeedbackKind {
+    MenuMove,
+    MenuSelect,
+    PopupOpen,
+    PopupClose,
+    /// The shield took a hit
+    Hit,
+    /// Fuel or shield picked up
+    Pickup,
+    Death,
+}
+
+pub enum FeedbackCategory {
+    Menu,
+    Popup,
+    Gameplay,
+}
+
+impl FeedbackKind {
+    pub const ALL: [FeedbackKind; 7] = [
+        FeedbackKind::MenuMove,
+        FeedbackKind::MenuSelect,
+        FeedbackKind::PopupOpen,
+        FeedbackKind::PopupClose,
+        FeedbackKind::Hit,
+        FeedbackKind::Pickup,
+        FeedbackKind::Death,
+    ];
+
+    pub fn category(&self) -> FeedbackCategory {
+        match self {
+            FeedbackKind::MenuMove | FeedbackKind::MenuSelect => FeedbackCategory::Menu,
+            FeedbackKind::PopupOpen | FeedbackKind::PopupClose => FeedbackCategory::Popup,
+            FeedbackKind::Hit | FeedbackKind::Pickup | FeedbackKind::Death => {
+                FeedbackCategory::Gameplay
+            }
+        }
+    }
+
+    /// Name in the settings file, e.g. `menu_move`
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeedbackKind::MenuMove => "menu_move",
+            FeedbackKind::MenuSelect => "menu_select",
+            FeedbackKind::PopupOpen => "popup_open",
+            FeedbackKind::PopupClose => "popup_close",
+            FeedbackKind::Hit => "hit",
+            FeedbackKind::Pickup => "pickup",
+            FeedbackKind::Death => "death",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FeedbackKind> {
+        FeedbackKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+    }
+
+    /// How hard the pulse is, from 1 to 10
+    pub fn strength(&self) -> u8 {
+        match self {
+            FeedbackKind::MenuMove => 1,
+            FeedbackKind::MenuSelect | FeedbackKind::PopupOpen | FeedbackKind::PopupClose => 2,
+            FeedbackKind::Pickup => 3,
+            FeedbackKind::Hit => 6,
+            FeedbackKind::Death => 10,
+        }
+    }
+}
+
+/// Something that plays feedback pulses.
+pub trait FeedbackDevice {
+    /// Queue a pulse of `strength` (1 to 10) for `kind`.
+    fn pulse(&mut self, kind: FeedbackKind, strength: u8);
+
+    /// Play what was queued. Called once per frame.
+    fn flush(&mut self, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// The terminal: rings its bell or, with `flash`, shows the screen in
+/// reverse video for a frame. Several pulses in one frame play once.
+#[derive(Default)]
+pub struct TerminalPulse {
+    pub flash: bool,
+    pending: bool,
+    /// The screen is in reverse video, until the next flush
+    flashing: bool,
+}
+
+impl FeedbackDevice for TerminalPulse {
+    fn pulse(&mut self, _kind: FeedbackKind, _strength: u8) {
+        self.pending = true;
+    }
+
+    fn flush(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        if self.flashing {
+            write!(out, "\x1b[?5l")?;
+            self.flashing = false;
+        }
+        if std::mem::take(&mut self.pending) {
+            if self.flash {
+                write!(out, "\x1b[?5h")?;
+                self.flashing = true;
+            } else {
+                write!(out, "\x07")?;
+            }
+        }
+        out.flush()
+    }
+}
+
+pub struct Feedback {
+    /// Silences every category, see [`crate::settings::Settings::sound`]
+    pub muted: bool,
+    pub menu_volume: u8,
+    pub popup_volume: u8,
+    pub gameplay_volume: u8,
+    /// Kinds never played, see [`crate::settings::Settings::quiet`]
+    pub quiet: Vec<FeedbackKind>,
+    /// Plays pulses when no other device does
+    pub terminal: TerminalPulse,
+    /// Devices found on start, e.g. a controller that rumbles
+    devices: Vec<Box<dyn FeedbackDevice>>,
+    /// Everything emitted, muted or not, for tests to check
+    #[cfg(test)]
+    pub fired: Vec<FeedbackKind>,
+}
+
+impl Feedback {
+    pub fn new() -> Self {
+        // only the gamepad feature finds devices so far
+        #[allow(unused_mut)]
+        let mut devices: Vec<Box<dyn FeedbackDevice>> = Vec::new();
+        #[cfg(feature = "gamepad")]
+        if let Some(rumble) = crate::gamepad::Rumble::new() {
+            devices.push(Box::new(rumble));
+        }
+
+        Feedback {
+            muted: false,
+            menu_volume: DEFAULT_VOLUME,
+            popup_volume: DEFAULT_VOLUME,
+            gameplay_volume: DEFAULT_VOLUME,
+            quiet: Vec::new(),
+            terminal: TerminalPulse::default(),
+            devices,
+            #[cfg(test)]
+            fired: Vec::new(),
+        }
+    }
+
+    pub fn volume(&self, category: FeedbackCategory) -> u8 {
+        match category {
+            FeedbackCategory::Menu => self.menu_volume,
+            FeedbackCategory::Popup => self.popup_volume,
+            FeedbackCategory::Gameplay => self.gameplay_volume,
+        }
+    }
+
+    /// Queue a feedback pulse; it is played on the next [`Feedback::flush`].
+    pub fn emit(&mut self, kind: FeedbackKind) {
+        #[cfg(test)]
+        self.fired.push(kind);
+
+        let volume = self.volume(kind.category());
+        if self.muted || volume == 0 || self.quiet.contains(&kind) {
+            return;
+        }
+        let strength = (kind.strength() * volume / DEFAULT_VOLUME).clamp(1, 10);
+        if self.devices.is_empty() {
+            self.terminal.pulse(kind, strength);
+        }
+        for device in &mut self.devices {
+            device.pulse(kind, strength);
+        }
+    }
+
+    /// Play queued feedback.
+    pub fn flush(&mut self, stdout: &mut impl Write) -> Result<(), std::io::Error> {
+        self.terminal.flush(stdout)?;
+        for device in &mut self.devices {
+            device.flush(stdout)?;
+        }
+        Ok(())
+    }
+} // end of Feedback implementation.
+
+/// Assert the world emitted `kind` since it was created, e.g.
+/// `assert_event_fired!(world, FeedbackKind::PopupOpen)`.
+#[cfg(test)]
+macro_rules! assert_event_fired {
+    ($world:expr, $kind:expr) => {
+        assert!(
+            $world.feedback.fired.contains(&$kind),
+            "{:?} was not fired, got {:?}",
+            $kind,
+            $world.feedback.fired
+        )
+    };
+}
+
+#[cfg(test)]
+pub(crate) use assert_event_fired;
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, io::Write, rc::Rc};
+
+    use super::{Feedback, FeedbackDevice, FeedbackKind};
+
+    /// Keeps the pulses it gets
+    struct Recorder(Rc<RefCell<Vec<(FeedbackKind, u8)>>>);
+
+    impl FeedbackDevice for Recorder {
+        fn pulse(&mut self, kind: FeedbackKind, strength: u8) {
+            self.0.borrow_mut().push((kind, strength));
+        }
+
+        fn flush(&mut self, _out: &mut dyn Write) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn devices_take_over_from_the_terminal() {
+        let mut feedback = Feedback::new();
+        let mut out = Vec::new();
+        feedback.emit(FeedbackKind::MenuMove);
+        feedback.emit(FeedbackKind::MenuSelect);
+        feedback.flush(&mut out).unwrap();
+        assert_eq!(out, b"\x07");
+
+        let pulses = Rc::new(RefCell::new(Vec::new()));
+        feedback.devices = vec![Box::new(Recorder(pulses.clone()))];
+        feedback.quiet = vec![FeedbackKind::Pickup];
+        feedback.emit(FeedbackKind::Pickup);
+        feedback.emit(FeedbackKind::Death);
+        out.clear();
+        feedback.flush(&mut out).unwrap();
+        assert!(out.is_empty());
+        assert_eq!(*pulses.borrow(), [(FeedbackKind::Death, 10)]);
+
+        // volumes scale the pulses of their category, 0 mutes it
+        pulses.borrow_mut().clear();
+        feedback.menu_volume = 10;
+        feedback.gameplay_volume = 0;
+        feedback.emit(FeedbackKind::MenuSelect);
+        feedback.emit(FeedbackKind::Death);
+        assert_eq!(*pulses.borrow(), [(FeedbackKind::MenuSelect, 4)]);
+    }
+}
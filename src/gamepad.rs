@@ -0,0 +1,139 @@
+//! Controller input (`gamepad` feature).
+//!
+//! Buttons and sticks are turned into the same [`Action`]s the keyboard
+//! produces, so the rest of the game does not care where input came from.
+//! Controllers with force feedback also rumble, see [`Rumble`].
+
+use std::io::{self, Write};
+
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks},
+    Axis, Button, Event, EventType, GamepadId, Gilrs,
+};
+
+use crate::{
+    feedback::{FeedbackDevice, FeedbackKind},
+    keymap::Action,
+};
+
+/// How far a stick has to be pushed to count as a move
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// Length of the strongest rumble, weaker ones are shorter
+const RUMBLE_MS: u32 = 300;
+
+pub struct Gamepad {
+    gilrs: Gilrs,
+}
+
+impl Gamepad {
+    /// `None` if the platform has no controller support.
+    pub fn new() -> Option<Gamepad> {
+        Gilrs::new().ok().map(|gilrs| Gamepad { gilrs })
+    }
+
+    /// Actions for this tick: one per button press, plus a move for every
+    /// direction held down (like a repeating key).
+    pub fn actions(&mut self) -> Vec<Action> {
+        let mut actions = vec![];
+
+        while let Some(Event { event, .. }) = self.gilrs.next_event() {
+            if let EventType::ButtonPressed(button, _) = event {
+                match button {
+                    Button::South | Button::RightTrigger => actions.push(Action::Shoot),
+                    Button::West => actions.push(Action::Bomb),
+                    Button::North => actions.push(Action::Missile),
+                    Button::Start => actions.push(Action::Pause),
+                    Button::Select => actions.push(Action::Quit),
+                    _ => {}
+                }
+            }
+        }
+
+        for (_, gamepad) in self.gilrs.gamepads() {
+            let x = gamepad.value(Axis::LeftStickX);
+            let y = gamepad.value(Axis::LeftStickY);
+
+            if gamepad.is_pressed(Button::DPadUp) || y > STICK_THRESHOLD {
+                actions.push(Action::MoveUp);
+            }
+            if gamepad.is_pressed(Button::DPadDown) || y < -STICK_THRESHOLD {
+                actions.push(Action::MoveDown);
+            }
+            if gamepad.is_pressed(Button::DPadLeft) || x < -STICK_THRESHOLD {
+                actions.push(Action::MoveLeft);
+            }
+            if gamepad.is_pressed(Button::DPadRight) || x > STICK_THRESHOLD {
+                actions.push(Action::MoveRight);
+            }
+        }
+
+        actions
+    }
+}
+
+/// Rumbles every controller that supports force feedback.
+pub struct Rumble {
+    gilrs: Gilrs,
+    /// Strongest pulse since the last flush, 0 for none
+    strongest: u8,
+    /// The rumble playing; dropping it stops it
+    effect: Option<Effect>,
+}
+
+impl Rumble {
+    /// `None` if no connected controller can rumble.
+    pub fn new() -> Option<Rumble> {
+        let gilrs = Gilrs::new().ok()?;
+        let mut gamepads = gilrs.gamepads();
+        gamepads
+            .any(|(_, gamepad)| gamepad.is_ff_supported())
+            .then_some(Rumble {
+                gilrs,
+                strongest: 0,
+                effect: None,
+            })
+    }
+}
+
+impl FeedbackDevice for Rumble {
+    fn pulse(&mut self, _kind: FeedbackKind, strength: u8) {
+        self.strongest = self.strongest.max(strength);
+    }
+
+    fn flush(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        // keep the list of connected controllers current
+        while self.gilrs.next_event().is_some() {}
+
+        let strength = std::mem::take(&mut self.strongest);
+        if strength == 0 {
+            return Ok(());
+        }
+
+        let ids: Vec<GamepadId> = self
+            .gilrs
+            .gamepads()
+            .filter(|(_, gamepad)| gamepad.is_ff_supported())
+            .map(|(id, _)| id)
+            .collect();
+        let length = Ticks::from_ms(RUMBLE_MS * u32::from(strength) / 10);
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: u16::MAX / 10 * u16::from(strength),
+                },
+                scheduling: Replay {
+                    play_for: length,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .repeat(Repeat::For(length))
+            .gamepads(&ids)
+            .finish(&mut self.gilrs)
+            .map_err(io::Error::other)?;
+        effect.play().map_err(io::Error::other)?;
+        self.effect = Some(effect);
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! Best scores, kept in `highscores.txt` one per line:
+//!
+//! ```text
+//! name;score;traveled;seconds[;assisted]
+//! ada;1250;2300;135
+//! bob;980;1900;120;assisted
+//! ```
+
+use std::{cmp::Reverse, fs, path::Path};
+
+pub const HIGHSCORES_FILE: &str = "highscores.txt";
+
+/// Scores kept on the board
+pub const MAX_ENTRIES: usize = 10;
+
+/// Longest name the board takes
+pub const MAX_NAME_LEN: usize = 12;
+
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    /// Lines of river flown
+    pub traveled: u32,
+    /// Time actually played, in seconds
+    pub seconds: u64,
+    /// An input macro was played, see [`crate::input_macro`]
+    pub assisted: bool,
+}
+
+impl HighScore {
+    fn parse(line: &str) -> Option<HighScore> {
+        let mut fields = line.split(';');
+        Some(HighScore {
+            name: fields.next()?.to_string(),
+            score: fields.next()?.parse().ok()?,
+            traveled: fields.next()?.parse().ok()?,
+            seconds: fields.next()?.parse().ok()?,
+            assisted: fields.next() == Some("assisted"),
+        })
+    }
+} // end of HighScore implementation.
+
+/// Best scores first
+#[derive(Default)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// The board saved in `path`. Broken lines are skipped.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<HighScores> {
+        let mut entries: Vec<HighScore> = fs::read_to_string(path)?
+            .lines()
+            .filter_map(HighScore::parse)
+            .collect();
+        entries.sort_by_key(|entry| Reverse(entry.score));
+        entries.truncate(MAX_ENTRIES);
+        Ok(HighScores { entries })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let lines: String = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{};{};{};{}{}\n",
+                    entry.name,
+                    entry.score,
+                    entry.traveled,
+                    entry.seconds,
+                    if entry.assisted { ";assisted" } else { "" }
+                )
+            })
+            .collect();
+        fs::write(path, lines)
+    }
+
+    /// Whether `score` makes it to the board.
+    pub fn places(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < MAX_ENTRIES
+                || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Put `entry` on the board, below equal scores. Returns its rank,
+    /// counting from `0`.
+    pub fn insert(&mut self, mut entry: HighScore) -> usize {
+        // the file is `;` separated
+        entry.name = entry.name.replace(';', " ");
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        rank
+    }
+} // end of HighScores implementation.
+
+#[cfg(test)]
+mod tests {
+    use super::{HighScore, HighScores, MAX_ENTRIES};
+
+    fn entry(score: u32) -> HighScore {
+        HighScore {
+            name: "ada;b".to_string(),
+            score,
+            traveled: 0,
+            seconds: 0,
+            assisted: false,
+        }
+    }
+
+    #[test]
+    fn board_keeps_the_best() {
+        let mut board = HighScores::default();
+        for score in 1..=MAX_ENTRIES as u32 {
+            board.insert(entry(score * 10));
+        }
+
+        assert!(!board.places(10));
+        assert!(board.places(15));
+        assert_eq!(board.insert(entry(55)), 5);
+        assert_eq!(board.entries.len(), MAX_ENTRIES);
+        assert_eq!(board.entries.last().unwrap().score, 20);
+        assert_eq!(
+            HighScore::parse("ada b;55;0;0").unwrap().name,
+            board.entries[5].name
+        );
+        assert!(HighScore::parse("bob;9;1;1;assisted").unwrap().assisted);
+        assert_eq!(HighScore::parse("eve;100000;0;0").unwrap().score, 100_000);
+    }
+}
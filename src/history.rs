@@ -0,0 +1,341 @@
+//! Past runs, appended to `history.log` one line per run:
+//!
+//! ```text
+//! seconds;score;ending;fuel;score;entities;column;lane
+//! 42;310;Fuel;17,16,15;0,20,40;3,5,4;50,52,49;40,55,35
+//! ```
+//!
+//! Samples are taken once per second of play by a [`Sampler`], less often on
+//! long runs.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{canvas::Canvas, drawable::Drawable, locale::Locale};
+
+pub const HISTORY_FILE: &str = "history.log";
+
+/// Blocks a sparkline is drawn with, lowest first
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Shades of a heatmap, least time spent first
+const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Most samples kept per metric
+const MAX_SAMPLES: usize = 512;
+
+#[derive(Default)]
+pub struct RunRecord {
+    /// Time actually played, in seconds
+    pub seconds: u64,
+    pub score: u32,
+    /// How the run ended, e.g. `Fuel` or `Quit`
+    pub ending: String,
+    pub fuel: Vec<u32>,
+    pub scores: Vec<u32>,
+    /// Enemies, fuels and enemy bullets on screen
+    pub entities: Vec<u16>,
+    /// Column of the player
+    pub columns: Vec<u16>,
+    /// Where the player flew across the river, see [`Sample::lane`]
+    pub lanes: Vec<u16>,
+}
+
+/// World metrics at one point of a run
+pub struct Sample {
+    pub fuel: u32,
+    pub score: u32,
+    pub entities: u16,
+    pub column: u16,
+    /// Position across the river, from 0 on the left bank to [`MAX_LANE`]
+    /// on the right one
+    pub lane: u16,
+}
+
+/// Highest [`Sample::lane`]
+pub const MAX_LANE: u16 = 100;
+
+/// Takes a [`Sample`] every `interval` of play into a [`RunRecord`]. When a
+/// record is full the resolution is halved, so memory stays bounded however
+/// long the run.
+pub struct Sampler {
+    pub record: RunRecord,
+    interval: Duration,
+    next: Duration,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Sampler {
+            record: RunRecord::default(),
+            interval: Duration::from_secs(1),
+            next: Duration::ZERO,
+        }
+    }
+}
+
+impl Sampler {
+    /// Whether a sample is due after `play_time` of play.
+    pub fn due(&self, play_time: Duration) -> bool {
+        play_time >= self.next
+    }
+
+    pub fn push(&mut self, sample: Sample) {
+        let record = &mut self.record;
+        if record.fuel.len() >= MAX_SAMPLES {
+            halve(&mut record.fuel);
+            halve(&mut record.scores);
+            halve(&mut record.entities);
+            halve(&mut record.columns);
+            halve(&mut record.lanes);
+            self.interval *= 2;
+        }
+
+        record.fuel.push(sample.fuel);
+        record.scores.push(sample.score);
+        record.entities.push(sample.entities);
+        record.columns.push(sample.column);
+        record.lanes.push(sample.lane);
+        self.next += self.interval;
+    }
+} // end of Sampler implementation.
+
+/// Drop every other sample of `series`.
+fn halve<T: Copy>(series: &mut Vec<T>) {
+    *series = series.iter().copied().step_by(2).collect();
+}
+
+fn parse_samples<T: FromStr>(samples: &str) -> Option<Vec<T>> {
+    samples
+        .split(',')
+        .filter(|sample| !sample.is_empty())
+        .map(|sample| sample.parse().ok())
+        .collect()
+}
+
+fn join_samples<T: ToString>(samples: &[T]) -> String {
+    samples
+        .iter()
+        .map(|sample| sample.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Draw `values` in at most `width` columns, scaled between their minimum and
+/// maximum.
+pub fn sparkline<T: Copy + Ord + Into<u64>>(values: &[T], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    let min: u64 = (*values.iter().min().unwrap()).into();
+    let max: u64 = (*values.iter().max().unwrap()).into();
+    let range = (max - min).max(1);
+    let columns = values.len().min(width);
+
+    (0..columns)
+        .map(|column| values[column * values.len() / columns])
+        .map(|value| value.into() - min)
+        .map(|value| SPARKS[(value * (SPARKS.len() as u64 - 1) / range) as usize])
+        .collect()
+}
+
+impl RunRecord {
+    fn parse(line: &str) -> Option<RunRecord> {
+        let mut fields = line.split(';');
+        let record = RunRecord {
+            seconds: fields.next()?.parse().ok()?,
+            score: fields.next()?.parse().ok()?,
+            ending: fields.next()?.to_string(),
+            fuel: parse_samples(fields.next()?)?,
+            scores: parse_samples(fields.next()?)?,
+            // missing from records older than these metrics
+            entities: parse_samples(fields.next().unwrap_or_default())?,
+            columns: parse_samples(fields.next().unwrap_or_default())?,
+            lanes: parse_samples(fields.next().unwrap_or_default())?,
+        };
+        Some(record)
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "{};{};{};{};{};{};{};{}\n",
+            self.seconds,
+            self.score,
+            self.ending,
+            join_samples(&self.fuel),
+            join_samples(&self.scores),
+            join_samples(&self.entities),
+            join_samples(&self.columns),
+            join_samples(&self.lanes)
+        )
+    }
+
+    /// Runs saved in `path`, oldest first. Broken lines are skipped.
+    pub fn load_all(path: impl AsRef<Path>) -> std::io::Result<Vec<RunRecord>> {
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .filter_map(RunRecord::parse)
+            .collect())
+    }
+
+    pub fn append(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(self.to_line().as_bytes())
+    }
+
+    /// One line summary, as listed in the history menu
+    pub fn label(&self, locale: &Locale) -> String {
+        format!(
+            "{}  {:>6}  {}",
+            locale.duration(Duration::from_secs(self.seconds)),
+            locale.number(self.score),
+            self.ending
+        )
+    }
+} // end of RunRecord implementation.
+
+/// How often `values`, between `0` and `max`, fell in each of `width`
+/// columns, as shades: the darker, the more often.
+pub fn heatmap(values: &[u16], max: u16, width: usize) -> String {
+    let mut counts = vec![0usize; width];
+    for value in values {
+        let column = *value.min(&max) as usize * width.saturating_sub(1) / max.max(1) as usize;
+        if let Some(count) = counts.get_mut(column) {
+            *count += 1;
+        }
+    }
+    let most = counts.iter().copied().max().unwrap_or(0).max(1);
+    counts
+        .into_iter()
+        // any time at all shows
+        .map(|count| SHADES[(count * (SHADES.len() - 1)).div_ceil(most)])
+        .collect()
+}
+
+/// Where the player spent a run across the river, between its banks.
+pub struct Heatmap {
+    /// Left end of the strip
+    pub at: (u16, u16),
+    pub strip: String,
+}
+
+impl Drawable for Heatmap {
+    fn draw(&self, sc: &mut Canvas) {
+        let (c, l) = self.at;
+        let (bank, river) = (sc.theme().bank, sc.theme().river);
+        let width = self.strip.chars().count() as u16;
+        sc.draw_styled_char((c, l), '▐', bank)
+            .draw_styled_line((c + 1, l), self.strip.clone(), river)
+            .draw_styled_char((c + 1 + width, l), '▌', bank);
+    }
+}
+
+/// The detail view of a run: a centered panel with its graphs.
+pub struct RunDetail<'a> {
+    pub record: &'a RunRecord,
+    pub locale: Locale,
+}
+
+impl Drawable for RunDetail<'_> {
+    fn draw(&self, sc: &mut Canvas) {
+        let record = self.record;
+        let (maxc, maxl) = sc.size();
+        let width = (maxc as usize).saturating_sub(4).min(60);
+        let graph_width = width.saturating_sub(10);
+        let lines = [
+            format!("Run  {}", record.label(&self.locale)),
+            String::new(),
+            format!("Fuel   {}", sparkline(&record.fuel, graph_width)),
+            format!("Score  {}", sparkline(&record.scores, graph_width)),
+            format!("Crowd  {}", sparkline(&record.entities, graph_width)),
+            format!("Column {}", sparkline(&record.columns, graph_width)),
+            // the heatmap goes over this line
+            "River".to_string(),
+            String::new(),
+            "Press any key to go back".to_string(),
+        ];
+
+        let c = (maxc / 2).saturating_sub(width as u16 / 2 + 1);
+        let mut l = (maxl / 2).saturating_sub(lines.len() as u16 / 2 + 1);
+        let frame = sc.theme().popup;
+
+        sc.draw_styled_line((c, l), format!("╔{}╗", "═".repeat(width)), frame);
+        for line in lines {
+            l += 1;
+            sc.draw_styled_line((c, l), format!("║ {:<w$} ║", line, w = width - 2), frame);
+        }
+        l += 1;
+        sc.draw_styled_line((c, l), format!("╚{}╝", "═".repeat(width)), frame);
+
+        // older records have no lanes
+        if !record.lanes.is_empty() {
+            sc.draw(&Heatmap {
+                at: (c + 9, l - 3),
+                strip: heatmap(&record.lanes, MAX_LANE, graph_width.saturating_sub(2)),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{heatmap, sparkline, RunRecord, Sample, Sampler, MAX_LANE, MAX_SAMPLES};
+    use crate::locale::Locale;
+    use std::time::Duration;
+
+    #[test]
+    fn record_round_trip() {
+        let record = RunRecord {
+            seconds: 3,
+            score: 40,
+            ending: "Fuel".to_string(),
+            fuel: vec![17, 16, 15],
+            scores: vec![0, 20, 40],
+            ..Default::default()
+        };
+
+        let loaded = RunRecord::parse(record.to_line().trim_end()).unwrap();
+        let locale = Locale::default();
+        assert_eq!(loaded.label(&locale), record.label(&locale));
+        assert_eq!(loaded.fuel, record.fuel);
+        assert_eq!(sparkline(&loaded.scores, 10), "▁▄█");
+        assert!(RunRecord::parse("3;40;Fuel;x;").is_none());
+
+        // mostly hugging the left bank
+        let lanes = [0, 0, 5, 10, 0, 100, 50];
+        assert_eq!(heatmap(&lanes, MAX_LANE, 5), "█ ░ ░");
+        assert_eq!(heatmap(&[], MAX_LANE, 3), "   ");
+    }
+
+    #[test]
+    fn sampler_stays_bounded() {
+        let mut sampler = Sampler::default();
+        let mut play_time = Duration::ZERO;
+        for tick in 0..10_000 {
+            if sampler.due(play_time) {
+                sampler.push(Sample {
+                    fuel: 0,
+                    score: tick,
+                    entities: 0,
+                    column: 0,
+                    lane: 0,
+                });
+            }
+            play_time += Duration::from_millis(100);
+        }
+
+        assert!(sampler.record.scores.len() <= MAX_SAMPLES);
+        assert_eq!(sampler.record.scores.len(), sampler.record.columns.len());
+        // samples stay in order of time
+        assert!(sampler.record.scores.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -0,0 +1,105 @@
+//! A short recorded input pattern (a dodge, a strafe and shoot) replayed
+//! with a single key, for players who find the pattern hard to key in.
+//!
+//! Press the record key, fly the pattern, press it again; the macro key then
+//! plays it back tick by tick. Runs that played a macro are marked assisted
+//! on the high-score board.
+
+use crate::keymap::Action;
+
+/// Longest macro, in ticks
+pub const MAX_TICKS: usize = 100;
+
+#[derive(Default)]
+pub struct InputMacro {
+    /// Actions of each tick recorded so far, `None` when not recording
+    recording: Option<Vec<Vec<Action>>>,
+    /// The last finished recording
+    recorded: Vec<Vec<Action>>,
+    /// Ticks of `recorded` left to play back, in order
+    playing: Vec<Vec<Action>>,
+}
+
+impl InputMacro {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.playing.is_empty()
+    }
+
+    /// Start recording, or stop and keep what was recorded.
+    pub fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            Some(ticks) => self.recorded = ticks,
+            None if !self.is_playing() => self.recording = Some(vec![Vec::new()]),
+            None => {}
+        }
+    }
+
+    /// Note an action of the current tick, if recording.
+    pub fn record(&mut self, action: Action) {
+        if let Some(tick) = self.recording.as_mut().and_then(|ticks| ticks.last_mut()) {
+            tick.push(action);
+        }
+    }
+
+    /// Start playing the recording back. Returns `false` if there is none.
+    pub fn play(&mut self) -> bool {
+        if self.is_recording() || self.recorded.iter().all(Vec::is_empty) {
+            return false;
+        }
+        self.playing = self.recorded.iter().rev().cloned().collect();
+        true
+    }
+
+    /// Actions to replay this tick.
+    pub fn next_tick(&mut self) -> Vec<Action> {
+        self.playing.pop().unwrap_or_default()
+    }
+
+    /// A tick went by: actions from now on belong to the next one. Recording
+    /// stops by itself at [`MAX_TICKS`].
+    pub fn end_tick(&mut self) {
+        if let Some(ticks) = &mut self.recording {
+            if ticks.len() < MAX_TICKS {
+                ticks.push(Vec::new());
+            } else {
+                self.toggle_recording();
+            }
+        }
+    }
+} // end of InputMacro implementation.
+
+#[cfg(test)]
+mod tests {
+    use super::{InputMacro, MAX_TICKS};
+    use crate::keymap::Action;
+
+    #[test]
+    fn plays_back_tick_by_tick() {
+        let mut input_macro = InputMacro::default();
+        assert!(!input_macro.play());
+
+        input_macro.toggle_recording();
+        input_macro.record(Action::MoveLeft);
+        input_macro.end_tick();
+        input_macro.end_tick();
+        input_macro.record(Action::Shoot);
+        input_macro.toggle_recording();
+
+        assert!(input_macro.play());
+        assert_eq!(input_macro.next_tick(), [Action::MoveLeft]);
+        assert!(input_macro.next_tick().is_empty());
+        assert_eq!(input_macro.next_tick(), [Action::Shoot]);
+        assert!(!input_macro.is_playing());
+
+        input_macro.toggle_recording();
+        for _ in 0..MAX_TICKS {
+            input_macro.record(Action::MoveUp);
+            input_macro.end_tick();
+        }
+        assert!(!input_macro.is_recording());
+    }
+}
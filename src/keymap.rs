@@ -0,0 +1,224 @@
+//! Key bindings.
+//!
+//! Keys are mapped to game [`Action`]s instead of being matched directly, so
+//! they can be rebound at runtime or from a `keys.conf` file:
+//!
+//! ```text
+//! # action = key, key...
+//! shoot = f
+//! pause = esc, p
+//! ```
+//!
+//! A binding replaces the keys the action had before. Menus follow the
+//! bindings of up, down and shoot, and the arrows and Enter when they are
+//! not bound to anything.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use crossterm::event::KeyCode;
+
+pub const KEYS_FILE: &str = "keys.conf";
+
+/// Controls offered on the first run, as the `keys.conf` lines they put in
+/// place of the default bindings
+pub const PRESETS: [(&str, &str); 3] = [
+    ("Arrows and WASD", ""),
+    ("Vim keys (hjkl)", "left = h\ndown = j\nup = k\nright = l\n"),
+    (
+        "Number pad (8456)",
+        "up = 8\nleft = 4\ndown = 5\nright = 6\n",
+    ),
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Shoot,
+    Pause,
+    Quit,
+    /// Start or stop recording the input macro
+    Record,
+    /// Play the recorded input macro back
+    PlayMacro,
+    /// Clear the screen of enemies, see [`crate::entities::Player::bombs`]
+    Bomb,
+    /// Fire a homing missile, see [`crate::entities::Player::missiles`]
+    Missile,
+}
+
+impl Action {
+    fn from_name(name: &str) -> Option<Action> {
+        match name {
+            "up" => Some(Action::MoveUp),
+            "down" => Some(Action::MoveDown),
+            "left" => Some(Action::MoveLeft),
+            "right" => Some(Action::MoveRight),
+            "shoot" => Some(Action::Shoot),
+            "pause" => Some(Action::Pause),
+            "quit" => Some(Action::Quit),
+            "record" => Some(Action::Record),
+            "macro" => Some(Action::PlayMacro),
+            "bomb" => Some(Action::Bomb),
+            "missile" => Some(Action::Missile),
+            _ => None,
+        }
+    }
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    match name {
+        "space" => Some(KeyCode::Char(' ')),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "esc" => Some(KeyCode::Esc),
+        "enter" => Some(KeyCode::Enter),
+        "tab" => Some(KeyCode::Tab),
+        _ => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Some(KeyCode::Char(ch)),
+                _ => None,
+            }
+        }
+    }
+}
+
+pub struct KeyMap {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut keymap = KeyMap {
+            bindings: HashMap::new(),
+        };
+
+        keymap
+            .bind(&[KeyCode::Char('w'), KeyCode::Up], Action::MoveUp)
+            .bind(&[KeyCode::Char('s'), KeyCode::Down], Action::MoveDown)
+            .bind(&[KeyCode::Char('a'), KeyCode::Left], Action::MoveLeft)
+            .bind(&[KeyCode::Char('d'), KeyCode::Right], Action::MoveRight)
+            .bind(&[KeyCode::Char(' ')], Action::Shoot)
+            .bind(&[KeyCode::Char('b')], Action::Bomb)
+            .bind(&[KeyCode::Char('v')], Action::Missile)
+            .bind(&[KeyCode::Char('p'), KeyCode::Esc], Action::Pause)
+            .bind(&[KeyCode::Char('q')], Action::Quit)
+            .bind(&[KeyCode::Char('r')], Action::Record)
+            .bind(&[KeyCode::Char('m')], Action::PlayMacro);
+
+        keymap
+    }
+}
+
+impl KeyMap {
+    /// Default bindings overridden by the `action = key, ...` lines of `path`.
+    /// Unknown actions and keys are ignored.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<KeyMap> {
+        let mut keymap = KeyMap::default();
+        keymap.apply(&fs::read_to_string(path)?);
+        Ok(keymap)
+    }
+
+    /// Lines of `path` that [`KeyMap::load`] would ignore.
+    pub fn check(path: impl AsRef<Path>) -> std::io::Result<Vec<String>> {
+        Ok(KeyMap::default().apply(&fs::read_to_string(path)?))
+    }
+
+    /// Apply every binding of `config`, returning the lines not understood.
+    fn apply(&mut self, config: &str) -> Vec<String> {
+        let mut ignored = Vec::new();
+        for line in config.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let binding = line.split_once('=').and_then(|(action, keys)| {
+                let action = Action::from_name(&action.trim().to_lowercase())?;
+                let keys = keys
+                    .split(',')
+                    .map(|key| key_from_name(&key.trim().to_lowercase()))
+                    .collect::<Option<Vec<KeyCode>>>()?;
+                Some((action, keys))
+            });
+            match binding {
+                Some((action, keys)) => {
+                    self.bind(&keys, action);
+                }
+                None => ignored.push(line.to_string()),
+            }
+        }
+        ignored
+    }
+
+    /// Bind `keys` to `action`, in place of the keys it had and of whatever
+    /// `keys` did before.
+    pub fn bind(&mut self, keys: &[KeyCode], action: Action) -> &mut KeyMap {
+        self.bindings.retain(|_, bound| *bound != action);
+        for key in keys {
+            self.bindings.insert(*key, action);
+        }
+        self
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.bindings.get(&key).copied()
+    }
+
+    /// The action of `key` in a menu: its binding, or moving and choosing for
+    /// arrows and Enter that are not bound to anything.
+    pub fn menu_action(&self, key: KeyCode) -> Option<Action> {
+        self.action(key).or(match key {
+            KeyCode::Up => Some(Action::MoveUp),
+            KeyCode::Down => Some(Action::MoveDown),
+            KeyCode::Enter => Some(Action::Shoot),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, KeyMap};
+    use crossterm::event::KeyCode;
+
+    #[test]
+    fn config_overrides_defaults() {
+        let mut keymap = KeyMap::default();
+        let ignored = keymap.apply("# comment\nshoot = f\npause = ESC\nunknown = x\nquit = nope");
+
+        assert_eq!(ignored, ["unknown = x", "quit = nope"]);
+        assert_eq!(keymap.action(KeyCode::Char('f')), Some(Action::Shoot));
+        assert_eq!(keymap.action(KeyCode::Esc), Some(Action::Pause));
+        assert_eq!(keymap.action(KeyCode::Char('x')), None);
+        // the action's old keys are gone, the others' stay in place
+        assert_eq!(keymap.action(KeyCode::Char(' ')), None);
+        assert_eq!(keymap.action(KeyCode::Char('p')), None);
+        assert_eq!(keymap.action(KeyCode::Char('w')), Some(Action::MoveUp));
+
+        assert_eq!(
+            keymap.apply("up = k, up\ndown = j, nope"),
+            ["down = j, nope"]
+        );
+        assert_eq!(keymap.action(KeyCode::Char('k')), Some(Action::MoveUp));
+        assert_eq!(keymap.action(KeyCode::Up), Some(Action::MoveUp));
+        assert_eq!(keymap.action(KeyCode::Char('w')), None);
+    }
+
+    #[test]
+    fn menus_follow_the_bindings() {
+        let mut keymap = KeyMap::default();
+        keymap.apply("up = k\ndown = j\nshoot = s");
+        assert_eq!(keymap.menu_action(KeyCode::Char('k')), Some(Action::MoveUp));
+        assert_eq!(keymap.menu_action(KeyCode::Char('s')), Some(Action::Shoot));
+        // arrows and Enter still work in menus once free
+        assert_eq!(keymap.menu_action(KeyCode::Up), Some(Action::MoveUp));
+        assert_eq!(keymap.menu_action(KeyCode::Enter), Some(Action::Shoot));
+        assert_eq!(keymap.menu_action(KeyCode::Char('w')), None);
+    }
+}
@@ -0,0 +1,149 @@
+//! How numbers and durations are written on screen and in summaries: the
+//! thousands separator of the player's locale (`LC_ALL`, `LC_NUMERIC` or
+//! `LANG`, or `locale = de_DE` in `settings.conf`), and durations as `mm:ss`
+//! or, with `durations = verbose`, as `1h 02m 05s`.
+
+use std::time::Duration;
+
+/// Languages grouping thousands with a dot, a space or an apostrophe; the
+/// others use a comma.
+const DOT: [&str; 12] = [
+    "da", "de", "el", "es", "id", "it", "nl", "pt", "ro", "sl", "tr", "vi",
+];
+const SPACE: [&str; 12] = [
+    "cs", "et", "fi", "fr", "hu", "lt", "lv", "nb", "pl", "ru", "sk", "sv",
+];
+const APOSTROPHE: [&str; 2] = ["de_CH", "it_CH"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TimeStyle {
+    /// `mm:ss`
+    #[default]
+    Clock,
+    /// `1h 02m 05s`
+    Verbose,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Locale {
+    /// Between groups of three digits, none for the `C` locale
+    pub separator: Option<char>,
+    pub time: TimeStyle,
+}
+
+impl Locale {
+    /// The locale named by `tag`, such as `fr_FR.UTF-8`, `en-US` or `C`.
+    pub fn from_tag(tag: &str) -> Locale {
+        let tag = tag
+            .split(['.', '@'])
+            .next()
+            .unwrap_or_default()
+            .replace('-', "_");
+        let language = tag.split('_').next().unwrap_or_default();
+        let separator = if tag.is_empty() || tag == "C" || tag == "POSIX" {
+            None
+        } else if APOSTROPHE.contains(&tag.as_str()) {
+            Some('\'')
+        } else if DOT.contains(&language) {
+            Some('.')
+        } else if SPACE.contains(&language) {
+            Some(' ')
+        } else {
+            Some(',')
+        };
+        Locale {
+            separator,
+            ..Locale::default()
+        }
+    }
+
+    /// The locale numbers are written in, from the environment.
+    pub fn detect() -> Locale {
+        // the first of these that is set wins
+        let tag = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+            .unwrap_or_default();
+        Locale::from_tag(&tag)
+    }
+
+    /// `number` with its thousands grouped.
+    pub fn number(&self, number: impl Into<u64>) -> String {
+        let digits = number.into().to_string();
+        let Some(separator) = self.separator else {
+            return digits;
+        };
+        let mut grouped = String::new();
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index) % 3 == 0 {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// `duration` to the second.
+    pub fn duration(&self, duration: Duration) -> String {
+        let seconds = duration.as_secs();
+        match self.time {
+            TimeStyle::Clock => format!("{:02}:{:02}", seconds / 60, seconds % 60),
+            TimeStyle::Verbose if seconds >= 3600 => format!(
+                "{}h {:02}m {:02}s",
+                seconds / 3600,
+                seconds % 3600 / 60,
+                seconds % 60
+            ),
+            TimeStyle::Verbose if seconds >= 60 => {
+                format!("{}m {:02}s", seconds / 60, seconds % 60)
+            }
+            TimeStyle::Verbose => format!("{seconds}s"),
+        }
+    }
+
+    /// `at` to the tenth of a second, as logs write it.
+    pub fn timestamp(&self, at: Duration) -> String {
+        let tenths = at.subsec_millis() / 100;
+        let mut text = self.duration(at);
+        match self.time {
+            TimeStyle::Clock => format!("{text}.{tenths}"),
+            TimeStyle::Verbose => {
+                // the tenths go before the unit
+                text.pop();
+                format!("{text}.{tenths}s")
+            }
+        }
+    }
+} // end of Locale implementation.
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Locale, TimeStyle};
+
+    #[test]
+    fn numbers_and_durations_follow_the_locale() {
+        assert_eq!(Locale::from_tag("C").number(1234567u32), "1234567");
+        assert_eq!(
+            Locale::from_tag("en_US.UTF-8").number(1234567u32),
+            "1,234,567"
+        );
+        assert_eq!(Locale::from_tag("de-DE").number(1234u32), "1.234");
+        assert_eq!(Locale::from_tag("fr_FR.UTF-8").number(999u32), "999");
+        assert_eq!(Locale::from_tag("fr_FR.UTF-8").number(123456u32), "123 456");
+        assert_eq!(Locale::from_tag("de_CH").number(10000u32), "10'000");
+
+        let mut locale = Locale::default();
+        let played = Duration::from_secs(3725);
+        assert_eq!(locale.duration(played), "62:05");
+        locale.time = TimeStyle::Verbose;
+        assert_eq!(locale.duration(played), "1h 02m 05s");
+        assert_eq!(locale.duration(Duration::from_secs(65)), "1m 05s");
+        assert_eq!(locale.duration(Duration::from_secs(9)), "9s");
+
+        let at = Duration::from_millis(72_450);
+        assert_eq!(locale.timestamp(at), "1m 12.4s");
+        assert_eq!(Locale::default().timestamp(at), "01:12.4");
+    }
+}
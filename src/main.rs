@@ -7,41 +7,241 @@ use crossterm::{
     ExecutableCommand,
 };
 
+#[cfg(feature = "alloc-stats")]
+mod alloc_stats;
+mod arith;
+mod behavior;
+mod biome;
+mod blocks;
 mod canvas;
+mod card;
+mod challenge;
+mod color_depth;
+mod combat_log;
+mod combo;
+mod control;
+mod doctor;
 mod drawable;
+mod editor;
 mod entities;
 mod events;
+mod feedback;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod highscores;
+mod history;
+mod input_macro;
+mod keymap;
+mod locale;
+mod menu;
+mod onboarding;
+mod pacing;
+mod pack;
+mod renderer;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod season;
+mod settings;
+mod sprite;
+mod stage;
 mod stout_ext;
+mod theme;
+mod tips;
+mod track;
+mod versus;
+mod webhook;
 mod world;
 
 use events::*;
 use world::*;
 
+/// The value given with `<flag> <value>`, if any.
+fn arg_value(flag: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != flag).nth(1)
+}
+
+/// The seed given with `--seed <number>`, if any.
+fn seed_from_args() -> Option<u64> {
+    arg_value("--seed")?.parse().ok()
+}
+
+/// Host or join over `level` the versus race asked for with `--host <port>`, or
+/// `<address:port>`, or `--join <address>`, if any. Returns it with its seed.
+fn race_from_args(level: &versus::Level) -> std::io::Result<Option<(versus::Versus, u64)>> {
+    let invalid = |what| std::io::Error::new(std::io::ErrorKind::InvalidInput, what);
+    if let Some(at) = arg_value("--host") {
+        let target = match arg_value("--target") {
+            Some(target) => target
+                .parse()
+                .map_err(|_| invalid("--target needs a distance"))?,
+            None => versus::DEFAULT_TARGET,
+        };
+        let seed = seed_from_args().unwrap_or_else(rand::random);
+        println!("Waiting for a rival at {at}...");
+        Ok(Some((
+            versus::Versus::host(&at, seed, target, level)?,
+            seed,
+        )))
+    } else if let Some(address) = arg_value("--join") {
+        println!("Joining the race at {address}...");
+        versus::Versus::join(&address, level).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
 fn main() -> std::io::Result<()> {
-    // init the screen
     let mut sc = stdout();
+    if std::env::args().any(|arg| arg == "--doctor") {
+        return doctor::report(&mut sc);
+    }
+    let problems: Vec<_> = doctor::quick()
+        .into_iter()
+        .filter(|finding| !finding.ok)
+        .collect();
+    let track = match arg_value("--track") {
+        Some(path) => Some(
+            track::Track::from_file(&path)
+                .map_err(|error| std::io::Error::new(error.kind(), format!("{path}: {error}")))?,
+        ),
+        None => None,
+    };
+    #[cfg(feature = "scripting")]
+    let script = match arg_value("--script") {
+        Some(path) => Some(scripting::Script::load(&path).map_err(|error| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{path}: {error}"))
+        })?),
+        None => None,
+    };
+    // before the screen is taken over, hosting waits for the rival
+    let level = versus::Level {
+        width: size()?.0,
+        track: track
+            .as_ref()
+            .map(|track| content_hash(track.text().as_bytes())),
+        #[cfg(feature = "scripting")]
+        script: script.as_ref().map(scripting::Script::hash),
+        #[cfg(not(feature = "scripting"))]
+        script: None,
+    };
+    let race = race_from_args(&level)?;
+    let control = match arg_value("--control") {
+        Some(path) => Some(
+            control::open(&path)
+                .map_err(|error| std::io::Error::new(error.kind(), format!("{path}: {error}")))?,
+        ),
+        None => None,
+    };
+
+    // no settings yet, this is the first run
+    let first_run = !std::path::Path::new(settings::SETTINGS_FILE).exists();
+
+    // init the screen
     let (maxc, maxl) = size().unwrap();
     sc.execute(Hide)?;
     enable_raw_mode()?;
 
+    // the track editor instead of a game
+    if let Some(path) = arg_value("--edit-track") {
+        let settings = settings::Settings::load(settings::SETTINGS_FILE).unwrap_or_default();
+        let depth = color_depth::ColorDepth::detect();
+        let theme = settings.theme(depth).fitted(depth);
+        let edited = editor::run(&mut sc, path.into(), (maxc, maxl), theme);
+        sc.clear_all()?.execute(Show)?;
+        disable_raw_mode()?;
+        return edited;
+    }
+
     // init the world
-    let slowness = 60;
     let mut world = World::new(maxc, maxl);
+    if let Ok(keymap) = keymap::KeyMap::load(keymap::KEYS_FILE) {
+        world.keymap = keymap;
+    }
+    if let Ok(settings) = settings::Settings::load(settings::SETTINGS_FILE) {
+        world.settings = settings;
+        world.apply_settings();
+    }
+    if let Ok(biomes) = biome::Biome::load(biome::BIOMES_FILE) {
+        world.biomes = biomes;
+        world.apply_settings();
+    }
+    let stages =
+        stage::Stage::load(stage::STAGES_FILE).unwrap_or_else(|_| stage::Stage::built_in());
+    world.stages = stage::StageManager::new(stages);
+    if world.settings.packs {
+        let packs = pack::ContentPack::load(pack::PACKS_FILE)
+            .unwrap_or_else(|_| pack::ContentPack::built_in());
+        if let Some(pack) = pack::ContentPack::active(packs, card::month_day()) {
+            world.set_pack(pack);
+        }
+    }
+    if let Ok(tips) = tips::Tips::load(tips::TIPS_FILE) {
+        world.tips = tips;
+    }
+    if let Some(url) = &world.settings.events_url {
+        world.event_feed = Some(season::fetch(url.clone()));
+    }
+    let race_seed = race.as_ref().map(|(_, seed)| *seed);
+    if let Some(seed) = race_seed.or(seed_from_args()).or(world.settings.seed) {
+        world.set_seed(seed);
+    }
+    #[cfg(feature = "scripting")]
+    {
+        world.script = script;
+    }
+    if let Some(track) = track {
+        world.set_track(track);
+    }
+    if arg_value("--save-track").is_some() {
+        world.record_track();
+    }
+    if let Some(file) = control {
+        world.control = Some(control::listen(file));
+    }
+    if let Some((versus, _)) = race {
+        world.versus = Some(versus);
+        world.add_hud_widget(VersusWidget::default());
+    }
+
+    if first_run {
+        world.onboarding_screen(&mut sc)?;
+    }
 
     // show welcoming banner
-    world.welcome_screen(&mut sc)?;
+    world.welcome_screen(&mut sc, &problems)?;
 
     // Main game loop
     // - Events
     // - Physics
     // - Drawing
-    world.game_loop(&mut sc, slowness)?;
+    let outcome = world.game_loop(&mut sc);
 
     // game is finished
+    if let GameOutcome::Error(error) = outcome {
+        // give the terminal back before reporting
+        sc.clear_all()?.execute(Show)?;
+        disable_raw_mode()?;
+        return Err(error);
+    }
+
     world.clear_screen(&mut sc)?;
-    world.goodbye_screen(&mut sc)?;
+    world.goodbye_screen(&mut sc, &outcome)?;
+    world.card_screen(&mut sc)?;
+    world.highscore_screen(&mut sc)?;
 
     sc.clear_all()?.execute(Show)?;
     disable_raw_mode()?;
+
+    if let (Some(path), Some(track)) = (arg_value("--save-track"), &world.recording) {
+        if let Err(error) = track.to_file(&path) {
+            eprintln!("Could not save the track to {path}: {error}");
+        }
+    }
+    // only once the terminal is back, so curl can report on it
+    if let Some(url) = &world.settings.webhook {
+        if let Err(error) = world.summary(&outcome).post(url) {
+            eprintln!("Could not post the run summary: {error}");
+        }
+    }
     Ok(())
 }
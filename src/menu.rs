@@ -0,0 +1,163 @@
+//! Focus and selection shared by every interactive scene (menus, dialogs).
+//!
+//! A [`Menu`] keeps track of the focused item, wraps around at both ends and
+//! skips disabled items. Scenes feed it actions, from keys through
+//! [`crate::keymap::KeyMap::menu_action`], and react to the returned
+//! [`MenuEvent`].
+
+use crate::{canvas::Canvas, drawable::Drawable, keymap::Action};
+
+pub struct MenuItem {
+    pub label: String,
+    pub enabled: bool,
+}
+
+pub enum MenuEvent {
+    /// Focus moved to another item
+    Moved,
+    /// The item at this index was activated
+    Activated(usize),
+}
+
+pub struct Menu {
+    pub title: String,
+    pub items: Vec<MenuItem>,
+    focused: usize,
+}
+
+impl Menu {
+    pub fn new(title: impl Into<String>, labels: &[&str]) -> Self {
+        Menu {
+            title: title.into(),
+            items: labels
+                .iter()
+                .map(|label| MenuItem {
+                    label: label.to_string(),
+                    enabled: true,
+                })
+                .collect(),
+            focused: 0,
+        }
+    }
+
+    /// Move the focus by `step` items, wrapping around and skipping disabled
+    /// ones. Returns `false` if the focus did not change.
+    fn move_focus(&mut self, step: isize) -> bool {
+        let len = self.items.len() as isize;
+        let mut index = self.focused as isize;
+
+        for _ in 0..len {
+            index = (index + step).rem_euclid(len);
+            if self.items[index as usize].enabled {
+                let moved = index as usize != self.focused;
+                self.focused = index as usize;
+                return moved;
+            }
+        }
+
+        false
+    }
+
+    pub fn focus_next(&mut self) -> bool {
+        self.move_focus(1)
+    }
+
+    pub fn focus_prev(&mut self) -> bool {
+        self.move_focus(-1)
+    }
+
+    /// Put the focus back on the first enabled item.
+    pub fn reset(&mut self) {
+        self.focused = 0;
+        if !self.items.is_empty() && !self.items[0].enabled {
+            self.focus_next();
+        }
+    }
+
+    /// Move the focus up or down, or shoot to activate the focused item.
+    pub fn handle_action(&mut self, action: Action) -> Option<MenuEvent> {
+        match action {
+            Action::MoveUp if self.focus_prev() => Some(MenuEvent::Moved),
+            Action::MoveDown if self.focus_next() => Some(MenuEvent::Moved),
+            Action::Shoot
+                if self
+                    .items
+                    .get(self.focused)
+                    .is_some_and(|item| item.enabled) =>
+            {
+                Some(MenuEvent::Activated(self.focused))
+            }
+            _ => None,
+        }
+    }
+} // end of Menu implementation.
+
+impl Drawable for Menu {
+    fn draw(&self, sc: &mut Canvas) {
+        let (maxc, maxl) = sc.size();
+        let width = self
+            .items
+            .iter()
+            .map(|item| item.label.chars().count())
+            .chain([self.title.chars().count()])
+            .max()
+            .unwrap_or(0)
+            + 4;
+        let height = self.items.len() as u16 + 4;
+
+        let c = (maxc / 2).saturating_sub(width as u16 / 2 + 1);
+        let mut l = (maxl / 2).saturating_sub(height / 2);
+
+        let frame = sc.theme().popup;
+        let focus = sc.theme().popup_focus;
+        let disabled = sc.theme().popup_disabled;
+
+        sc.draw_styled_line((c, l), format!("╔{}╗", "═".repeat(width)), frame);
+        l += 1;
+        sc.draw_styled_line((c, l), format!("║{:^width$}║", self.title), frame);
+        l += 1;
+        sc.draw_styled_line((c, l), format!("╟{}╢", "─".repeat(width)), frame);
+
+        for (index, item) in self.items.iter().enumerate() {
+            l += 1;
+            sc.draw_styled_line((c, l), format!("║{}║", " ".repeat(width)), frame);
+            if index == self.focused {
+                let label = format!("▶ {:^w$} ◀", item.label, w = width - 4);
+                sc.draw_styled_line((c + 1, l), label, focus);
+            } else {
+                let style = if item.enabled { frame } else { disabled };
+                sc.draw_styled_line((c + 1, l), format!("{:^width$}", item.label), style);
+            }
+        }
+
+        l += 1;
+        sc.draw_styled_line((c, l), format!("╚{}╝", "═".repeat(width)), frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Menu, MenuEvent};
+    use crate::keymap::Action;
+
+    #[test]
+    fn focus_wraps_around() {
+        let mut menu = Menu::new("Test", &["One", "Two", "Three"]);
+        assert!(menu.focus_prev());
+        assert_eq!(menu.focused, 2);
+        assert!(menu.focus_next());
+        assert_eq!(menu.focused, 0);
+    }
+
+    #[test]
+    fn focus_skips_disabled_items() {
+        let mut menu = Menu::new("Test", &["One", "Two", "Three"]);
+        menu.items[1].enabled = false;
+        menu.focus_next();
+        assert_eq!(menu.focused, 2);
+        assert!(matches!(
+            menu.handle_action(Action::Shoot),
+            Some(MenuEvent::Activated(2))
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+//! First-run onboarding: when there is no `settings.conf` yet, a few menus
+//! ask for the controls, the theme and the difficulty before the first game,
+//! and the answers are written to the config files so they are asked once.
+
+use crossterm::event::KeyCode;
+
+use crate::{
+    keymap::{KeyMap, PRESETS},
+    menu::{Menu, MenuEvent},
+    settings::{Settings, SPEEDS},
+    theme::Theme,
+};
+
+/// A question of the onboarding
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Step {
+    Controls,
+    Theme,
+    Difficulty,
+}
+
+const STEPS: [Step; 3] = [Step::Controls, Step::Theme, Step::Difficulty];
+
+pub struct Onboarding {
+    menu: Menu,
+    step: usize,
+    /// Item picked at each step so far
+    choices: Vec<usize>,
+}
+
+impl Onboarding {
+    pub fn new() -> Self {
+        Onboarding {
+            menu: Onboarding::menu_of(STEPS[0]),
+            step: 0,
+            choices: Vec::new(),
+        }
+    }
+
+    fn menu_of(step: Step) -> Menu {
+        let labels: Vec<String> = match step {
+            Step::Controls => PRESETS.iter().map(|(name, _)| name.to_string()).collect(),
+            Step::Theme => Theme::all()
+                .iter()
+                .map(|theme| theme.name.to_string())
+                .collect(),
+            Step::Difficulty => SPEEDS.iter().map(|(name, _)| name.to_string()).collect(),
+        };
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let title = match step {
+            Step::Controls => "Controls",
+            Step::Theme => "Theme",
+            Step::Difficulty => "Difficulty (speed)",
+        };
+        let mut menu = Menu::new(title, &labels);
+        if step == Step::Difficulty {
+            // the default speed comes first to hand
+            let tick_ms = Settings::default().tick_ms;
+            let index = SPEEDS.iter().position(|(_, speed)| *speed == tick_ms);
+            for _ in 0..index.unwrap_or(0) {
+                menu.focus_next();
+            }
+        }
+        menu
+    }
+
+    /// The question being asked
+    pub fn menu(&self) -> &Menu {
+        &self.menu
+    }
+
+    /// `(step, steps)` for the hint line, counting from 1
+    pub fn progress(&self) -> (usize, usize) {
+        (self.step + 1, STEPS.len())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.step >= STEPS.len()
+    }
+
+    /// Answer the question with `code`, bound in `keymap`; Esc keeps the
+    /// defaults for the rest.
+    pub fn handle_key(&mut self, code: KeyCode, keymap: &KeyMap) -> Option<MenuEvent> {
+        if code == KeyCode::Esc {
+            self.step = STEPS.len();
+            return None;
+        }
+        let event = keymap
+            .menu_action(code)
+            .and_then(|action| self.menu.handle_action(action));
+        if let Some(MenuEvent::Activated(index)) = event {
+            self.choices.push(index);
+            self.step += 1;
+            if let Some(step) = STEPS.get(self.step) {
+                self.menu = Onboarding::menu_of(*step);
+            }
+        }
+        event
+    }
+
+    /// Put the answers in `settings`. Returns the `keys.conf` lines of the
+    /// controls picked, empty for the default ones.
+    pub fn apply(&self, settings: &mut Settings) -> &'static str {
+        let mut keys = "";
+        for (step, choice) in STEPS.iter().zip(&self.choices) {
+            match step {
+                Step::Controls => keys = PRESETS[*choice].1,
+                Step::Theme => settings.theme = Some(Theme::all()[*choice].name.to_string()),
+                Step::Difficulty => settings.tick_ms = SPEEDS[*choice].1,
+            }
+        }
+        keys
+    }
+} // end of Onboarding implementation.
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+
+    use super::Onboarding;
+    use crate::{keymap::KeyMap, settings::Settings};
+
+    #[test]
+    fn answers_end_up_in_the_settings() {
+        let keymap = KeyMap::default();
+        let mut onboarding = Onboarding::new();
+        let mut settings = Settings::default();
+        for code in [KeyCode::Down, KeyCode::Enter, KeyCode::Down, KeyCode::Enter] {
+            onboarding.handle_key(code, &keymap);
+        }
+        assert_eq!(onboarding.progress(), (3, 3));
+        // from the normal speed, one down
+        onboarding.handle_key(KeyCode::Down, &keymap);
+        onboarding.handle_key(KeyCode::Enter, &keymap);
+        assert!(onboarding.is_done());
+
+        assert!(onboarding.apply(&mut settings).contains("left = h"));
+        assert_eq!(settings.theme.as_deref(), Some("Monochrome"));
+        assert_eq!(settings.speed_name(), "Fast");
+
+        // skipping keeps the defaults
+        let mut onboarding = Onboarding::new();
+        onboarding.handle_key(KeyCode::Esc, &keymap);
+        assert!(onboarding.is_done());
+        assert_eq!(onboarding.apply(&mut Settings::default()), "");
+    }
+}
@@ -0,0 +1,51 @@
+//! Frame pacing.
+//!
+//! Reading input, simulating and drawing take time of their own, so the loop
+//! only sleeps what is left of the tick instead of a whole tick.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+pub struct FramePacer {
+    frame_started: Instant,
+    /// Smoothed frames per second actually reached
+    fps: f32,
+}
+
+impl FramePacer {
+    pub fn new() -> Self {
+        FramePacer {
+            frame_started: Instant::now(),
+            fps: 0.0,
+        }
+    }
+
+    /// Sleep until `tick` has passed since the previous frame ended, then
+    /// start timing the next one.
+    pub fn wait(&mut self, tick: Duration) {
+        if let Some(left) = tick.checked_sub(self.frame_started.elapsed()) {
+            thread::sleep(left);
+        }
+
+        let now = Instant::now();
+        let took = (now - self.frame_started).as_secs_f32();
+        self.frame_started = now;
+
+        if took > 0.0 {
+            // smooth over the last few frames so the HUD stays readable
+            self.fps = if self.fps == 0.0 {
+                1.0 / took
+            } else {
+                self.fps * 0.9 + 0.1 / took
+            };
+        }
+    }
+
+    /// Frames per second reached lately, for the debug HUD
+    #[cfg(debug_assertions)]
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+} // end of FramePacer implementation.
@@ -0,0 +1,183 @@
+//! Holiday content packs: around a holiday the river dresses up for it, with
+//! glyphs swapped for themed ones, a greeting and a banner, and some pickups
+//! turning up more often.
+//!
+//! Packs are data, read from `packs.conf` when there is one:
+//!
+//! ```text
+//! [Halloween]
+//! dates = 10-25..11-01
+//! glyphs = ♣†, ♠†
+//! greeting = The river is haunted tonight...
+//! banner = ☾ Halloween ☾
+//! pickups = bomb
+//! ```
+//!
+//! Dates are `MM-DD` and may wrap around the new year. The first pack whose
+//! dates hold today is used; `packs = off` in the settings turns them off.
+//! Runs with a seed get the look but not the pickups, so they play the same
+//! on any day.
+
+use std::{fs, path::Path};
+
+use crate::{blocks, world::SpawnKind};
+
+pub const PACKS_FILE: &str = "packs.conf";
+
+/// How much more often the pickups of a pack spawn
+pub const PICKUP_BOOST: u16 = 3;
+
+const BUILT_IN: &str = "\
+[Halloween]
+dates = 10-25..11-01
+glyphs = ♣†, ♠†
+greeting = The river is haunted tonight...
+banner = ☾ Halloween ☾
+pickups = bomb
+
+[Winter]
+dates = 12-20..01-02
+glyphs = ♣✻, ♠✻, ▴✻
+greeting = Season's greetings, pilot! Fuel is on the house.
+banner = ✻ Winter holidays ✻
+pickups = fuel
+";
+
+pub struct ContentPack {
+    pub name: String,
+    /// First and last day, as month and day
+    pub dates: ((u32, u32), (u32, u32)),
+    /// Glyphs drawn instead of others, see [`crate::canvas::Canvas::set_glyphs`]
+    pub glyphs: Vec<(char, char)>,
+    /// Shown on the title screen
+    pub greeting: Option<String>,
+    /// Shown in the HUD while playing, the name if unset
+    pub banner: Option<String>,
+    /// Pickups spawned [`PICKUP_BOOST`] times as often
+    pub pickups: Vec<SpawnKind>,
+}
+
+impl ContentPack {
+    fn new(name: &str) -> ContentPack {
+        ContentPack {
+            name: name.to_string(),
+            // no dates, never on
+            dates: ((0, 0), (0, 0)),
+            glyphs: Vec::new(),
+            greeting: None,
+            banner: None,
+            pickups: Vec::new(),
+        }
+    }
+
+    /// The packs shipped with the game
+    pub fn built_in() -> Vec<ContentPack> {
+        ContentPack::parse(BUILT_IN).0
+    }
+
+    /// The packs of `path`; the built-in ones if it has none.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Vec<ContentPack>> {
+        let packs = ContentPack::parse(&fs::read_to_string(path)?).0;
+        Ok(if packs.is_empty() {
+            ContentPack::built_in()
+        } else {
+            packs
+        })
+    }
+
+    /// Lines of `path` that [`ContentPack::load`] would ignore.
+    pub fn check(path: impl AsRef<Path>) -> std::io::Result<Vec<String>> {
+        Ok(ContentPack::parse(&fs::read_to_string(path)?).1)
+    }
+
+    /// The pack of `packs` on at `today` (month and day), if any.
+    pub fn active(packs: Vec<ContentPack>, today: (u32, u32)) -> Option<ContentPack> {
+        packs.into_iter().find(|pack| pack.holds(today))
+    }
+
+    fn holds(&self, day: (u32, u32)) -> bool {
+        let (first, last) = self.dates;
+        if first <= last {
+            first <= day && day <= last
+        } else {
+            // over the new year
+            first <= day || day <= last
+        }
+    }
+
+    /// The packs of `config`, and the lines not understood.
+    fn parse(config: &str) -> (Vec<ContentPack>, Vec<String>) {
+        blocks::parse(config, ContentPack::new, |pack, key, value| {
+            match (key, value) {
+                ("dates", dates) => parse_dates(dates).map(|dates| pack.dates = dates).is_some(),
+                ("glyphs", swaps) => {
+                    let swaps: Option<Vec<_>> = swaps.split(',').map(parse_swap).collect();
+                    swaps.map(|swaps| pack.glyphs = swaps).is_some()
+                }
+                ("greeting", text) => {
+                    pack.greeting = Some(text.to_string());
+                    true
+                }
+                ("banner", text) => {
+                    pack.banner = Some(text.to_string());
+                    true
+                }
+                ("pickups", names) => {
+                    let kinds: Option<Vec<_>> = names.split(',').map(pickup).collect();
+                    kinds.map(|kinds| pack.pickups = kinds).is_some()
+                }
+                _ => false,
+            }
+        })
+    }
+} // end of ContentPack implementation.
+
+/// `MM-DD..MM-DD`
+fn parse_dates(dates: &str) -> Option<((u32, u32), (u32, u32))> {
+    let day = |text: &str| {
+        let (month, day) = text.trim().split_once('-')?;
+        let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+        ((1..=12).contains(&month) && (1..=31).contains(&day)).then_some((month, day))
+    };
+    let (first, last) = dates.split_once("..")?;
+    Some((day(first)?, day(last)?))
+}
+
+/// Two glyphs, the one drawn and the one drawn instead
+fn parse_swap(swap: &str) -> Option<(char, char)> {
+    let mut glyphs = swap.trim().chars();
+    match (glyphs.next(), glyphs.next(), glyphs.next()) {
+        (Some(from), Some(to), None) => Some((from, to)),
+        _ => None,
+    }
+}
+
+/// The pickups a pack may boost
+fn pickup(name: &str) -> Option<SpawnKind> {
+    SpawnKind::from_name(name.trim())
+        .filter(|kind| matches!(kind, SpawnKind::Fuel | SpawnKind::Pad | SpawnKind::Bomb))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContentPack;
+    use crate::world::SpawnKind;
+
+    #[test]
+    fn packs_follow_the_calendar() {
+        let config = "[Spring]\ndates = 03-20..04-10\nglyphs = ♣✿\npickups = pad, fuel\n\
+                      colors = pink\n[Odd]\ndates = 13-01..01-01\n";
+        let (packs, ignored) = ContentPack::parse(config);
+        assert_eq!(ignored, ["colors = pink", "dates = 13-01..01-01"]);
+        assert_eq!(packs[0].glyphs, [('♣', '✿')]);
+        assert_eq!(packs[0].pickups, [SpawnKind::Pad, SpawnKind::Fuel]);
+
+        assert!(ContentPack::active(ContentPack::built_in(), (10, 16)).is_none());
+        let halloween = ContentPack::active(ContentPack::built_in(), (10, 31)).unwrap();
+        assert_eq!(halloween.name, "Halloween");
+        for day in [(12, 24), (12, 31), (1, 1)] {
+            let winter = ContentPack::active(ContentPack::built_in(), day).unwrap();
+            assert_eq!(winter.name, "Winter");
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Where the [`Canvas`](crate::canvas::Canvas) ends up.
+//!
+//! The game draws through a [`Renderer`]; any [`Write`] taking crossterm
+//! commands (the terminal, an in-memory buffer) is one, a capture buffer for
+//! tests is another, and a GUI or WASM canvas could be plugged in the same
+//! way.
+
+use std::io::Write;
+
+use crossterm::{
+    cursor::MoveTo,
+    style::{ContentStyle, Print, StyledContent},
+    terminal::{Clear, ClearType},
+    QueueableCommand,
+};
+
+pub trait Renderer {
+    /// Blank the whole output.
+    fn clear(&mut self) -> Result<(), std::io::Error>;
+
+    /// Put `content` at `(column, line)`, one cell per char.
+    fn draw_styled(
+        &mut self,
+        loc: (u16, u16),
+        content: &str,
+        style: Option<ContentStyle>,
+    ) -> Result<(), std::io::Error>;
+
+    /// Show everything drawn so far.
+    fn flush(&mut self) -> Result<(), std::io::Error>;
+}
+
+impl<W: Write> Renderer for W {
+    fn clear(&mut self) -> Result<(), std::io::Error> {
+        self.queue(Clear(ClearType::All))?;
+        Ok(())
+    }
+
+    fn draw_styled(
+        &mut self,
+        (c, l): (u16, u16),
+        content: &str,
+        style: Option<ContentStyle>,
+    ) -> Result<(), std::io::Error> {
+        self.queue(MoveTo(c, l))?;
+        match style {
+            Some(style) => self.queue(Print(StyledContent::new(style, content)))?,
+            None => self.queue(Print(content))?,
+        };
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Write::flush(self)
+    }
+}
+
+/// Keeps the characters drawn, without styles, to check a frame in tests.
+#[cfg(test)]
+pub struct CaptureRenderer {
+    pub lines: Vec<Vec<char>>,
+}
+
+#[cfg(test)]
+impl CaptureRenderer {
+    pub fn new(max_c: u16, max_l: u16) -> Self {
+        CaptureRenderer {
+            lines: vec![vec![' '; max_c as usize]; max_l as usize],
+        }
+    }
+
+    /// Line `l` as drawn, trailing blanks included
+    pub fn line(&self, l: usize) -> String {
+        self.lines[l].iter().collect()
+    }
+}
+
+#[cfg(test)]
+impl Renderer for CaptureRenderer {
+    fn clear(&mut self) -> Result<(), std::io::Error> {
+        for line in &mut self.lines {
+            line.fill(' ');
+        }
+        Ok(())
+    }
+
+    fn draw_styled(
+        &mut self,
+        (c, l): (u16, u16),
+        content: &str,
+        _style: Option<ContentStyle>,
+    ) -> Result<(), std::io::Error> {
+        if let Some(line) = self.lines.get_mut(l as usize) {
+            for (cell, ch) in line.iter_mut().skip(c as usize).zip(content.chars()) {
+                *cell = ch;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,247 @@
+//! Game modes scripted in [rhai](https://rhai.rs) (`scripting` feature),
+//! loaded with `--script <file>`.
+//!
+//! A script registers handlers for what happens in a run, the same events
+//! the combat log records, and reacts through a few calls that can not break
+//! the world:
+//!
+//! ```text
+//! on("kill", |event| {
+//!     if event.kind == "Helicopter" { send("helicopter"); }
+//! });
+//! on("section", |event| {
+//!     set_chance("fuel", 5);
+//!     popup(`Section ${event.section}: fuel runs short`);
+//! });
+//! ```
+//!
+//! Events are `kill` (`kind`, `reward`), `depot_shot`, `pickup` (`what`),
+//! `bomb` (`kills`), `hit` (`shield_left`), `section` (`section`, `biome`),
+//! `stage` (`name`, `biome`), `warp` and `death` (`cause`). `send` sends an
+//! entity down the river like a stage wave, `set_chance` overrides a spawn
+//! chance (per mille) and `popup` shows a line of text.
+
+use std::{cell::RefCell, fs, path::Path, rc::Rc};
+
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, Map, AST};
+
+use crate::{
+    combat_log::CombatEvent,
+    control::Command,
+    world::{content_hash, SpawnKind},
+};
+
+/// Most operations a handler may run for one event
+const MAX_OPERATIONS: u64 = 50_000;
+
+/// What the calls of a script leave for the game to pick up
+#[derive(Default)]
+struct Calls {
+    handlers: Vec<(String, FnPtr)>,
+    commands: Vec<Command>,
+    chances: Vec<(SpawnKind, u16)>,
+}
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    calls: Rc<RefCell<Calls>>,
+    /// Hash of the source, see [`crate::versus::Level`]
+    hash: u64,
+}
+
+impl Script {
+    /// Compile and run the script at `path`, registering its handlers.
+    pub fn load(path: impl AsRef<Path>) -> Result<Script, String> {
+        let source = fs::read_to_string(&path).map_err(|error| error.to_string())?;
+        Script::compile(&source)
+    }
+
+    fn compile(source: &str) -> Result<Script, String> {
+        let calls = Rc::new(RefCell::new(Calls::default()));
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(1000)
+            .set_max_array_size(1000)
+            .set_max_map_size(100)
+            .disable_symbol("eval");
+
+        let registry = Rc::clone(&calls);
+        engine.register_fn("on", move |event: &str, handler: FnPtr| {
+            registry
+                .borrow_mut()
+                .handlers
+                .push((event.to_string(), handler));
+        });
+        let registry = Rc::clone(&calls);
+        engine.register_fn("send", move |kind: &str| {
+            let kind = spawn_kind(kind)?;
+            registry.borrow_mut().commands.push(Command::Spawn { kind });
+            Ok::<_, Box<EvalAltResult>>(())
+        });
+        let registry = Rc::clone(&calls);
+        engine.register_fn("set_chance", move |kind: &str, chance: i64| {
+            let kind = spawn_kind(kind)?;
+            let chance = chance.clamp(0, 1000) as u16;
+            let mut calls = registry.borrow_mut();
+            calls.chances.retain(|(set, _)| *set != kind);
+            calls.chances.push((kind, chance));
+            Ok::<_, Box<EvalAltResult>>(())
+        });
+        let registry = Rc::clone(&calls);
+        engine.register_fn("popup", move |text: &str| {
+            let text = text.to_string();
+            registry.borrow_mut().commands.push(Command::Popup { text });
+        });
+
+        let ast = engine.compile(source).map_err(|error| error.to_string())?;
+        engine.run_ast(&ast).map_err(|error| error.to_string())?;
+        Ok(Script {
+            engine,
+            ast,
+            calls,
+            hash: content_hash(source.as_bytes()),
+        })
+    }
+
+    /// Hash of the script's source, the same for the same script anywhere
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Spawn chance of `kind` set by the script, if it set one
+    pub fn chance(&self, kind: SpawnKind) -> Option<u16> {
+        let calls = self.calls.borrow();
+        calls
+            .chances
+            .iter()
+            .find(|(set, _)| *set == kind)
+            .map(|(_, chance)| *chance)
+    }
+
+    /// Run the handlers of `event`. Returns what they asked for; a handler
+    /// that failed shows its error in a popup.
+    pub fn fire(&mut self, event: &CombatEvent) -> Vec<Command> {
+        let (name, fields) = describe(event);
+        let handlers: Vec<FnPtr> = self
+            .calls
+            .borrow()
+            .handlers
+            .iter()
+            .filter(|(on, _)| on == name)
+            .map(|(_, handler)| handler.clone())
+            .collect();
+        for handler in handlers {
+            let called = handler.call::<Dynamic>(&self.engine, &self.ast, (fields.clone(),));
+            if let Err(error) = called {
+                let text = format!("Script error on {name}: {error}");
+                let popup = Command::Popup { text };
+                self.calls.borrow_mut().commands.push(popup);
+            }
+        }
+        std::mem::take(&mut self.calls.borrow_mut().commands)
+    }
+} // end of Script implementation.
+
+fn spawn_kind(name: &str) -> Result<SpawnKind, Box<EvalAltResult>> {
+    SpawnKind::from_name(name).ok_or_else(|| format!("no such kind: {name}").into())
+}
+
+/// Name of `event` for `on`, and its fields
+fn describe(event: &CombatEvent) -> (&'static str, Map) {
+    let mut fields = Map::new();
+    let mut field = |key: &str, value: Dynamic| {
+        fields.insert(key.into(), value);
+    };
+    let name = match event {
+        CombatEvent::Kill { kind, reward } => {
+            field("kind", kind.to_string().into());
+            field("reward", i64::from(*reward).into());
+            "kill"
+        }
+        CombatEvent::DepotShot => "depot_shot",
+        CombatEvent::Pickup(what) => {
+            field("what", what.to_string().into());
+            "pickup"
+        }
+        CombatEvent::Bomb { kills } => {
+            field("kills", (*kills as i64).into());
+            "bomb"
+        }
+        CombatEvent::Hit { shield_left } => {
+            field("shield_left", i64::from(*shield_left).into());
+            "hit"
+        }
+        CombatEvent::Section { section, biome } => {
+            field("section", i64::from(*section).into());
+            field("biome", biome.clone().into());
+            "section"
+        }
+        CombatEvent::Stage { name, biome } => {
+            field("name", name.clone().into());
+            field("biome", biome.clone().into());
+            "stage"
+        }
+        CombatEvent::Warp => "warp",
+        CombatEvent::Death(cause) => {
+            field("cause", format!("{cause:?}").into());
+            "death"
+        }
+    };
+    (name, fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Script;
+    use crate::{combat_log::CombatEvent, control::Command, world::SpawnKind};
+
+    #[test]
+    fn handlers_react_to_events() {
+        let mut script = Script::compile(
+            r#"
+            on("kill", |event| {
+                if event.reward >= 30 { send("helicopter"); }
+            });
+            on("section", |event| {
+                set_chance("fuel", 5);
+                popup(`Section ${event.section}`);
+            });
+            on("warp", |event| send("dragon"));
+            "#,
+        )
+        .unwrap();
+
+        let kill = |reward| CombatEvent::Kill {
+            kind: "Helicopter",
+            reward,
+        };
+        assert!(script.fire(&kill(10)).is_empty());
+        assert_eq!(
+            script.fire(&kill(60)),
+            [Command::Spawn {
+                kind: SpawnKind::Helicopter
+            }]
+        );
+
+        let section = CombatEvent::Section {
+            section: 4,
+            biome: "Forest".to_string(),
+        };
+        assert_eq!(
+            script.fire(&section),
+            [Command::Popup {
+                text: "Section 4".to_string()
+            }]
+        );
+        assert_eq!(script.chance(SpawnKind::Fuel), Some(5));
+        assert_eq!(script.chance(SpawnKind::Enemy), None);
+
+        let warp = script.fire(&CombatEvent::Warp);
+        assert!(matches!(&warp[..], [Command::Popup { text }] if text.contains("dragon")));
+
+        assert!(Script::compile("loop { }").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Seasonal events, announced by a small JSON file served at the
+//! `events_url` set in `settings.conf`:
+//!
+//! ```json
+//! {"event": "double_fuel"}
+//! ```
+//!
+//! The file is fetched with `curl` in the background while the game starts;
+//! if it is slow, missing or unknown, the game simply runs without an event.
+//! An event fetched after a run started only applies from the next run, so
+//! it never changes the spawns of a run under way.
+
+use std::{
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SeasonalEvent {
+    /// Fuel depots hold twice the gas
+    DoubleFuel,
+    /// Enemies spawn more often
+    EnemyRush,
+}
+
+impl SeasonalEvent {
+    fn from_name(name: &str) -> Option<SeasonalEvent> {
+        match name {
+            "double_fuel" => Some(SeasonalEvent::DoubleFuel),
+            "enemy_rush" => Some(SeasonalEvent::EnemyRush),
+            _ => None,
+        }
+    }
+
+    /// The event announced in a JSON document, if any and known.
+    fn parse(json: &str) -> Option<SeasonalEvent> {
+        let value: serde_json::Value = serde_json::from_str(json).ok()?;
+        SeasonalEvent::from_name(value.get("event")?.as_str()?)
+    }
+
+    pub fn banner(&self) -> &'static str {
+        match self {
+            SeasonalEvent::DoubleFuel => "★ Double fuel week ★",
+            SeasonalEvent::EnemyRush => "★ Enemy rush ★",
+        }
+    }
+} // end of SeasonalEvent implementation.
+
+/// Start fetching the current event from `url`. The receiver gets the event
+/// once known, or nothing if there is none.
+pub fn fetch(url: String) -> Receiver<SeasonalEvent> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let output = Command::new("curl")
+            .args(["--silent", "--fail", "--max-time", "10", &url])
+            .stderr(Stdio::null())
+            .output();
+
+        let event = output
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| SeasonalEvent::parse(&String::from_utf8_lossy(&output.stdout)));
+        if let Some(event) = event {
+            // The game may be over already, nobody to tell then.
+            let _ = sender.send(event);
+        }
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SeasonalEvent;
+
+    #[test]
+    fn unknown_events_are_ignored() {
+        assert_eq!(
+            SeasonalEvent::parse(r#"{"event": "enemy_rush", "ends": "sunday"}"#),
+            Some(SeasonalEvent::EnemyRush)
+        );
+        assert_eq!(SeasonalEvent::parse(r#"{"event": "snow"}"#), None);
+        assert_eq!(SeasonalEvent::parse("<html>"), None);
+    }
+}
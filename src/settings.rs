@@ -0,0 +1,395 @@
+//! Player settings, persisted to `settings.conf` as `key = value` lines.
+//! Lines starting with `#` are comments; saving the settings drops them.
+
+use std::{fs, path::Path};
+
+use crate::{
+    color_depth::ColorDepth,
+    feedback::{FeedbackKind, DEFAULT_VOLUME, MAX_VOLUME},
+    locale::{Locale, TimeStyle},
+    theme::Theme,
+};
+
+pub const SETTINGS_FILE: &str = "settings.conf";
+
+/// Tick speeds offered in the settings screen, slowest first
+pub const SPEEDS: [(&str, u64); 3] = [("Slow", 80), ("Normal", 60), ("Fast", 40)];
+
+const DEFAULT_MAX_BULLETS: usize = 40;
+const DEFAULT_MAX_ENEMIES: usize = 60;
+const DEFAULT_ENTITY_CAP: usize = 200;
+const DEFAULT_WARP_DISTANCE: u16 = 120;
+
+pub struct Settings {
+    pub sound: bool,
+    /// Flash the screen instead of ringing the bell
+    pub flash: bool,
+    /// Feedback kinds never played, see [`crate::feedback`]
+    pub quiet: Vec<FeedbackKind>,
+    /// Volumes of menu, popup and gameplay feedback, `0` to [`MAX_VOLUME`]
+    pub menu_volume: u8,
+    pub popup_volume: u8,
+    pub gameplay_volume: u8,
+    /// Milliseconds between two ticks
+    pub tick_ms: u64,
+    /// Name of the color theme picked, see [`crate::theme::Theme::by_name`].
+    /// The one suiting the terminal's colors unless set.
+    pub theme: Option<String>,
+    /// Seed every run is played with, a random one if unset
+    pub seed: Option<u64>,
+    /// Write a combat log of every run, see [`crate::combat_log`]
+    pub combat_log: bool,
+    /// Dress the river up around holidays, see [`crate::pack`]
+    pub packs: bool,
+    /// Most bullets, the player's and the enemies', flying at once
+    pub max_bullets: usize,
+    /// Most enemies on the river at once; spawns wait beyond that
+    pub max_enemies: usize,
+    /// Most enemies, fuels and enemy bullets alive at once; the least
+    /// valuable are culled beyond that
+    pub entity_cap: usize,
+    /// Lines of river a tunnel skips
+    pub warp_distance: u16,
+    /// URL the summary of the game is posted to, see [`crate::webhook`]
+    pub webhook: Option<String>,
+    /// URL of the current seasonal event, see [`crate::season`]
+    pub events_url: Option<String>,
+    /// Locale numbers are written in, such as `de_DE`; the environment's
+    /// unless set, see [`crate::locale`]
+    pub locale: Option<String>,
+    /// How durations are written
+    pub durations: TimeStyle,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            sound: true,
+            flash: false,
+            quiet: Vec::new(),
+            menu_volume: DEFAULT_VOLUME,
+            popup_volume: DEFAULT_VOLUME,
+            gameplay_volume: DEFAULT_VOLUME,
+            tick_ms: 60,
+            theme: None,
+            seed: None,
+            combat_log: false,
+            packs: true,
+            max_bullets: DEFAULT_MAX_BULLETS,
+            max_enemies: DEFAULT_MAX_ENEMIES,
+            entity_cap: DEFAULT_ENTITY_CAP,
+            warp_distance: DEFAULT_WARP_DISTANCE,
+            webhook: None,
+            events_url: None,
+            locale: None,
+            durations: TimeStyle::Clock,
+        }
+    }
+}
+
+impl Settings {
+    /// Defaults overridden by whatever `path` sets. Unknown keys and invalid
+    /// values are ignored.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Settings> {
+        let mut settings = Settings::default();
+        settings.apply(&fs::read_to_string(path)?);
+        Ok(settings)
+    }
+
+    /// Lines of `path` that [`Settings::load`] would ignore.
+    pub fn check(path: impl AsRef<Path>) -> std::io::Result<Vec<String>> {
+        Ok(Settings::default().apply(&fs::read_to_string(path)?))
+    }
+
+    /// Apply every line of `config`, returning the ones not understood.
+    fn apply(&mut self, config: &str) -> Vec<String> {
+        let mut ignored = Vec::new();
+        for line in config.lines() {
+            if line.trim().is_empty() || line.trim().starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                ignored.push(line.to_string());
+                continue;
+            };
+
+            let understood = match (key.trim(), value.trim()) {
+                ("sound", "on") => {
+                    self.sound = true;
+                    true
+                }
+                ("sound", "off") => {
+                    self.sound = false;
+                    true
+                }
+                ("flash", "on") => {
+                    self.flash = true;
+                    true
+                }
+                ("flash", "off") => {
+                    self.flash = false;
+                    true
+                }
+                ("quiet", kinds) => {
+                    let kinds: Option<Vec<_>> = kinds
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(FeedbackKind::from_name)
+                        .collect();
+                    kinds.map(|kinds| self.quiet = kinds).is_some()
+                }
+                ("menu_volume" | "popup_volume" | "gameplay_volume", value) => {
+                    match value.parse::<u8>() {
+                        Ok(volume @ 0..=MAX_VOLUME) => {
+                            *self.volume_mut(key.trim()) = volume;
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+                ("tick_ms", value) => value.parse().map(|tick_ms| self.tick_ms = tick_ms).is_ok(),
+                // a target frame rate is another way to put the tick length
+                ("fps", value) => match value.parse::<u64>() {
+                    Ok(fps @ 1..=1000) => {
+                        self.tick_ms = 1000 / fps;
+                        true
+                    }
+                    _ => false,
+                },
+                ("combat_log", "on") => {
+                    self.combat_log = true;
+                    true
+                }
+                ("combat_log", "off") => {
+                    self.combat_log = false;
+                    true
+                }
+                ("packs", "on") => {
+                    self.packs = true;
+                    true
+                }
+                ("packs", "off") => {
+                    self.packs = false;
+                    true
+                }
+                ("max_bullets", value) => value.parse().map(|max| self.max_bullets = max).is_ok(),
+                ("max_enemies", value) => value.parse().map(|max| self.max_enemies = max).is_ok(),
+                ("entity_cap", value) => value.parse().map(|cap| self.entity_cap = cap).is_ok(),
+                ("warp_distance", value) => value
+                    .parse()
+                    .map(|distance| self.warp_distance = distance)
+                    .is_ok(),
+                ("seed", value) => value.parse().map(|seed| self.seed = Some(seed)).is_ok(),
+                ("webhook", url) if !url.is_empty() => {
+                    self.webhook = Some(url.to_string());
+                    true
+                }
+                ("events_url", url) if !url.is_empty() => {
+                    self.events_url = Some(url.to_string());
+                    true
+                }
+                ("locale", tag) if !tag.is_empty() => {
+                    self.locale = Some(tag.to_string());
+                    true
+                }
+                ("durations", "clock") => {
+                    self.durations = TimeStyle::Clock;
+                    true
+                }
+                ("durations", "verbose") => {
+                    self.durations = TimeStyle::Verbose;
+                    true
+                }
+                ("theme", "auto") => {
+                    self.theme = None;
+                    true
+                }
+                ("theme", value) => Theme::by_name(value)
+                    .map(|theme| self.theme = Some(theme.name.to_string()))
+                    .is_some(),
+                _ => false,
+            };
+            if !understood {
+                ignored.push(line.to_string());
+            }
+        }
+        ignored
+    }
+
+    fn to_config(&self) -> String {
+        let mut config = format!(
+            "sound = {}\ntick_ms = {}\n",
+            if self.sound { "on" } else { "off" },
+            self.tick_ms
+        );
+        // left out, the theme keeps following the terminal
+        if let Some(theme) = &self.theme {
+            config += &format!("theme = {theme}\n");
+        }
+        if self.flash {
+            config += "flash = on\n";
+        }
+        if !self.quiet.is_empty() {
+            let names: Vec<_> = self.quiet.iter().map(FeedbackKind::name).collect();
+            config += &format!("quiet = {}\n", names.join(", "));
+        }
+        for (key, volume) in [
+            ("menu_volume", self.menu_volume),
+            ("popup_volume", self.popup_volume),
+            ("gameplay_volume", self.gameplay_volume),
+        ] {
+            if volume != DEFAULT_VOLUME {
+                config += &format!("{key} = {volume}\n");
+            }
+        }
+        if self.combat_log {
+            config += "combat_log = on\n";
+        }
+        if !self.packs {
+            config += "packs = off\n";
+        }
+        if self.max_bullets != DEFAULT_MAX_BULLETS {
+            config += &format!("max_bullets = {}\n", self.max_bullets);
+        }
+        if self.max_enemies != DEFAULT_MAX_ENEMIES {
+            config += &format!("max_enemies = {}\n", self.max_enemies);
+        }
+        if self.entity_cap != DEFAULT_ENTITY_CAP {
+            config += &format!("entity_cap = {}\n", self.entity_cap);
+        }
+        if self.warp_distance != DEFAULT_WARP_DISTANCE {
+            config += &format!("warp_distance = {}\n", self.warp_distance);
+        }
+        if let Some(seed) = self.seed {
+            config += &format!("seed = {seed}\n");
+        }
+        if let Some(url) = &self.webhook {
+            config += &format!("webhook = {url}\n");
+        }
+        if let Some(url) = &self.events_url {
+            config += &format!("events_url = {url}\n");
+        }
+        if let Some(tag) = &self.locale {
+            config += &format!("locale = {tag}\n");
+        }
+        if self.durations == TimeStyle::Verbose {
+            config += "durations = verbose\n";
+        }
+        config
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        fs::write(path, self.to_config())
+    }
+
+    /// The volume setting named `key`, the gameplay one unless it is the
+    /// menu or popup one.
+    fn volume_mut(&mut self, key: &str) -> &mut u8 {
+        match key {
+            "menu_volume" => &mut self.menu_volume,
+            "popup_volume" => &mut self.popup_volume,
+            _ => &mut self.gameplay_volume,
+        }
+    }
+
+    /// The theme picked, or the one suiting a terminal with `depth`.
+    pub fn theme(&self, depth: ColorDepth) -> Theme {
+        self.theme
+            .as_deref()
+            .and_then(Theme::by_name)
+            .unwrap_or_else(|| Theme::for_depth(depth))
+    }
+
+    /// The locale set, the environment's one unless there is one.
+    pub fn locale(&self) -> Locale {
+        let locale = self
+            .locale
+            .as_deref()
+            .map_or_else(Locale::detect, Locale::from_tag);
+        Locale {
+            time: self.durations,
+            ..locale
+        }
+    }
+
+    /// Name of the current speed, if it is one of [`SPEEDS`].
+    pub fn speed_name(&self) -> &'static str {
+        SPEEDS
+            .iter()
+            .find(|(_, tick_ms)| *tick_ms == self.tick_ms)
+            .map_or("Custom", |(name, _)| name)
+    }
+
+    /// Switch to the next of [`SPEEDS`], wrapping around.
+    pub fn next_speed(&mut self) {
+        let next = SPEEDS
+            .iter()
+            .position(|(_, tick_ms)| *tick_ms == self.tick_ms)
+            .map_or(0, |index| (index + 1) % SPEEDS.len());
+        self.tick_ms = SPEEDS[next].1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Settings;
+    use crate::{color_depth::ColorDepth, feedback::FeedbackKind, locale::TimeStyle};
+
+    #[test]
+    fn config_round_trip() {
+        let mut settings = Settings {
+            sound: false,
+            theme: Some("monochrome".to_string()),
+            seed: Some(7),
+            combat_log: true,
+            packs: false,
+            max_enemies: 12,
+            entity_cap: 90,
+            warp_distance: 40,
+            quiet: vec![FeedbackKind::MenuMove, FeedbackKind::Pickup],
+            menu_volume: 0,
+            gameplay_volume: 8,
+            locale: Some("de_DE".to_string()),
+            durations: TimeStyle::Verbose,
+            ..Default::default()
+        };
+        settings.next_speed();
+
+        let mut loaded = Settings::default();
+        assert!(loaded.apply(&settings.to_config()).is_empty());
+        assert_eq!(
+            loaded.apply("# tuned by hand\nfps = 0\nvolume = 3\npopup_volume = 11"),
+            ["fps = 0", "volume = 3", "popup_volume = 11"]
+        );
+        assert!(!loaded.sound);
+        assert_eq!(loaded.tick_ms, settings.tick_ms);
+        assert_eq!(loaded.speed_name(), "Fast");
+        assert_eq!(loaded.theme(ColorDepth::TrueColor).name, "Monochrome");
+        assert_eq!(loaded.seed, Some(7));
+        assert!(loaded.combat_log);
+        assert!(!loaded.packs);
+        assert_eq!((loaded.max_bullets, loaded.max_enemies), (40, 12));
+        assert_eq!(loaded.entity_cap, 90);
+        assert_eq!(loaded.warp_distance, 40);
+        assert_eq!(loaded.quiet, settings.quiet);
+        assert_eq!(
+            (
+                loaded.menu_volume,
+                loaded.popup_volume,
+                loaded.gameplay_volume
+            ),
+            (0, 5, 8)
+        );
+        assert_eq!(loaded.locale().number(12345u32), "12.345");
+        assert_eq!(loaded.locale().time, TimeStyle::Verbose);
+
+        // a theme never picked is not saved, and follows the terminal
+        let defaults = Settings::default();
+        assert!(!defaults.to_config().contains("theme"));
+        assert_eq!(defaults.theme(ColorDepth::Mono).name, "Monochrome");
+        assert_eq!(defaults.theme(ColorDepth::Ansi16).name, "Classic");
+        loaded.apply("theme = auto");
+        assert_eq!(loaded.theme, None);
+    }
+}
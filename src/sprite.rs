@@ -0,0 +1,153 @@
+//! Sprites: shapes taking more than one cell, such as the player's jet,
+//! ships and bridges. A sprite is drawn and hit by all its cells.
+//!
+//! ```text
+//!  ▲      the jet, anchored at the body so the player's location is the
+//! ╱█╲     bottom line it flies on
+//! ```
+
+use crossterm::style::ContentStyle;
+
+use crate::{canvas::Canvas, entities::Location, world::Region};
+
+#[derive(Clone, Copy)]
+enum Shape {
+    /// Glyphs, top row first; spaces are see-through
+    Rows(&'static [&'static str]),
+    /// A single row of `width` times `glyph`
+    Bar { glyph: char, width: u16 },
+}
+
+/// Sprites are built on every draw and collision check, so they only borrow
+/// their glyphs.
+#[derive(Clone, Copy)]
+pub struct Sprite {
+    shape: Shape,
+    /// Cell of the grid at the entity's location, `(column, row)`
+    anchor: (u16, u16),
+}
+
+impl Sprite {
+    pub const fn new(rows: &'static [&'static str], anchor: (u16, u16)) -> Sprite {
+        Sprite {
+            shape: Shape::Rows(rows),
+            anchor,
+        }
+    }
+
+    /// A single row of `width` times `glyph`, anchored at its left end.
+    pub const fn bar(glyph: char, width: u16) -> Sprite {
+        Sprite {
+            shape: Shape::Bar { glyph, width },
+            anchor: (0, 0),
+        }
+    }
+
+    /// Row, column and glyph of every cell of the grid, see-through or not.
+    fn glyphs(&self) -> impl Iterator<Item = (u16, u16, char)> {
+        let (rows, bar) = match self.shape {
+            Shape::Rows(rows) => (rows, None),
+            Shape::Bar { glyph, width } => (&[][..], Some((glyph, width))),
+        };
+        let rows = rows.iter().enumerate().flat_map(|(row, glyphs)| {
+            glyphs
+                .chars()
+                .enumerate()
+                .map(move |(column, glyph)| (row as u16, column as u16, glyph))
+        });
+        let (glyph, width) = bar.unwrap_or((' ', 0));
+        rows.chain((0..width).map(move |column| (0, column, glyph)))
+    }
+
+    /// Column, line and glyph of every solid cell with the anchor at
+    /// `location`. Cells off the top or left edge are left out.
+    pub fn cells<'a>(
+        &'a self,
+        location: &'a Location,
+    ) -> impl Iterator<Item = (u16, u16, char)> + 'a {
+        self.glyphs().filter_map(move |(row, column, glyph)| {
+            let c = (location.c + column).checked_sub(self.anchor.0)?;
+            let l = (location.l + row).checked_sub(self.anchor.1)?;
+            (glyph != ' ').then_some((c, l, glyph))
+        })
+    }
+
+    /// The smallest region holding the sprite at `location`.
+    pub fn bounds(&self, location: &Location) -> Region {
+        let (width, height) = match self.shape {
+            Shape::Rows(rows) => (
+                rows.iter()
+                    .map(|row| row.chars().count())
+                    .max()
+                    .unwrap_or(1)
+                    .max(1) as u16,
+                rows.len().max(1) as u16,
+            ),
+            Shape::Bar { width, .. } => (width.max(1), 1),
+        };
+        let left = location.c.saturating_sub(self.anchor.0);
+        let top = location.l.saturating_sub(self.anchor.1);
+        Region {
+            top,
+            bottom: top + height - 1,
+            left,
+            right: left + width - 1,
+        }
+    }
+
+    /// Whether any solid cell at `location` is in `region`.
+    pub fn hits(&self, location: &Location, region: &Region) -> bool {
+        self.cells(location).any(|(c, l, _)| region.contains(c, l))
+    }
+
+    pub fn draw(&self, sc: &mut Canvas, location: &Location, style: ContentStyle) {
+        for (c, l, glyph) in self.cells(location) {
+            sc.draw_styled_char((c, l), glyph, style);
+        }
+    }
+} // end of Sprite implementation.
+
+#[cfg(test)]
+mod tests {
+    use super::Sprite;
+    use crate::{entities::Location, world::Region};
+
+    #[test]
+    fn cells_follow_the_anchor() {
+        let jet = Sprite::new(&[" ▲ ", "╱█╲"], (1, 1));
+        let at = Location::new(10, 5);
+        let cells: Vec<_> = jet.cells(&at).collect();
+        assert_eq!(
+            cells,
+            [(10, 4, '▲'), (9, 5, '╱'), (10, 5, '█'), (11, 5, '╲')]
+        );
+
+        // the see-through corners do not collide
+        let corner = Region {
+            top: 4,
+            bottom: 4,
+            left: 9,
+            right: 9,
+        };
+        assert!(!jet.hits(&at, &corner));
+        let wing = Region {
+            top: 5,
+            bottom: 6,
+            left: 11,
+            right: 12,
+        };
+        assert!(jet.hits(&at, &wing));
+
+        let bounds = jet.bounds(&at);
+        assert_eq!(
+            (bounds.top, bounds.bottom, bounds.left, bounds.right),
+            (4, 5, 9, 11)
+        );
+
+        let bridge = Sprite::bar('═', 3);
+        let cells: Vec<_> = bridge.cells(&at).collect();
+        assert_eq!(cells, [(10, 5, '═'), (11, 5, '═'), (12, 5, '═')]);
+        let bounds = bridge.bounds(&at);
+        assert_eq!((bounds.left, bounds.right, bounds.bottom), (10, 12, 5));
+    }
+}
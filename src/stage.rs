@@ -0,0 +1,434 @@
+//! Stages: a run as a sequence of levels, each flying on for a distance or a
+//! number of ticks with its own spawn chances, river and pace, announced by a
+//! popup when it starts. A last stage with an end completes the run when it
+//! ends; without one, it lasts until the end of the run.
+//!
+//! Stages are data, read from `stages.conf` when there is one, so pacing
+//! can be tweaked without rebuilding the game:
+//!
+//! ```text
+//! [Narrows]
+//! ticks = 1000
+//! river = narrow
+//! speed = 0.8
+//! spawns = enemy 120, helicopter 40
+//! popup = Stage 3: The Narrows, hold on for a minute
+//! wave = 300 enemy 6
+//! ```
+//!
+//! A stage ends after `distance` lines or `ticks` ticks, and never without
+//! either, so only the last one should leave both out. `speed` scales how
+//! fast the river scrolls, `1` being the usual pace. Spawn chances left
+//! out are the usual ones. A `wave` sends that many entities of a kind, one
+//! per line, once the stage has flown that many lines; a stage may have
+//! several.
+
+use std::{collections::VecDeque, fs, iter, path::Path};
+
+use crate::{
+    blocks,
+    world::{self, SpawnKind, SpawnTable},
+};
+
+pub const STAGES_FILE: &str = "stages.conf";
+
+/// Ticks a stage's popup stays on screen
+const POPUP_TICKS: u16 = 90;
+
+/// Most entities scripts and the control interface can have waiting to
+/// spawn; more are dropped
+const MAX_QUEUED: usize = 100;
+
+const BUILT_IN: &str = "\
+[Warm-up]
+distance = 400
+river = wide
+spawns = enemy 50, strafer 0, helicopter 10, ship 0, tunnel 0, fuel 15, obstacle 0
+popup = Stage 1: Warm-up, the river is calm for now
+
+[Patrol]
+distance = 1200
+popup = Stage 2: Patrol, they have noticed you
+wave = 600 helicopter 5
+
+[Narrows]
+# about a minute at the normal speed
+ticks = 1000
+river = narrow
+spawns = enemy 120, helicopter 40, ship 10, fuel 12, bomb 2
+popup = Stage 3: The Narrows, hold on for a minute
+wave = 300 enemy 6
+
+[Open river]
+speed = 1.2
+spawns = enemy 130, strafer 30, helicopter 40, ship 30
+popup = Stage 4: Open river, anything goes from here
+";
+
+/// When a stage gives way to the next
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StageEnd {
+    /// After flying this many lines of river
+    Distance(u32),
+    /// After this many ticks of play
+    Ticks(u32),
+}
+
+/// How wide the river is kept
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RiverMode {
+    Wide,
+    Normal,
+    Narrow,
+}
+
+impl RiverMode {
+    fn from_name(name: &str) -> Option<RiverMode> {
+        match name {
+            "wide" => Some(RiverMode::Wide),
+            "normal" => Some(RiverMode::Normal),
+            "narrow" => Some(RiverMode::Narrow),
+            _ => None,
+        }
+    }
+
+    /// Narrowest and widest (if capped) the river may get, in columns
+    pub fn widths(&self) -> (u16, Option<u16>) {
+        match self {
+            RiverMode::Wide => (14, None),
+            RiverMode::Normal => (3, None),
+            RiverMode::Narrow => (3, Some(14)),
+        }
+    }
+} // end of RiverMode implementation.
+
+/// A burst of entities sent during a stage
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Wave {
+    /// Lines into the stage it comes at
+    pub at: u32,
+    pub kind: SpawnKind,
+    pub count: u16,
+}
+
+impl Wave {
+    /// `<at> <kind> <count>`
+    fn parse(wave: &str) -> Option<Wave> {
+        let mut fields = wave.split_whitespace();
+        let wave = Wave {
+            at: fields.next()?.parse().ok()?,
+            kind: SpawnKind::from_name(fields.next()?)?,
+            count: fields.next()?.parse().ok()?,
+        };
+        fields.next().is_none().then_some(wave)
+    }
+} // end of Wave implementation.
+
+pub struct Stage {
+    pub name: String,
+    /// When the stage ends, never if not given
+    pub end: Option<StageEnd>,
+    pub spawns: SpawnTable,
+    pub river: RiverMode,
+    /// How much faster than usual the river scrolls
+    pub speed: f32,
+    /// Shown when the stage starts
+    pub popup: Option<String>,
+    /// In the order they come
+    pub waves: Vec<Wave>,
+}
+
+impl Stage {
+    fn new(name: &str) -> Stage {
+        Stage {
+            name: name.to_string(),
+            end: None,
+            spawns: SpawnTable::default(),
+            river: RiverMode::Normal,
+            speed: 1.0,
+            popup: None,
+            waves: Vec::new(),
+        }
+    }
+
+    /// The stages shipped with the game
+    pub fn built_in() -> Vec<Stage> {
+        Stage::parse(BUILT_IN).0
+    }
+
+    /// The stages of `path`; the built-in ones if it has none.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Vec<Stage>> {
+        let stages = Stage::parse(&fs::read_to_string(path)?).0;
+        Ok(if stages.is_empty() {
+            Stage::built_in()
+        } else {
+            stages
+        })
+    }
+
+    /// Lines of `path` that [`Stage::load`] would ignore.
+    pub fn check(path: impl AsRef<Path>) -> std::io::Result<Vec<String>> {
+        Ok(Stage::parse(&fs::read_to_string(path)?).1)
+    }
+
+    /// The stages of `config`, and the lines not understood.
+    fn parse(config: &str) -> (Vec<Stage>, Vec<String>) {
+        blocks::parse(config, Stage::new, |stage, key, value| match (key, value) {
+            ("distance", lines) => lines
+                .parse()
+                .map(|lines| stage.end = Some(StageEnd::Distance(lines)))
+                .is_ok(),
+            ("ticks", ticks) => ticks
+                .parse()
+                .map(|ticks| stage.end = Some(StageEnd::Ticks(ticks)))
+                .is_ok(),
+            ("river", mode) => RiverMode::from_name(mode)
+                .map(|mode| stage.river = mode)
+                .is_some(),
+            ("speed", speed) => speed
+                .parse()
+                .ok()
+                .filter(|speed: &f32| speed.is_finite() && *speed > 0.0)
+                .map(|speed| stage.speed = speed)
+                .is_some(),
+            ("spawns", chances) => parse_spawns(chances)
+                .map(|spawns| stage.spawns = spawns)
+                .is_some(),
+            ("popup", text) => {
+                stage.popup = Some(text.to_string());
+                true
+            }
+            ("wave", wave) => Wave::parse(wave)
+                .map(|wave| {
+                    stage.waves.push(wave);
+                    stage.waves.sort_by_key(|wave| wave.at);
+                })
+                .is_some(),
+            _ => false,
+        })
+    }
+} // end of Stage implementation.
+
+/// `<kind> <chance>, ...`, over the usual chances
+fn parse_spawns(chances: &str) -> Option<SpawnTable> {
+    let mut table = SpawnTable::default();
+    for (kind, chance) in world::parse_chances(chances)? {
+        *table.chance_mut(kind) = chance;
+    }
+    Some(table)
+}
+
+/// Plays [`Stage`]s one after the other. Without any, the run is one long
+/// stage with the spawner's own chances.
+#[derive(Default)]
+pub struct StageManager {
+    stages: Vec<Stage>,
+    current: usize,
+    /// Distance flown when the current stage started
+    started_at: u32,
+    /// Ticks into the current stage
+    ticks: u32,
+    /// Popup on screen and the ticks it has left
+    popup: Option<(String, u16)>,
+    /// Next wave of the current stage to send
+    next_wave: usize,
+    /// Entities of waves sent, still to spawn
+    queued: VecDeque<SpawnKind>,
+    /// The last stage came to its end
+    completed: bool,
+}
+
+impl StageManager {
+    pub fn new(stages: Vec<Stage>) -> StageManager {
+        let mut manager = StageManager {
+            stages,
+            ..StageManager::default()
+        };
+        manager.restart();
+        manager
+    }
+
+    /// Place of the stage being played among the stages, `0` for the first
+    pub fn index(&self) -> usize {
+        self.current
+    }
+
+    /// The stage being played, if there are stages at all
+    pub fn stage(&self) -> Option<&Stage> {
+        self.stages.get(self.current)
+    }
+
+    /// Lines flown in the current stage, out of `traveled` in all
+    pub fn flown(&self, traveled: u32) -> u32 {
+        traveled.saturating_sub(self.started_at)
+    }
+
+    /// The last stage was flown to its end, see [`Stage::end`]
+    pub fn completed(&self) -> bool {
+        self.completed
+    }
+
+    /// Popup text to show, if any
+    pub fn popup(&self) -> Option<&str> {
+        self.popup.as_ref().map(|(text, _)| text.as_str())
+    }
+
+    /// Next entity of a wave to spawn, if any. With `enemies_held`, an
+    /// enemy waits its turn and holds up the rest of the queue.
+    pub fn next_wave_spawn(&mut self, enemies_held: bool) -> Option<SpawnKind> {
+        match self.queued.front() {
+            Some(kind) if enemies_held && kind.is_enemy() => None,
+            _ => self.queued.pop_front(),
+        }
+    }
+
+    /// Send `kind` down the river with the waves, unless [`MAX_QUEUED`] are
+    /// waiting already.
+    pub fn queue(&mut self, kind: SpawnKind) {
+        if self.queued.len() < MAX_QUEUED {
+            self.queued.push_back(kind);
+        }
+    }
+
+    /// Show `text` as a popup.
+    pub fn announce(&mut self, text: String) {
+        self.popup = Some((text, POPUP_TICKS));
+    }
+
+    /// Back to the first stage, for a new run.
+    pub fn restart(&mut self) {
+        self.current = 0;
+        self.started_at = 0;
+        self.ticks = 0;
+        self.next_wave = 0;
+        self.queued.clear();
+        self.completed = false;
+        self.show_popup();
+    }
+
+    fn show_popup(&mut self) {
+        self.popup = self
+            .stage()
+            .and_then(|stage| stage.popup.clone())
+            .map(|text| (text, POPUP_TICKS));
+    }
+
+    /// A tick went by with `traveled` lines flown so far. Returns `true`
+    /// when it started the next stage.
+    pub fn tick(&mut self, traveled: u32) -> bool {
+        self.ticks += 1;
+        if let Some((_, ticks_left)) = &mut self.popup {
+            *ticks_left -= 1;
+            if *ticks_left == 0 {
+                self.popup = None;
+            }
+        }
+
+        let Some(stage) = self.stages.get(self.current) else {
+            return false;
+        };
+        let flown = self.flown(traveled);
+        while let Some(wave) = stage
+            .waves
+            .get(self.next_wave)
+            .filter(|wave| wave.at <= flown)
+        {
+            // the same bound as `queue`, however big the waves
+            let room = MAX_QUEUED.saturating_sub(self.queued.len());
+            self.queued
+                .extend(iter::repeat_n(wave.kind, wave.count.into()).take(room));
+            self.next_wave += 1;
+        }
+
+        let over = match stage.end {
+            Some(StageEnd::Distance(lines)) => flown >= lines,
+            Some(StageEnd::Ticks(ticks)) => self.ticks >= ticks,
+            None => false,
+        };
+        if !over {
+            return false;
+        }
+        if self.current + 1 >= self.stages.len() {
+            self.completed = true;
+            return false;
+        }
+
+        self.current += 1;
+        self.started_at = traveled;
+        self.ticks = 0;
+        self.next_wave = 0;
+        self.show_popup();
+        true
+    }
+} // end of StageManager implementation.
+
+#[cfg(test)]
+mod tests {
+    use super::{RiverMode, Stage, StageEnd, StageManager, MAX_QUEUED};
+    use crate::world::SpawnKind;
+
+    #[test]
+    fn stages_are_read_with_their_waves() {
+        let config = "[Gauntlet]\nticks = 50\nriver = narrow\nspawns = ship 90, fuel 0\n\
+                      speed = 1.5\nspeed = 0\nwave = 20 ship 2\nwave = 5 enemy 1\n\
+                      wave = soon\nweather = rain\n";
+        let (stages, ignored) = Stage::parse(config);
+        assert_eq!(ignored, ["speed = 0", "wave = soon", "weather = rain"]);
+        let gauntlet = &stages[0];
+        assert_eq!(gauntlet.end, Some(StageEnd::Ticks(50)));
+        assert_eq!(gauntlet.river, RiverMode::Narrow);
+        assert_eq!(gauntlet.speed, 1.5);
+        let spawns = &gauntlet.spawns;
+        assert_eq!((spawns.ship, spawns.fuel, spawns.enemy), (90, 0, 100));
+
+        let mut manager = StageManager::new(stages);
+        manager.tick(5);
+        assert_eq!(manager.next_wave_spawn(false), Some(SpawnKind::Enemy));
+        assert_eq!(manager.next_wave_spawn(false), None);
+        manager.tick(25);
+        assert_eq!(manager.next_wave_spawn(false), Some(SpawnKind::Ship));
+        assert_eq!(manager.next_wave_spawn(false), Some(SpawnKind::Ship));
+        assert_eq!(manager.next_wave_spawn(false), None);
+
+        // waves queue no more than a script may
+        let (stages, _) = Stage::parse("[Swarm]\nwave = 0 enemy 60000\nwave = 0 fuel 9\n");
+        let mut manager = StageManager::new(stages);
+        manager.tick(0);
+        let mut queued = 0;
+        while manager.next_wave_spawn(false) == Some(SpawnKind::Enemy) {
+            queued += 1;
+        }
+        assert_eq!(queued, MAX_QUEUED);
+    }
+
+    #[test]
+    fn stages_follow_each_other() {
+        let mut stages = StageManager::new(Stage::built_in());
+        assert_eq!(stages.stage().unwrap().name, "Warm-up");
+        assert!(stages.popup().unwrap().starts_with("Stage 1"));
+
+        assert!(!stages.tick(399));
+        assert!(stages.tick(400));
+        assert_eq!(stages.stage().unwrap().name, "Patrol");
+        assert!(stages.tick(1600));
+        // the narrows go by the clock, not the distance
+        assert!(!stages.tick(100_000));
+        assert!((0..998).all(|_| !stages.tick(100_000)));
+        assert!(stages.tick(100_000));
+        assert_eq!(stages.stage().unwrap().name, "Open river");
+        assert!(!stages.tick(u32::MAX));
+        assert!(!stages.completed());
+
+        // a last stage with an end completes the run
+        let (last, _) = Stage::parse("[Sprint]\ndistance = 50\n");
+        let mut sprint = StageManager::new(last);
+        assert!(!sprint.tick(49) && !sprint.completed());
+        assert!(!sprint.tick(50) && sprint.completed());
+        sprint.restart();
+        assert!(!sprint.completed());
+
+        stages.restart();
+        assert_eq!(stages.stage().unwrap().name, "Warm-up");
+        assert!(StageManager::default().stage().is_none());
+    }
+}
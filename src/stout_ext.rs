@@ -1,12 +1,19 @@
-//! This module contains extension to [`Stdout`] object.
+//! This module contains extension to [`Stdout`] and any other [`Write`] the
+//! game can draw into.
 
-use std::{fmt::Display, io::Stdout};
+use std::{
+    fmt::Display,
+    io::{Stdout, Write},
+};
 
 use crossterm::{cursor::MoveTo, style::Print, terminal::Clear, QueueableCommand};
 
-use crate::entities::{Bullet, Enemy, Fuel, Location, Player};
+use crate::entities::{
+    BombCrate, Bridge, Bullet, Enemy, EnemyBullet, Fuel, Location, Obstacle, Player, RechargePad,
+    Spark, Tunnel,
+};
 
-pub type StdoutResult<'a> = Result<&'a mut Stdout, std::io::Error>;
+pub type StdoutResult<'a, W = Stdout> = Result<&'a mut W, std::io::Error>;
 
 pub trait AsLocationTuple {
     fn as_loc_tuple(&self) -> (u16, u16);
@@ -30,6 +37,48 @@ impl AsLocationTuple for &Bullet {
     }
 }
 
+impl AsLocationTuple for &Spark {
+    fn as_loc_tuple(&self) -> (u16, u16) {
+        self.location.as_loc_tuple()
+    }
+}
+
+impl AsLocationTuple for &EnemyBullet {
+    fn as_loc_tuple(&self) -> (u16, u16) {
+        self.location.as_loc_tuple()
+    }
+}
+
+impl AsLocationTuple for &Bridge {
+    fn as_loc_tuple(&self) -> (u16, u16) {
+        self.location.as_loc_tuple()
+    }
+}
+
+impl AsLocationTuple for &Tunnel {
+    fn as_loc_tuple(&self) -> (u16, u16) {
+        self.location.as_loc_tuple()
+    }
+}
+
+impl AsLocationTuple for &RechargePad {
+    fn as_loc_tuple(&self) -> (u16, u16) {
+        self.location.as_loc_tuple()
+    }
+}
+
+impl AsLocationTuple for &BombCrate {
+    fn as_loc_tuple(&self) -> (u16, u16) {
+        self.location.as_loc_tuple()
+    }
+}
+
+impl AsLocationTuple for &Obstacle {
+    fn as_loc_tuple(&self) -> (u16, u16) {
+        self.location.as_loc_tuple()
+    }
+}
+
 impl AsLocationTuple for &Fuel {
     fn as_loc_tuple(&self) -> (u16, u16) {
         self.location.as_loc_tuple()
@@ -60,31 +109,31 @@ impl AsLocationTuple for u16 {
     }
 }
 
-pub trait StdoutExt {
-    fn clear_all(&mut self) -> StdoutResult;
+pub trait StdoutExt: Sized {
+    fn clear_all(&mut self) -> StdoutResult<'_, Self>;
 
-    fn move_cursor(&mut self, loc: impl AsLocationTuple) -> StdoutResult;
+    fn move_cursor(&mut self, loc: impl AsLocationTuple) -> StdoutResult<'_, Self>;
 
-    fn print(&mut self, display: impl Display) -> StdoutResult;
+    fn print(&mut self, display: impl Display) -> StdoutResult<'_, Self>;
 
-    fn draw(&mut self, loc: impl AsLocationTuple, display: impl Display) -> StdoutResult;
+    fn draw(&mut self, loc: impl AsLocationTuple, display: impl Display) -> StdoutResult<'_, Self>;
 }
 
-impl StdoutExt for Stdout {
-    fn move_cursor(&mut self, loc: impl AsLocationTuple) -> StdoutResult {
+impl<W: Write> StdoutExt for W {
+    fn move_cursor(&mut self, loc: impl AsLocationTuple) -> StdoutResult<'_, Self> {
         let (c, l) = loc.as_loc_tuple();
         self.queue(MoveTo(c, l))
     }
 
-    fn draw(&mut self, loc: impl AsLocationTuple, display: impl Display) -> StdoutResult {
+    fn draw(&mut self, loc: impl AsLocationTuple, display: impl Display) -> StdoutResult<'_, Self> {
         self.move_cursor(loc)?.print(display)
     }
 
-    fn clear_all(&mut self) -> StdoutResult {
+    fn clear_all(&mut self) -> StdoutResult<'_, Self> {
         self.queue(Clear(crossterm::terminal::ClearType::All))
     }
 
-    fn print(&mut self, display: impl Display) -> StdoutResult {
+    fn print(&mut self, display: impl Display) -> StdoutResult<'_, Self> {
         self.queue(Print(display))
     }
 }
@@ -0,0 +1,316 @@
+//! Color themes.
+//!
+//! Every style the game draws with lives in a [`Theme`]. The canvas carries
+//! the active theme so drawables pick their styles from it.
+
+use crossterm::style::{Color, ContentStyle, Stylize};
+
+use crate::{biome::Biome, color_depth::ColorDepth};
+
+#[derive(Clone)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Biomes may recolor the river and banks, see [`Theme::in_biome`]
+    pub biome_colors: bool,
+    pub river: ContentStyle,
+    pub bank: ContentStyle,
+    /// Decorations on the banks
+    pub decoration: ContentStyle,
+    pub player: ContentStyle,
+    pub enemy: ContentStyle,
+    /// Enemies waiting on the bank
+    pub enemy_on_bank: ContentStyle,
+    pub helicopter: ContentStyle,
+    pub fuel: ContentStyle,
+    /// Fuel depots catching the light
+    pub fuel_glint: ContentStyle,
+    pub bullet: ContentStyle,
+    /// Fading trail behind bullets
+    pub bullet_trail: ContentStyle,
+    pub enemy_bullet: ContentStyle,
+    /// Sparks of an explosion
+    pub explosion: ContentStyle,
+    /// Smoke an explosion leaves behind
+    pub smoke: ContentStyle,
+    pub bridge: ContentStyle,
+    pub bridge_wreck: ContentStyle,
+    pub tunnel: ContentStyle,
+    pub pad: ContentStyle,
+    /// Bomb crates
+    pub bomb: ContentStyle,
+    /// Rocks and buoys in the river
+    pub obstacle: ContentStyle,
+    /// The screen while a bomb goes off
+    pub bomb_flash: ContentStyle,
+    pub warp: ContentStyle,
+    pub hud: ContentStyle,
+    pub popup: ContentStyle,
+    pub popup_focus: ContentStyle,
+    pub popup_disabled: ContentStyle,
+}
+
+impl Theme {
+    /// Blue river between green banks
+    pub fn classic() -> Theme {
+        Theme {
+            name: "Classic",
+            biome_colors: true,
+            river: ContentStyle::new().on_blue(),
+            bank: ContentStyle::new().on_green(),
+            decoration: ContentStyle::new().black().on_green(),
+            player: ContentStyle::new().white().on_blue(),
+            enemy: ContentStyle::new().red().on_blue(),
+            enemy_on_bank: ContentStyle::new().red().on_green(),
+            helicopter: ContentStyle::new().magenta().on_blue(),
+            fuel: ContentStyle::new().yellow().on_blue(),
+            fuel_glint: ContentStyle::new().white().on_blue(),
+            bullet: ContentStyle::new().cyan().on_blue(),
+            bullet_trail: ContentStyle::new().dark_cyan().on_blue(),
+            enemy_bullet: ContentStyle::new().red().on_blue(),
+            explosion: ContentStyle::new().yellow().on_blue(),
+            smoke: ContentStyle::new().dark_grey().on_blue(),
+            bridge: ContentStyle::new().black().on_dark_yellow(),
+            bridge_wreck: ContentStyle::new().dark_yellow().on_blue(),
+            tunnel: ContentStyle::new().black().on_cyan(),
+            pad: ContentStyle::new().yellow().on_green(),
+            bomb: ContentStyle::new().red().on_white(),
+            obstacle: ContentStyle::new().black().on_blue(),
+            bomb_flash: ContentStyle::new().on_white(),
+            warp: ContentStyle::new().white().on_dark_blue(),
+            hud: ContentStyle::new().black().on_white(),
+            popup: ContentStyle::new().white().on_black(),
+            popup_focus: ContentStyle::new().black().on_white().bold(),
+            popup_disabled: ContentStyle::new().dark_grey().on_black(),
+        }
+    }
+
+    /// Greys only, for terminals with poor color support
+    pub fn monochrome() -> Theme {
+        Theme {
+            name: "Monochrome",
+            biome_colors: false,
+            river: ContentStyle::new().on_black(),
+            bank: ContentStyle::new().on_dark_grey(),
+            decoration: ContentStyle::new().grey().on_dark_grey(),
+            player: ContentStyle::new().white().on_black().bold(),
+            enemy: ContentStyle::new().white().on_black(),
+            enemy_on_bank: ContentStyle::new().white().on_dark_grey(),
+            helicopter: ContentStyle::new().white().on_black(),
+            fuel: ContentStyle::new().grey().on_black(),
+            fuel_glint: ContentStyle::new().white().on_black().bold(),
+            bullet: ContentStyle::new().white().on_black(),
+            bullet_trail: ContentStyle::new().dark_grey().on_black(),
+            enemy_bullet: ContentStyle::new().grey().on_black(),
+            explosion: ContentStyle::new().white().on_black(),
+            smoke: ContentStyle::new().dark_grey().on_black(),
+            bridge: ContentStyle::new().black().on_grey(),
+            bridge_wreck: ContentStyle::new().grey().on_black(),
+            tunnel: ContentStyle::new().black().on_white(),
+            pad: ContentStyle::new().white().on_dark_grey(),
+            bomb: ContentStyle::new().black().on_white(),
+            obstacle: ContentStyle::new().grey().on_black().bold(),
+            bomb_flash: ContentStyle::new().on_white(),
+            warp: ContentStyle::new().white().on_black(),
+            hud: ContentStyle::new().black().on_white(),
+            popup: ContentStyle::new().white().on_black(),
+            popup_focus: ContentStyle::new().black().on_white().bold(),
+            popup_disabled: ContentStyle::new().dark_grey().on_black(),
+        }
+    }
+
+    /// Deeper, softer colors for terminals with 256 colors or more
+    pub fn vivid() -> Theme {
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+        let (river, bank, popup) = (rgb(24, 78, 160), rgb(46, 125, 50), rgb(20, 20, 28));
+        let on_river = |fg| ContentStyle::new().with(fg).on(river);
+        Theme {
+            name: "Vivid",
+            biome_colors: true,
+            river: ContentStyle::new().on(river),
+            bank: ContentStyle::new().on(bank),
+            decoration: ContentStyle::new().with(rgb(20, 60, 20)).on(bank),
+            player: on_river(rgb(250, 250, 250)),
+            enemy: on_river(rgb(230, 57, 70)),
+            enemy_on_bank: ContentStyle::new().with(rgb(230, 57, 70)).on(bank),
+            helicopter: on_river(rgb(200, 80, 200)),
+            fuel: on_river(rgb(255, 200, 40)),
+            fuel_glint: on_river(rgb(255, 250, 220)),
+            bullet: on_river(rgb(120, 230, 255)),
+            bullet_trail: on_river(rgb(60, 140, 180)),
+            enemy_bullet: on_river(rgb(255, 110, 90)),
+            explosion: on_river(rgb(255, 170, 40)),
+            smoke: on_river(rgb(110, 110, 120)),
+            bridge: ContentStyle::new().black().on(rgb(180, 140, 60)),
+            bridge_wreck: on_river(rgb(150, 110, 50)),
+            tunnel: ContentStyle::new().black().on(rgb(80, 200, 210)),
+            pad: ContentStyle::new().with(rgb(255, 220, 60)).on(bank),
+            bomb: ContentStyle::new()
+                .with(rgb(200, 30, 30))
+                .on(rgb(245, 245, 245)),
+            obstacle: on_river(rgb(150, 140, 130)),
+            bomb_flash: ContentStyle::new().on(rgb(255, 250, 235)),
+            warp: ContentStyle::new().white().on(rgb(16, 40, 110)),
+            hud: ContentStyle::new().black().on(rgb(230, 230, 230)),
+            popup: ContentStyle::new().white().on(popup),
+            popup_focus: ContentStyle::new().black().on_white().bold(),
+            popup_disabled: ContentStyle::new().with(rgb(110, 110, 110)).on(popup),
+        }
+    }
+
+    /// The theme that suits a terminal with `depth` best
+    pub fn for_depth(depth: ColorDepth) -> Theme {
+        match depth {
+            ColorDepth::Mono => Theme::monochrome(),
+            ColorDepth::Ansi16 => Theme::classic(),
+            ColorDepth::Ansi256 | ColorDepth::TrueColor => Theme::vivid(),
+        }
+    }
+
+    /// This theme with every color a terminal with `depth` can not show
+    /// replaced by the nearest one it can.
+    pub fn fitted(&self, depth: ColorDepth) -> Theme {
+        let mut theme = self.clone();
+        for style in theme.styles_mut() {
+            style.foreground_color = style.foreground_color.map(|color| depth.fit(color));
+            style.background_color = style.background_color.map(|color| depth.fit(color));
+        }
+        theme
+    }
+
+    /// This theme in `biome`: whatever is drawn on the river or the banks
+    /// gets the biome's background colors.
+    pub fn in_biome(&self, biome: &Biome) -> Theme {
+        let mut theme = self.clone();
+        if !self.biome_colors {
+            return theme;
+        }
+
+        let swaps = [
+            (self.river.background_color, biome.river),
+            (self.bank.background_color, biome.bank),
+        ];
+        for style in theme.scene_mut() {
+            for (from, to) in swaps {
+                if let (Some(from), Some(to)) = (from, to) {
+                    if style.background_color == Some(from) {
+                        style.background_color = Some(to);
+                        break;
+                    }
+                }
+            }
+        }
+        theme
+    }
+
+    /// Every style drawn on the river or the banks
+    fn scene_mut(&mut self) -> [&mut ContentStyle; 17] {
+        [
+            &mut self.river,
+            &mut self.bank,
+            &mut self.decoration,
+            &mut self.player,
+            &mut self.enemy,
+            &mut self.enemy_on_bank,
+            &mut self.helicopter,
+            &mut self.fuel,
+            &mut self.fuel_glint,
+            &mut self.bullet,
+            &mut self.bullet_trail,
+            &mut self.enemy_bullet,
+            &mut self.explosion,
+            &mut self.smoke,
+            &mut self.bridge_wreck,
+            &mut self.pad,
+            &mut self.obstacle,
+        ]
+    }
+
+    /// Every style
+    fn styles_mut(&mut self) -> [&mut ContentStyle; 26] {
+        let Theme {
+            name: _,
+            biome_colors: _,
+            river,
+            bank,
+            decoration,
+            player,
+            enemy,
+            enemy_on_bank,
+            helicopter,
+            fuel,
+            fuel_glint,
+            bullet,
+            bullet_trail,
+            enemy_bullet,
+            explosion,
+            smoke,
+            bridge,
+            bridge_wreck,
+            tunnel,
+            pad,
+            bomb,
+            obstacle,
+            bomb_flash,
+            warp,
+            hud,
+            popup,
+            popup_focus,
+            popup_disabled,
+        } = self;
+        [
+            river,
+            bank,
+            decoration,
+            player,
+            enemy,
+            enemy_on_bank,
+            helicopter,
+            fuel,
+            fuel_glint,
+            bullet,
+            bullet_trail,
+            enemy_bullet,
+            explosion,
+            smoke,
+            bridge,
+            bridge_wreck,
+            tunnel,
+            pad,
+            bomb,
+            obstacle,
+            bomb_flash,
+            warp,
+            hud,
+            popup,
+            popup_focus,
+            popup_disabled,
+        ]
+    }
+
+    /// Every built-in theme, in the order the settings screen cycles them
+    pub fn all() -> Vec<Theme> {
+        vec![Theme::classic(), Theme::monochrome(), Theme::vivid()]
+    }
+
+    pub fn by_name(name: &str) -> Option<Theme> {
+        Theme::all()
+            .into_iter()
+            .find(|theme| theme.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The built-in theme after this one, wrapping around
+    pub fn next(&self) -> Theme {
+        let themes = Theme::all();
+        let index = themes
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .map_or(0, |index| (index + 1) % themes.len());
+        themes[index].clone()
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::classic()
+    }
+}
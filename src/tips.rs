@@ -0,0 +1,157 @@
+//! One-time tips for mistakes the player keeps making.
+//!
+//! Mistakes noticed during a run turn into a tip shown at the start of the
+//! next one. Each tip is shown once; `tips.conf` remembers which ones were:
+//!
+//! ```text
+//! shown = out_of_fuel
+//! pending = grazing_bank
+//! ```
+
+use std::{fs, path::Path};
+
+pub const TIPS_FILE: &str = "tips.conf";
+
+/// Ticks a tip stays on screen
+const TIP_TICKS: u16 = 120;
+
+/// Separate grazes of the bank in a run before it counts as a mistake
+const GRAZES: u16 = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Tip {
+    GrazingBank,
+    ShootingFuel,
+    OutOfFuel,
+}
+
+impl Tip {
+    const ALL: [Tip; 3] = [Tip::GrazingBank, Tip::ShootingFuel, Tip::OutOfFuel];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Tip::GrazingBank => "grazing_bank",
+            Tip::ShootingFuel => "shooting_fuel",
+            Tip::OutOfFuel => "out_of_fuel",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Tip> {
+        Tip::ALL.into_iter().find(|tip| tip.name() == name)
+    }
+
+    pub fn text(&self) -> &'static str {
+        match self {
+            Tip::GrazingBank => "Tip: keep to the middle of the river, the banks are deadly",
+            Tip::ShootingFuel => "Tip: low on fuel? Fly over depots instead of shooting them",
+            Tip::OutOfFuel => "Tip: on an empty tank you glide for a while, find a depot",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Tips {
+    shown: Vec<Tip>,
+    /// Mistakes noticed so far, tipped about on the next run
+    pending: Vec<Tip>,
+    /// Tip on screen and the ticks it has left
+    pub showing: Option<(Tip, u16)>,
+    grazes: u16,
+    grazing: bool,
+}
+
+impl Tips {
+    /// Tips already shown or pending, as saved in `path`.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Tips> {
+        let mut tips = Tips::default();
+        tips.apply(&fs::read_to_string(path)?);
+        Ok(tips)
+    }
+
+    fn apply(&mut self, config: &str) {
+        for line in config.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+
+            match (key.trim(), Tip::from_name(value.trim())) {
+                ("shown", Some(tip)) => self.shown.push(tip),
+                ("pending", Some(tip)) => self.pending.push(tip),
+                _ => {}
+            }
+        }
+    }
+
+    fn to_config(&self) -> String {
+        let shown = self
+            .shown
+            .iter()
+            .map(|tip| format!("shown = {}\n", tip.name()));
+        let pending = self
+            .pending
+            .iter()
+            .map(|tip| format!("pending = {}\n", tip.name()));
+        shown.chain(pending).collect()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        fs::write(path, self.to_config())
+    }
+
+    /// Remember a mistake, unless its tip was already shown.
+    pub fn notice(&mut self, tip: Tip) {
+        if !self.shown.contains(&tip) && !self.pending.contains(&tip) {
+            self.pending.push(tip);
+        }
+    }
+
+    /// Count a tick spent next to the bank, or not. Every new graze counts
+    /// once, however long it lasts.
+    pub fn graze(&mut self, grazing: bool) {
+        if grazing && !self.grazing {
+            self.grazes += 1;
+            if self.grazes == GRAZES {
+                self.notice(Tip::GrazingBank);
+            }
+        }
+        self.grazing = grazing;
+    }
+
+    /// A new run starts: put up the oldest pending tip.
+    pub fn start_run(&mut self) {
+        self.grazes = 0;
+        self.grazing = false;
+        if !self.pending.is_empty() {
+            let tip = self.pending.remove(0);
+            self.shown.push(tip);
+            self.showing = Some((tip, TIP_TICKS));
+        }
+    }
+
+    /// Count down the tip on screen.
+    pub fn tick(&mut self) {
+        self.showing = match self.showing {
+            Some((tip, ticks)) if ticks > 1 => Some((tip, ticks - 1)),
+            _ => None,
+        };
+    }
+} // end of Tips implementation.
+
+#[cfg(test)]
+mod tests {
+    use super::{Tip, Tips};
+
+    #[test]
+    fn tips_are_shown_once() {
+        let mut tips = Tips::default();
+        tips.notice(Tip::OutOfFuel);
+        tips.start_run();
+        assert_eq!(tips.showing.map(|(tip, _)| tip), Some(Tip::OutOfFuel));
+
+        let mut loaded = Tips::default();
+        loaded.apply(&tips.to_config());
+        loaded.notice(Tip::OutOfFuel);
+        loaded.start_run();
+        assert!(loaded.showing.is_none());
+    }
+}
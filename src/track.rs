@@ -0,0 +1,259 @@
+//! Custom tracks: a fixed river, line by line, with markers for what spawns
+//! on it, flown with `--track <file>` instead of a random river. A run can
+//! be saved as one with `--save-track <file>`, to be shared and flown again.
+//!
+//! A track file has a line of text per line of river, the first one flown
+//! first:
+//!
+//! ```text
+//! # left bank, right bank, then what is on the line
+//! 30 50
+//! 30 50 fuel 40
+//! 31 52 island 38 44 enemy 33 helicopter 48
+//! 31 52 bridge
+//! ```
+//!
+//! An `island` is given by its first and end column, a marker by a spawn
+//! kind (as in `stages.conf`) and its column. Markers stand on water, but
+//! strafers and recharge pads on the bank right next to it. A track is flown
+//! over and over until the run ends.
+
+use std::{fmt::Write, fs, io, path::Path};
+
+use crate::world::SpawnKind;
+
+/// A line of river of a [`Track`]
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TrackLine {
+    /// Left and right bank, like a line of [`crate::World::map`]
+    pub banks: (u16, u16),
+    pub island: Option<(u16, u16)>,
+    /// A bridge crosses the river here
+    pub bridge: bool,
+    /// Entities spawning on the line, and their column
+    pub spawns: Vec<(SpawnKind, u16)>,
+}
+
+impl TrackLine {
+    /// `<left> <right> [island <start> <end>] [bridge] [<kind> <column>]...`
+    fn parse(line: &str) -> Option<TrackLine> {
+        let mut fields = line.split_whitespace();
+        let mut number = || fields.next()?.parse::<u16>().ok();
+        let mut line = TrackLine {
+            banks: (number()?, number()?),
+            ..TrackLine::default()
+        };
+        while let Some(field) = fields.next() {
+            let mut number = || fields.next()?.parse::<u16>().ok();
+            match field {
+                "island" => line.island = Some((number()?, number()?)),
+                "bridge" => line.bridge = true,
+                kind => line.spawns.push((SpawnKind::from_name(kind)?, number()?)),
+            }
+        }
+        let (left, right) = line.banks;
+        let island_fits = line
+            .island
+            .is_none_or(|(start, end)| left < start && start < end && end < right);
+        let markers_fit = line.spawns.iter().all(|(kind, c)| line.fits(*kind, *c));
+        (left < right && island_fits && markers_fit).then_some(line)
+    }
+
+    /// Column `c` is water: between the banks and off the island.
+    pub fn is_water(&self, c: u16) -> bool {
+        let (left, right) = self.banks;
+        let on_island = self
+            .island
+            .is_some_and(|(start, end)| (start..end).contains(&c));
+        (left..right).contains(&c) && !on_island
+    }
+
+    /// A `kind` marker may stand at column `c`: strafers and recharge pads on
+    /// the bank right next to the river, anything else on water.
+    pub fn fits(&self, kind: SpawnKind, c: u16) -> bool {
+        let (left, right) = self.banks;
+        match kind {
+            SpawnKind::Strafer | SpawnKind::Pad => c + 1 == left || c == right,
+            _ => self.is_water(c),
+        }
+    }
+} // end of TrackLine implementation.
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Track {
+    pub lines: Vec<TrackLine>,
+}
+
+impl Track {
+    /// The track saved at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Track> {
+        let (track, ignored) = Track::parse(&fs::read_to_string(path)?);
+        let invalid = |what: String| io::Error::new(io::ErrorKind::InvalidData, what);
+        if let Some(line) = ignored.first() {
+            return Err(invalid(format!("not a line of river: {line}")));
+        }
+        if track.lines.is_empty() {
+            return Err(invalid("the track has no river".to_string()));
+        }
+        Ok(track)
+    }
+
+    /// Save the track at `path`.
+    pub fn to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.text())
+    }
+
+    /// Line `n` of the track, which starts over once flown through
+    pub fn line(&self, n: usize) -> &TrackLine {
+        &self.lines[n % self.lines.len()]
+    }
+
+    /// The track in `text`, and the lines not understood.
+    fn parse(text: &str) -> (Track, Vec<String>) {
+        let mut track = Track::default();
+        let mut ignored = Vec::new();
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            match TrackLine::parse(trimmed) {
+                Some(line) => track.lines.push(line),
+                None => ignored.push(line.to_string()),
+            }
+        }
+        (track, ignored)
+    }
+
+    /// The track as saved, see [`Track::to_file`]
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for line in &self.lines {
+            let (left, right) = line.banks;
+            let _ = write!(text, "{left} {right}");
+            if let Some((start, end)) = line.island {
+                let _ = write!(text, " island {start} {end}");
+            }
+            if line.bridge {
+                text += " bridge";
+            }
+            for (kind, c) in &line.spawns {
+                let _ = write!(text, " {} {c}", kind.name());
+            }
+            text.push('\n');
+        }
+        text
+    }
+} // end of Track implementation.
+
+#[cfg(test)]
+mod tests {
+    use super::{Track, TrackLine};
+    use crate::{
+        world::{SpawnKind, WorldStatus},
+        World,
+    };
+
+    const TEXT: &str = "\
+# a short one
+30 50
+30 50 fuel 40
+31 52 island 38 44 enemy 33 helicopter 48
+31 52 bridge
+";
+
+    #[test]
+    fn tracks_are_written_and_read_back() {
+        let (track, ignored) = Track::parse(TEXT);
+        assert!(ignored.is_empty());
+        assert_eq!(
+            track.lines[2],
+            TrackLine {
+                banks: (31, 52),
+                island: Some((38, 44)),
+                bridge: false,
+                spawns: vec![(SpawnKind::Enemy, 33), (SpawnKind::Helicopter, 48)],
+            }
+        );
+        assert_eq!(Track::parse(&track.text()), (track.clone(), Vec::new()));
+
+        // markers stand in the river, strafers and pads right next to it
+        let text = "30 50 strafer 29 pad 50\n30 50 fuel 20\n30 50 pad 40";
+        let (track, ignored) = Track::parse(text);
+        assert_eq!(track.lines.len(), 1);
+        assert_eq!(ignored.len(), 2);
+
+        let (_, ignored) = Track::parse("50 30\n30 50 dragon 40\n30 50 island 20 40\n30");
+        assert_eq!(ignored.len(), 4);
+    }
+
+    #[test]
+    fn tracks_are_flown() {
+        let (track, _) = Track::parse(TEXT);
+        let mut world = World::new(80, 24);
+        world.set_track(track.clone());
+        assert_eq!(world.map[23], (30, 50));
+        assert_eq!(world.map[0], track.line(23).banks);
+        world.fly(4);
+        assert!(world
+            .map
+            .iter()
+            .all(|banks| *banks == (30, 50) || *banks == (31, 52)));
+        assert!(!world.bridges.is_empty() || !world.enemies.is_empty());
+    }
+
+    #[test]
+    fn tracks_wider_than_the_screen_are_squeezed_onto_it() {
+        let (track, _) = Track::parse("0 80\n0 79\n78 120 island 79 90");
+        let mut world = World::new(80, 24);
+        world.set_track(track);
+        assert_eq!(world.map[23], (0, 79));
+        assert_eq!(world.map[22], (0, 79));
+        assert_eq!(world.map[21], (76, 79));
+        assert_eq!(world.islands[21], None);
+        world.fly(4);
+        assert!(world.map.iter().all(|(_, right)| *right < 80));
+    }
+
+    #[test]
+    fn recorded_runs_fly_the_same_river() {
+        let mut world = World::new(80, 24);
+        world.set_seed(3);
+        world.record_track();
+        world.fly(100);
+        let recording = world.recording.clone().unwrap();
+        assert_eq!(recording.lines.len(), 24 + world.traveled as usize);
+        assert!(recording.lines.iter().any(|line| !line.spawns.is_empty()));
+
+        let mut replay = World::new(80, 24);
+        replay.set_track(recording.clone());
+        replay.fly(world.traveled as u16);
+        assert_eq!(replay.map, world.map);
+    }
+
+    #[test]
+    fn tracks_take_queued_spawns_and_keep_markers_through_tunnels() {
+        let (track, _) = Track::parse("30 50 fuel 40");
+        let mut world = World::new(80, 24);
+        world.set_track(track);
+        world.record_track();
+        world.stages.queue(SpawnKind::Ship);
+        world.fly(4);
+        assert!(!world.enemies.is_empty());
+
+        // the river rushing by in a tunnel still brings its markers
+        world.fuels.clear();
+        let traveled = world.traveled;
+        world.status = WorldStatus::Warping { lines_left: 8 };
+        world.fly(2);
+        assert_eq!(world.traveled, traveled + 8);
+        assert_eq!(world.fuels.len(), 8);
+        let recording = world.recording.unwrap();
+        assert!(recording
+            .lines
+            .iter()
+            .rev()
+            .take(8)
+            .all(|line| line.spawns.len() == 1));
+    }
+}
@@ -0,0 +1,545 @@
+//! Versus races: two players fly the same seeded river on their own
+//! machines, and the first to fly the target distance wins.
+//!
+//! One game hosts with `--host <port>`, on localhost only, or with
+//! `--host <address:port>` to listen on another address such as `0.0.0.0`.
+//! The other joins with `--join <address:port>`. The host sends the protocol
+//! version, the seed, the target and its [`Level`], and the guest answers
+//! `ready`, or `refuse` and why when anything differs, so mismatched games
+//! stop before the race instead of drifting apart in it. Then both send
+//! where they are once per tick, and a hash of their river every
+//! [`SYNC_LINES`] lines, one line each over TCP:
+//!
+//! ```text
+//! race 3 1234 3000 80 - 14695981039346656037
+//! ready
+//! at 250 812 1 48600 -
+//! sync 800 9182736455463728190
+//! ```
+//!
+//! (`race <protocol> <seed> <target> <width> <track> <script>`, `-` for no
+//! track or script, and `at <score> <distance> <still flying> <ms played>
+//! <ms to the target>`).
+//! Both rivers are generated from the seed, so only this progress needs to
+//! travel; the hashes catch rivers that came out different anyway. Whoever
+//! flew the target in less play time wins, the host on a tie.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+/// Lines of river to fly unless `--target` says otherwise
+pub const DEFAULT_TARGET: u32 = 3000;
+
+/// Version of the lines below, both games have to speak the same
+pub const PROTOCOL: u32 = 3;
+
+/// Lines of river between two hashes of it
+pub const SYNC_LINES: u32 = 100;
+
+/// Where the rival is, as last heard
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RivalStatus {
+    pub score: u32,
+    pub traveled: u32,
+    pub flying: bool,
+    /// Milliseconds played
+    pub played: u64,
+    /// Milliseconds played when the target was reached
+    pub finished: Option<u64>,
+}
+
+impl RivalStatus {
+    fn to_line(self) -> String {
+        let finished = self
+            .finished
+            .map_or("-".to_string(), |played| played.to_string());
+        format!(
+            "at {} {} {} {} {finished}\n",
+            self.score,
+            self.traveled,
+            u8::from(self.flying),
+            self.played
+        )
+    }
+
+    fn parse(line: &str) -> Option<RivalStatus> {
+        let mut fields = line.strip_prefix("at ")?.split(' ');
+        Some(RivalStatus {
+            score: fields.next()?.parse().ok()?,
+            traveled: fields.next()?.parse().ok()?,
+            flying: fields.next()? == "1",
+            played: fields.next()?.parse().ok()?,
+            finished: match fields.next()? {
+                "-" => None,
+                played => Some(played.parse().ok()?),
+            },
+        })
+    }
+} // end of RivalStatus implementation.
+
+/// What shapes the river besides the seed; both games need the same.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Level {
+    /// Columns of the screen, the river is laid out for them
+    pub width: u16,
+    /// Hashes of the track and the game mode script flown, if any, see
+    /// [`crate::world::content_hash`]
+    pub track: Option<u64>,
+    pub script: Option<u64>,
+}
+
+impl Level {
+    fn to_fields(self) -> String {
+        let hash = |hash: Option<u64>| hash.map_or("-".to_string(), |hash| hash.to_string());
+        format!("{} {} {}", self.width, hash(self.track), hash(self.script))
+    }
+
+    fn parse(fields: &[&str]) -> Option<Level> {
+        let hash = |field: &str| match field {
+            "-" => Some(None),
+            hash => hash.parse().ok().map(Some),
+        };
+        let [width, track, script] = fields else {
+            return None;
+        };
+        Some(Level {
+            width: width.parse().ok()?,
+            track: hash(track)?,
+            script: hash(script)?,
+        })
+    }
+
+    /// Why a race between `self` and the host's `level` would not be fair.
+    fn mismatch(&self, level: &Level) -> Option<String> {
+        if self.width != level.width {
+            return Some(format!(
+                "the screens are {} and {} columns wide",
+                level.width, self.width
+            ));
+        }
+        let differ = |what, ours: Option<u64>, theirs: Option<u64>| match (ours, theirs) {
+            (Some(_), None) | (None, Some(_)) => Some(format!("only one game flies a {what}")),
+            (Some(ours), Some(theirs)) if ours != theirs => Some(format!("the {what}s differ")),
+            _ => None,
+        };
+        differ("track", self.track, level.track)
+            .or_else(|| differ("script", self.script, level.script))
+    }
+} // end of Level implementation.
+
+/// A line from the rival
+enum Message {
+    Status(RivalStatus),
+    /// Hash of the river up to a line
+    Sync(u32, u64),
+}
+
+impl Message {
+    fn parse(line: &str) -> Option<Message> {
+        if let Some(sync) = line.strip_prefix("sync ") {
+            let (line, hash) = sync.split_once(' ')?;
+            return Some(Message::Sync(line.parse().ok()?, hash.parse().ok()?));
+        }
+        RivalStatus::parse(line).map(Message::Status)
+    }
+} // end of Message implementation.
+
+pub struct Versus {
+    /// Lines of river to fly to win
+    pub target: u32,
+    /// The rival's last status, `None` until the first one arrives
+    pub rival: Option<RivalStatus>,
+    /// The rival is still there
+    pub connected: bool,
+    /// The rival's river is not ours, see [`SYNC_LINES`]
+    pub desynced: bool,
+    /// Milliseconds played when we reached the target
+    pub finished: Option<u64>,
+    /// Ties go to the host
+    host: bool,
+    /// Our river hashes and the rival's, by line, until both are in
+    syncs: HashMap<u32, u64>,
+    rival_syncs: HashMap<u32, u64>,
+    stream: TcpStream,
+    /// Lines for the writing thread, so a slow network never holds a tick
+    outgoing: Sender<String>,
+    updates: Receiver<Message>,
+}
+
+impl Versus {
+    /// Wait for a rival at `at`, a port on localhost or an `address:port`,
+    /// and race on `seed` to `target` over `level`.
+    pub fn host(at: &str, seed: u64, target: u32, level: &Level) -> io::Result<Versus> {
+        let listener = match at.parse::<u16>() {
+            Ok(port) => TcpListener::bind(("127.0.0.1", port))?,
+            Err(_) => TcpListener::bind(at)?,
+        };
+        Versus::accept(&listener, seed, target, level)
+    }
+
+    /// Wait for a rival on `listener` and race on `seed` to `target` over
+    /// `level`, unless the rival refuses.
+    fn accept(listener: &TcpListener, seed: u64, target: u32, level: &Level) -> io::Result<Versus> {
+        let (mut stream, _) = listener.accept()?;
+        let race = format!("race {PROTOCOL} {seed} {target} {}\n", level.to_fields());
+        stream.write_all(race.as_bytes())?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let refused = |why: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("the rival refused the race: {why}"),
+            )
+        };
+        match line.trim_end() {
+            "ready" => Versus::start(stream, reader, target, true),
+            answer => Err(refused(
+                answer
+                    .strip_prefix("refuse ")
+                    .unwrap_or("it runs another version of the game"),
+            )),
+        }
+    }
+
+    /// Join the race hosted at `address`, if it is over the same `level`.
+    /// Returns it with its seed.
+    pub fn join(address: &str, level: &Level) -> io::Result<(Versus, u64)> {
+        let mut stream = TcpStream::connect(address)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a race host");
+        let fields: Vec<&str> = line
+            .trim_end()
+            .strip_prefix("race ")
+            .ok_or_else(invalid)?
+            .split(' ')
+            .collect();
+        let protocol: u32 = fields
+            .first()
+            .and_then(|protocol| protocol.parse().ok())
+            .ok_or_else(invalid)?;
+        let mut refuse = |why: String| {
+            // the host hears why too
+            stream.write_all(format!("refuse {why}\n").as_bytes())?;
+            Err(io::Error::new(io::ErrorKind::InvalidData, why))
+        };
+        if protocol != PROTOCOL {
+            return refuse(format!(
+                "the games run race protocols {protocol} and {PROTOCOL}"
+            ));
+        }
+        let [_, seed, target, host_level @ ..] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        let (Ok(seed), Ok(target), Some(host_level)) =
+            (seed.parse(), target.parse(), Level::parse(host_level))
+        else {
+            return Err(invalid());
+        };
+        if let Some(why) = level.mismatch(&host_level) {
+            return refuse(why);
+        }
+        stream.write_all(b"ready\n")?;
+        // the reader may hold the rival's first statuses already
+        Ok((Versus::start(stream, reader, target, false)?, seed))
+    }
+
+    /// Listen to the rival on `reader`, a clone of `stream`, and write to it,
+    /// both in the background.
+    fn start(
+        stream: TcpStream,
+        reader: BufReader<TcpStream>,
+        target: u32,
+        host: bool,
+    ) -> io::Result<Versus> {
+        stream.set_nodelay(true)?;
+        let (sender, updates) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if let Some(message) = Message::parse(&line) {
+                    if sender.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let (outgoing, lines) = mpsc::channel::<String>();
+        let mut writer = stream.try_clone()?;
+        thread::spawn(move || {
+            for line in lines {
+                if writer.write_all(line.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Versus {
+            target,
+            rival: None,
+            connected: true,
+            desynced: false,
+            finished: None,
+            host,
+            syncs: HashMap::new(),
+            rival_syncs: HashMap::new(),
+            stream,
+            outgoing,
+            updates,
+        })
+    }
+
+    /// Hand `line` to the writing thread. A rival gone is noted, not an
+    /// error.
+    fn write(&mut self, line: String) {
+        if self.connected && self.outgoing.send(line).is_err() {
+            self.connected = false;
+        }
+    }
+
+    /// Tell the rival where we are.
+    pub fn send(&mut self, status: RivalStatus) {
+        self.write(status.to_line());
+    }
+
+    /// Tell the rival the hash of our river up to `line`.
+    pub fn sync(&mut self, line: u32, hash: u64) {
+        self.syncs.insert(line, hash);
+        self.write(format!("sync {line} {hash}\n"));
+        self.compare_syncs();
+    }
+
+    /// Catch up with what the rival sent since the last call.
+    pub fn poll(&mut self) {
+        loop {
+            match self.updates.try_recv() {
+                Ok(message) => self.receive(message),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    break;
+                }
+            }
+        }
+    }
+
+    fn receive(&mut self, message: Message) {
+        match message {
+            Message::Status(status) => self.rival = Some(status),
+            Message::Sync(line, hash) => {
+                self.rival_syncs.insert(line, hash);
+                self.compare_syncs();
+            }
+        }
+    }
+
+    /// Check the hashes both sides have, and forget them.
+    fn compare_syncs(&mut self) {
+        let lines: Vec<u32> = self
+            .rival_syncs
+            .keys()
+            .filter(|line| self.syncs.contains_key(line))
+            .copied()
+            .collect();
+        for line in lines {
+            if self.syncs.remove(&line) != self.rival_syncs.remove(&line) {
+                self.desynced = true;
+            }
+        }
+    }
+
+    /// Whether we won, once it is known. `played` is how long we have
+    /// played, in milliseconds.
+    pub fn outcome(&self, played: u64) -> Option<bool> {
+        let rival = self.rival.filter(|_| self.connected);
+        match (self.finished, rival.and_then(|rival| rival.finished)) {
+            (Some(ours), Some(theirs)) => Some(ours < theirs || (ours == theirs && self.host)),
+            // the rival is gone, down, or past our time without the target
+            (Some(ours), None) => match rival {
+                None => Some(true).filter(|_| !self.connected),
+                Some(rival) => (!rival.flying || rival.played > ours).then_some(true),
+            },
+            (None, Some(theirs)) => (played > theirs).then_some(false),
+            (None, None) => None,
+        }
+    }
+} // end of Versus implementation.
+
+/// Hang up, so the rival hears the race is over for us.
+impl Drop for Versus {
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::{Level, Message, RivalStatus, Versus};
+
+    /// Take the rival's messages as they come until `done`, or until the
+    /// rival is gone.
+    fn wait_for(versus: &mut Versus, done: impl Fn(&Versus) -> bool) {
+        while !done(versus) {
+            let Ok(message) = versus.updates.recv() else {
+                versus.connected = false;
+                return;
+            };
+            versus.receive(message);
+        }
+    }
+
+    #[test]
+    fn rivals_share_seed_and_progress() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let guest = thread::spawn(move || Versus::join(&address, &Level::default()).unwrap());
+        let mut host = Versus::accept(&listener, 1234, 500, &Level::default()).unwrap();
+        let (mut guest, seed) = guest.join().unwrap();
+        assert_eq!((seed, guest.target), (1234, 500));
+
+        let status = RivalStatus {
+            score: 250,
+            traveled: 812,
+            flying: true,
+            played: 48_600,
+            finished: None,
+        };
+        guest.send(status);
+        guest.sync(100, 7);
+        host.sync(100, 8);
+        wait_for(&mut host, |host| host.rival.is_some());
+        assert_eq!(host.rival, Some(status));
+        wait_for(&mut host, |host| host.desynced);
+        assert!(host.desynced);
+
+        // the host's reader ends on the socket's EOF, hanging up the channel
+        drop(guest);
+        wait_for(&mut host, |_| false);
+        assert!(!host.connected);
+    }
+
+    #[test]
+    fn near_ties_have_one_winner() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let guest = thread::spawn(move || Versus::join(&address, &Level::default()).unwrap());
+        let mut host = Versus::accept(&listener, 1, 500, &Level::default()).unwrap();
+        let (mut guest, _) = guest.join().unwrap();
+
+        let status = |played, finished| RivalStatus {
+            score: 0,
+            traveled: 500,
+            flying: true,
+            played,
+            finished,
+        };
+        // both reached the target before hearing of the other
+        host.finished = Some(30_060);
+        guest.finished = Some(30_000);
+        assert_eq!(host.outcome(30_060), None);
+        host.rival = Some(status(30_060, Some(30_000)));
+        guest.rival = Some(status(30_120, Some(30_060)));
+        assert_eq!(
+            (host.outcome(30_120), guest.outcome(30_120)),
+            (Some(false), Some(true))
+        );
+
+        // the same time goes to the host
+        guest.finished = Some(30_060);
+        host.rival = Some(status(30_060, Some(30_060)));
+        assert_eq!(
+            (host.outcome(30_120), guest.outcome(30_120)),
+            (Some(true), Some(false))
+        );
+
+        // a rival past our time without the target lost
+        host.rival = Some(status(30_120, None));
+        assert_eq!(host.outcome(30_120), Some(true));
+        guest.finished = None;
+        assert_eq!(guest.outcome(30_000), None);
+
+        assert!(Message::parse("sync 100 x").is_none());
+    }
+
+    #[test]
+    fn hosts_of_another_version_are_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let host = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"race 2 1234 500\n").unwrap();
+            let mut answer = String::new();
+            BufReader::new(stream).read_line(&mut answer).unwrap();
+            answer
+        });
+        let error = Versus::join(&address, &Level::default()).err().unwrap();
+        assert_eq!(error.to_string(), "the games run race protocols 2 and 3");
+        assert_eq!(
+            host.join().unwrap(),
+            "refuse the games run race protocols 2 and 3\n"
+        );
+    }
+
+    #[test]
+    fn rivers_that_differ_are_refused_on_both_sides() {
+        let level = Level {
+            width: 80,
+            track: Some(7),
+            script: None,
+        };
+        let refusal = |guest_level: Level| {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            let guest = thread::spawn(move || Versus::join(&address, &guest_level).err());
+            let host = Versus::accept(&listener, 1, 500, &level).err();
+            let guest = guest.join().unwrap();
+            assert_eq!(host.is_some(), guest.is_some());
+            guest.map(|guest| (host.unwrap().to_string(), guest.to_string()))
+        };
+
+        assert_eq!(refusal(level), None);
+        let (host, guest) = refusal(Level {
+            width: 100,
+            ..level
+        })
+        .unwrap();
+        assert_eq!(guest, "the screens are 80 and 100 columns wide");
+        assert_eq!(host, format!("the rival refused the race: {guest}"));
+        let (_, guest) = refusal(Level {
+            track: None,
+            ..level
+        })
+        .unwrap();
+        assert_eq!(guest, "only one game flies a track");
+        let (_, guest) = refusal(Level {
+            track: Some(8),
+            ..level
+        })
+        .unwrap();
+        assert_eq!(guest, "the tracks differ");
+        let (_, guest) = refusal(Level {
+            script: Some(1),
+            ..level
+        })
+        .unwrap();
+        assert_eq!(guest, "only one game flies a script");
+    }
+}
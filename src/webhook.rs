@@ -0,0 +1,62 @@
+//! End-of-run summary posted to a webhook (Discord, Slack, ...) set with
+//! `webhook = <url>` in `settings.conf`.
+//!
+//! The post goes through a `curl` process started once the terminal is
+//! restored. It is left to finish on its own and reports a failure on the
+//! terminal itself, so a slow or failing endpoint never holds up quitting.
+
+use std::{
+    io,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use crate::locale::Locale;
+
+pub struct RunSummary {
+    pub score: u32,
+    pub section: u16,
+    /// Lines of river flown
+    pub traveled: u32,
+    /// How the run ended, e.g. `Fuel` or `Quit`
+    pub ending: String,
+    pub seed: u64,
+    pub played: Duration,
+    /// An input macro was played, see [`crate::input_macro`]
+    pub assisted: bool,
+    /// How numbers and the time played are written
+    pub locale: Locale,
+}
+
+impl RunSummary {
+    pub fn message(&self) -> String {
+        format!(
+            "RiverRaid run over: {} points, section {}, {} lines flown, {} played, \
+             ended: {} (seed {}){}",
+            self.locale.number(self.score),
+            self.section,
+            self.locale.number(self.traveled),
+            self.locale.duration(self.played),
+            self.ending,
+            self.seed,
+            if self.assisted { ", assisted" } else { "" }
+        )
+    }
+
+    /// Start posting the summary to `url`, without waiting for the answer.
+    pub fn post(&self, url: &str) -> io::Result<()> {
+        let message = self.message();
+        // Discord reads `content`, Slack reads `text`
+        let body = serde_json::json!({ "content": message, "text": message }).to_string();
+
+        // not waited for, see the module documentation
+        Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--max-time", "10"])
+            .args(["--header", "Content-Type: application/json"])
+            .args(["--data", &body, url])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()?;
+        Ok(())
+    }
+} // end of RunSummary implementation.
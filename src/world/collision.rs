@@ -0,0 +1,185 @@
+//! Bullets against what they can hit.
+//!
+//! Alive enemies and fuels are bucketed by line into a [`LineGrid`] once per
+//! collision check, so a bullet only looks at the few lines around it
+//! instead of every entity on screen.
+
+use crate::{
+    combat_log::CombatEvent,
+    entities::{Enemy, EntityStatus, Fuel, Location},
+    tips::Tip,
+    World,
+};
+
+/// Below this much gas, shooting a fuel depot earns a tip
+const LOW_GAS: u32 = 500;
+
+/// A rectangle of cells, bounds included
+pub struct Region {
+    pub top: u16,
+    pub bottom: u16,
+    pub left: u16,
+    pub right: u16,
+}
+
+impl Region {
+    /// `location` widened by `columns` on each side and `lines` above and
+    /// below.
+    pub fn around(location: &Location, columns: u16, lines: u16) -> Region {
+        Region {
+            top: location.l.saturating_sub(lines),
+            bottom: location.l.saturating_add(lines),
+            left: location.c.saturating_sub(columns),
+            right: location.c.saturating_add(columns),
+        }
+    }
+
+    pub fn contains(&self, c: u16, l: u16) -> bool {
+        (self.top..=self.bottom).contains(&l) && (self.left..=self.right).contains(&c)
+    }
+
+    pub fn intersects(&self, other: &Region) -> bool {
+        self.top <= other.bottom
+            && other.top <= self.bottom
+            && self.left <= other.right
+            && other.left <= self.right
+    }
+} // end of Region implementation.
+
+/// Something a bullet can hit, by index in its vector
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Target {
+    Enemy(usize),
+    Fuel(usize),
+}
+
+/// Alive targets, one bucket per screen line
+#[derive(Default)]
+pub struct LineGrid {
+    lines: Vec<Vec<Target>>,
+}
+
+impl LineGrid {
+    /// Bucket `enemies` and `fuels` again, keeping the buckets' memory.
+    fn rebuild(&mut self, enemies: &[Enemy], fuels: &[Fuel], maxl: u16) {
+        self.lines.resize_with(maxl as usize, Vec::new);
+        self.lines.iter_mut().for_each(Vec::clear);
+
+        let alive = |status: &EntityStatus| matches!(status, EntityStatus::Alive);
+        for (index, enemy) in enemies.iter().enumerate() {
+            match self.lines.get_mut(enemy.location.l as usize) {
+                Some(line) if alive(&enemy.status) => line.push(Target::Enemy(index)),
+                _ => {}
+            }
+        }
+        for (index, fuel) in fuels.iter().enumerate() {
+            match self.lines.get_mut(fuel.location.l as usize) {
+                Some(line) if alive(&fuel.status) => line.push(Target::Fuel(index)),
+                _ => {}
+            }
+        }
+    }
+} // end of LineGrid implementation.
+
+impl World {
+    /// Alive enemies and fuels overlapping `region`, wide ships included, as
+    /// of the last collision check.
+    pub fn query_hits(&self, region: &Region) -> Vec<Target> {
+        let last = (region.bottom as usize).min(self.grid.lines.len().saturating_sub(1));
+        let Some(lines) = self.grid.lines.get(region.top as usize..=last) else {
+            return Vec::new();
+        };
+
+        lines
+            .iter()
+            .flatten()
+            .filter(|target| match **target {
+                Target::Enemy(index) => self.enemies.get(index).is_some_and(|enemy| {
+                    matches!(enemy.status, EntityStatus::Alive)
+                        && region.intersects(&enemy.hitbox())
+                }),
+                Target::Fuel(index) => self.fuels.get(index).is_some_and(|fuel| {
+                    let Location { c, l } = fuel.location;
+                    matches!(fuel.status, EntityStatus::Alive) && region.contains(c, l)
+                }),
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Turn what the player's bullets hit into wrecks and score them.
+    pub(super) fn check_bullet_hits(&mut self) {
+        let mut grid = std::mem::take(&mut self.grid);
+        grid.rebuild(&self.enemies, &self.fuels, self.maxl);
+        self.grid = grid;
+
+        for index in 0..self.bullets.len() {
+            // a bullet and the river close in by two lines a substep, widen the
+            // hitbox vertically
+            let region = Region::around(&self.bullets[index].location, 0, 1);
+            let targets = self.query_hits(&region);
+            if !targets.is_empty() {
+                self.bullets[index].hit = true;
+            }
+            // shells fly on through, missiles are spent
+            if self.bullets[index].is_missile() && !targets.is_empty() {
+                self.bullets[index].energy = 0;
+            }
+            for target in targets {
+                match target {
+                    Target::Enemy(index) => {
+                        let enemy = &mut self.enemies[index];
+                        if matches!(enemy.status, EntityStatus::Alive) {
+                            enemy.status = EntityStatus::DeadBody;
+                            let kind = enemy.kind.name();
+                            let reward = self.combo.kill(enemy.reward());
+                            self.player.earn(reward);
+                            let location = enemy.location.clone();
+                            self.explode(&location);
+                            self.log(CombatEvent::Kill { kind, reward });
+                        }
+                    }
+                    Target::Fuel(index) => {
+                        let fuel = &mut self.fuels[index];
+                        if matches!(fuel.status, EntityStatus::Alive) {
+                            fuel.status = EntityStatus::DeadBody;
+                            self.player.earn(Fuel::REWARD);
+                            self.log(CombatEvent::DepotShot);
+                            if self.player.gas < LOW_GAS {
+                                self.tips.notice(Tip::ShootingFuel);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+} // end of World implementation.
+
+#[cfg(test)]
+mod tests {
+    use super::{Region, Target};
+    use crate::{
+        entities::{Bullet, Enemy, EntityStatus, Fuel, Location},
+        World,
+    };
+
+    #[test]
+    fn bullets_hit_through_the_grid() {
+        let mut world = World::new(80, 24);
+        world.enemies.push(Enemy::ship(30, 10));
+        world.enemies.push(Enemy::new(50, 10, EntityStatus::Alive));
+        world.fuels.push(Fuel::new(40, 11, EntityStatus::Alive));
+        world.bullets.push(Bullet::new(31, 11, 5));
+        world.bullets.push(Bullet::new(40, 12, 5));
+        world.check_bullet_hits();
+
+        // the ship is three cells wide
+        assert!(matches!(world.enemies[0].status, EntityStatus::DeadBody));
+        assert!(matches!(world.enemies[1].status, EntityStatus::Alive));
+        assert!(matches!(world.fuels[0].status, EntityStatus::DeadBody));
+
+        let everything = Region::around(&Location::new(40, 12), 40, 12);
+        assert_eq!(world.query_hits(&everything), [Target::Enemy(1)]);
+    }
+}
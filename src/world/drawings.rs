@@ -1,52 +1,111 @@
-use std::{
-    io::{Stdout, Write},
-    thread,
-    time::Duration,
-};
+use std::{fs::OpenOptions, io::Write, thread, time::Duration};
 
-use crossterm::{
-    event::{poll, read},
-    style::{ContentStyle, Stylize},
-};
+use crossterm::event::{poll, read, Event, KeyCode, KeyEventKind};
 
 use crate::{
-    entities::{DeathCause, PlayerStatus},
+    arith,
+    card::{today, SummaryCard, CARD_FILE},
+    doctor::Finding,
+    drawable::BombFlash,
+    entities::DeathCause,
+    feedback::FeedbackKind,
+    highscores::{HighScore, HighScores, HIGHSCORES_FILE, MAX_NAME_LEN},
+    keymap::{KeyMap, KEYS_FILE},
+    menu::MenuEvent,
+    onboarding::Onboarding,
+    settings::SETTINGS_FILE,
     stout_ext::StdoutExt,
-    World,
+    GameOutcome, World, WorldStatus,
 };
 
 impl World {
-    pub fn clear_screen<'a>(
+    pub fn clear_screen<'a, W: Write>(
         &'a self,
-        stdout: &'a mut Stdout,
-    ) -> Result<&mut Stdout, std::io::Error> {
+        stdout: &'a mut W,
+    ) -> Result<&'a mut W, std::io::Error> {
         stdout.clear_all()
     }
 
+    /// Scatter the biome's decorations on the banks.
+    fn draw_decorations(&mut self) {
+        let style = self.canvas.theme().decoration;
+        // the canvas is drawn on while the biome is read
+        let biome = &self.biomes[self.biome_index()];
+        for (l, (left, right)) in self.map.iter().enumerate() {
+            // lines count from the start of the river, so they scroll along
+            let line = self.traveled.wrapping_sub(l as u32);
+            let island = self.islands[l].map_or(0..0, |(start, end)| start..end);
+            for c in (0..*left).chain(island).chain(*right..self.maxc) {
+                if let Some(glyph) = biome.decoration(c, line) {
+                    self.canvas.draw_styled_char((c, l as u16), glyph, style);
+                }
+            }
+        }
+    }
+
     pub(super) fn draw_on_canvas(&mut self) {
         self.canvas.clear_all();
+        // only photo mode moves the camera, the game is always centered
+        let photo = matches!(self.status, WorldStatus::Photo);
+        self.canvas
+            .set_camera(if photo { self.camera } else { (0, 0) });
 
         // draw the map
         for l in 0..self.map.len() {
             let map_c = self.map[l].1;
             let maxc = self.maxc;
+            let (bank, river) = (self.canvas.theme().bank, self.canvas.theme().river);
             self.canvas
-                .draw_styled_line((0, l as u16), " ".repeat(self.map[l].0 as usize), ContentStyle::new().on_green())
-                .draw_styled_line((self.map[l].0, l as u16), " ".repeat((self.map[l].1-self.map[l].0) as usize), ContentStyle::new().on_blue())
-                .draw_styled_line((map_c, l as u16), " ".repeat((maxc - map_c) as usize), ContentStyle::new().on_green());
+                .draw_styled_line((0, l as u16), " ".repeat(self.map[l].0 as usize), bank)
+                .draw_styled_line(
+                    (self.map[l].0, l as u16),
+                    " ".repeat(arith::sub(self.map[l].1, self.map[l].0) as usize),
+                    river,
+                )
+                .draw_styled_line(
+                    (map_c, l as u16),
+                    " ".repeat(arith::sub(maxc, map_c) as usize),
+                    bank,
+                );
+            if let Some((start, end)) = self.islands[l] {
+                self.canvas.draw_styled_line(
+                    (start, l as u16),
+                    " ".repeat((end - start) as usize),
+                    bank,
+                );
+            }
         }
+        self.draw_decorations();
+        self.draw_scenery();
 
-        let status_style = ContentStyle::new().black().on_white();
-        let gas_present = self.player.gas / 100;
-        let enemies_count = self.enemies.len();
-        self.canvas
-            .draw_styled_line(2, format!(" Score: {} ", self.player.score), status_style)
-            .draw_styled_line((2, 3), format!(" Fuel: {} ", gas_present), status_style)
-            .draw_styled_line(
-                (2, 4),
-                format!(" Enemies: {} ", enemies_count),
-                status_style,
-            );
+        if !matches!(self.status, WorldStatus::Photo) {
+            self.draw_status();
+        }
+
+        // draw bridges
+        for bridge in self.bridges.iter() {
+            self.canvas.draw(bridge);
+        }
+
+        // draw recharge pads
+        for pad in self.pads.iter() {
+            self.canvas.draw(pad);
+        }
+
+        // draw bomb crates
+        for bomb in &self.bomb_crates {
+            self.canvas.draw(bomb);
+        }
+
+        // draw rocks and buoys
+        for obstacle in &self.obstacles {
+            self.canvas.draw(obstacle);
+        }
+
+        // draw tunnels
+        for tunnel in self.tunnels.iter() {
+            self.canvas.draw(tunnel);
+        }
 
         // draw fuel
         for fuel in self.fuels.iter() {
@@ -58,27 +117,73 @@ impl World {
             self.canvas.draw(enemy);
         }
 
+        // draw flashes, trails and explosions under the bullets
+        for spark in &self.sparks {
+            self.canvas.draw(spark);
+        }
+
         // draw bullet
         for bullet in &self.bullets {
             self.canvas.draw(bullet);
         }
 
+        // draw enemy bullets
+        for bullet in &self.enemy_bullets {
+            self.canvas.draw(bullet);
+        }
+
+        // a bomb going off outshines everything but the player
+        if self.bomb_flash > 0 {
+            self.canvas.draw(&BombFlash);
+        }
+
         // draw the player
         self.canvas.draw(&self.player);
+
+        // draw the tip of the run, or the popup of a new stage
+        let popup = self
+            .tips
+            .showing
+            .map(|(tip, _)| tip.text())
+            .or(self.stages.popup());
+        if let (Some(popup), false) = (popup, photo) {
+            let text = format!(" {popup} ");
+            let c = (self.maxc / 2).saturating_sub(text.chars().count() as u16 / 2);
+            let style = self.canvas.theme().popup;
+            self.canvas.draw_styled_line((c, 2), text, style);
+        }
     }
 
-    pub(super) fn pause_screen(&mut self) {
-        let pause_msg1: &str = "╔═══════════╗";
-        let pause_msg2: &str = "║Game Paused║";
-        let pause_msg3: &str = "╚═══════════╝";
+    pub(super) fn warp_screen(&mut self) {
+        let WorldStatus::Warping { lines_left } = self.status else {
+            return;
+        };
 
-        self.canvas
-            .draw_line((self.maxc / 2 - 6, self.maxl / 2 - 1), pause_msg1)
-            .draw_line((self.maxc / 2 - 6, self.maxl / 2), pause_msg2)
-            .draw_line((self.maxc / 2 - 6, self.maxl / 2 + 1), pause_msg3);
+        // streaks rushing by inside the river
+        let style = self.canvas.theme().warp;
+        for l in (lines_left as usize % 3..self.map.len()).step_by(3) {
+            let (left, right) = self.map[l];
+            self.canvas.draw_styled_line(
+                (left, l as u16),
+                "│".repeat((right - left) as usize),
+                style,
+            );
+        }
+
+        let banner = " ≋≋ WARP ≋≋ ";
+        self.canvas.draw_styled_line(
+            ((self.maxc / 2).saturating_sub(6), self.maxl / 2),
+            banner,
+            self.canvas.theme().tunnel,
+        );
     }
 
-    pub fn welcome_screen(&self, stdout: &mut Stdout) -> Result<(), std::io::Error> {
+    /// Title screen, listing the `problems` found by the startup self-check.
+    pub fn welcome_screen(
+        &mut self,
+        stdout: &mut impl Write,
+        problems: &[Finding],
+    ) -> Result<(), std::io::Error> {
         let welcome_msg: &str = "██████╗ ██╗██╗   ██╗███████╗██████╗ ██████╗  █████╗ ██╗██████╗     ██████╗ ██╗   ██╗███████╗████████╗\n\r██╔══██╗██║██║   ██║██╔════╝██╔══██╗██╔══██╗██╔══██╗██║██╔══██╗    ██╔══██╗██║   ██║██╔════╝╚══██╔══╝\n\r██████╔╝██║██║   ██║█████╗  ██████╔╝██████╔╝███████║██║██║  ██║    ██████╔╝██║   ██║███████╗   ██║   \n\r██╔══██╗██║╚██╗ ██╔╝██╔══╝  ██╔══██╗██╔══██╗██╔══██║██║██║  ██║    ██╔══██╗██║   ██║╚════██║   ██║   \n\r██║  ██║██║ ╚████╔╝ ███████╗██║  ██║██║  ██║██║  ██║██║██████╔╝    ██║  ██║╚██████╔╝███████║   ██║   \n\r╚═╝  ╚═╝╚═╝  ╚═══╝  ╚══════╝╚═╝  ╚═╝╚═╝  ╚═╝╚═╝  ╚═╝╚═╝╚═════╝     ╚═╝  ╚═╝ ╚═════╝ ╚══════╝   ╚═╝   \n";
         self.clear_screen(stdout)?;
 
@@ -88,12 +193,40 @@ impl World {
             stdout.draw((0, 2), "RiverRaid Rust")?;
         }
 
-        stdout.draw((2, self.maxl - 2), "Press any key to continue...")?;
+        // the title takes the lines down to 8, a greeting the 10th
+        let mut first_free = 9;
+        if let Some(greeting) = self.pack.as_ref().and_then(|pack| pack.greeting.as_ref()) {
+            stdout.draw((2, 10), greeting)?;
+            first_free = 11;
+        }
+
+        // problems above the prompt, as many as fit under the title
+        if !problems.is_empty() {
+            let prompt = arith::sub(self.maxl, 2);
+            let top = prompt
+                .saturating_sub(problems.len() as u16 + 2)
+                .max(first_free);
+            let room = prompt.saturating_sub(top + 2) as usize;
+            if room > 0 {
+                stdout.draw((2, top), "Run with --doctor for details:")?;
+                for (index, problem) in problems.iter().take(room).enumerate() {
+                    let line = format!("⚠ {}: {}", problem.check, problem.detail);
+                    stdout.draw((2, top + 1 + index as u16), line)?;
+                }
+            }
+        }
+
+        stdout.draw(
+            (2, arith::sub(self.maxl, 2)),
+            "Press any key to continue...",
+        )?;
         stdout.flush()?;
 
         loop {
             if poll(Duration::from_millis(0)).unwrap() {
                 read()?;
+                self.feedback.emit(FeedbackKind::MenuSelect);
+                self.feedback.flush(stdout)?;
                 break;
             }
         }
@@ -102,7 +235,57 @@ impl World {
         Ok(())
     }
 
-    pub fn goodbye_screen(&self, stdout: &mut Stdout) -> Result<(), std::io::Error> {
+    /// Ask a first-time player for their controls, theme and difficulty, and
+    /// save the answers; see [`crate::onboarding`].
+    pub fn onboarding_screen(&mut self, stdout: &mut impl Write) -> Result<(), std::io::Error> {
+        let mut onboarding = Onboarding::new();
+        self.canvas.invalidate();
+        while !onboarding.is_done() {
+            let (step, steps) = onboarding.progress();
+            let hint = format!("{step}/{steps}  Enter: choose, Esc: keep the defaults");
+            self.canvas.clear_all();
+            self.canvas
+                .draw_styled_line((2, 1), "Welcome, pilot! A few questions first.", None)
+                .draw(onboarding.menu())
+                .draw_styled_line((2, arith::sub(self.maxl, 2)), hint, None);
+            self.canvas.draw_map(stdout)?;
+
+            let Event::Key(event) = read()? else {
+                continue;
+            };
+            if event.kind != KeyEventKind::Press {
+                continue;
+            }
+            match onboarding.handle_key(event.code, &self.keymap) {
+                Some(MenuEvent::Moved) => self.feedback.emit(FeedbackKind::MenuMove),
+                Some(MenuEvent::Activated(_)) => self.feedback.emit(FeedbackKind::MenuSelect),
+                None => {}
+            }
+            self.feedback.flush(stdout)?;
+        }
+
+        let keys = onboarding.apply(&mut self.settings);
+        self.apply_settings();
+        // failing to save only means asking again next time
+        let _ = self.settings.save(SETTINGS_FILE);
+        if !keys.is_empty() {
+            let file = OpenOptions::new().create(true).append(true).open(KEYS_FILE);
+            if file
+                .and_then(|mut file| file.write_all(keys.as_bytes()))
+                .is_ok()
+            {
+                self.keymap = KeyMap::load(KEYS_FILE).unwrap_or_default();
+            }
+        }
+        self.clear_screen(stdout)?;
+        Ok(())
+    }
+
+    pub fn goodbye_screen(
+        &mut self,
+        stdout: &mut impl Write,
+        outcome: &GameOutcome,
+    ) -> Result<(), std::io::Error> {
         let goodbye_msg1: &str = " ██████╗  ██████╗  ██████╗ ██████╗      ██████╗  █████╗ ███╗   ███╗███████╗██╗\n\r██╔════╝ ██╔═══██╗██╔═══██╗██╔══██╗    ██╔════╝ ██╔══██╗████╗ ████║██╔════╝██║\n\r██║  ███╗██║   ██║██║   ██║██║  ██║    ██║  ███╗███████║██╔████╔██║█████╗  ██║\n\r██║   ██║██║   ██║██║   ██║██║  ██║    ██║   ██║██╔══██║██║╚██╔╝██║██╔══╝  ╚═╝\n\r╚██████╔╝╚██████╔╝╚██████╔╝██████╔╝    ╚██████╔╝██║  ██║██║ ╚═╝ ██║███████╗██╗\n\r ╚═════╝  ╚═════╝  ╚═════╝ ╚═════╝      ╚═════╝ ╚═╝  ╚═╝╚═╝     ╚═╝╚══════╝╚═╝\n";
         let goodbye_msg2: &str = "████████╗██╗  ██╗ █████╗ ███╗   ██╗██╗  ██╗███████╗\n\r╚══██╔══╝██║  ██║██╔══██╗████╗  ██║██║ ██╔╝██╔════╝\n\r   ██║   ███████║███████║██╔██╗ ██║█████╔╝ ███████╗\n\r   ██║   ██╔══██║██╔══██║██║╚██╗██║██╔═██╗ ╚════██║\n\r   ██║   ██║  ██║██║  ██║██║ ╚████║██║  ██╗███████║██╗\n\r   ╚═╝   ╚═╝  ╚═╝╚═╝  ╚═╝╚═╝  ╚═══╝╚═╝  ╚═╝╚══════╝╚═╝\n";
 
@@ -110,8 +293,21 @@ impl World {
             .draw((0, 2), goodbye_msg1)?
             .draw((0, 10), goodbye_msg2)?;
 
-        stdout.move_cursor((2, self.maxl - 5))?;
-        if let PlayerStatus::Dead(cause) = &self.player.status {
+        stdout.move_cursor((2, arith::sub(self.maxl, 5)))?;
+        if let GameOutcome::Race { won } = outcome {
+            stdout.print(if *won {
+                "You won the race!"
+            } else {
+                "Your rival won the race."
+            })?;
+        }
+        if let GameOutcome::Completed = outcome {
+            stdout.print("You flew every stage to the end!")?;
+        }
+        if let GameOutcome::PlayerQuit { abandoned: true } = outcome {
+            stdout.print("You left the run unfinished.")?;
+        }
+        if let GameOutcome::Died(cause) = outcome {
             match cause {
                 DeathCause::Ground => {
                     if self.maxc > 91 {
@@ -127,6 +323,12 @@ impl World {
                         stdout.print("An enemy killed you.")?;
                     }
                 }
+                DeathCause::Bridge => {
+                    stdout.print("You crashed into a bridge.")?;
+                }
+                DeathCause::Obstacle => {
+                    stdout.print("You crashed into a rock.")?;
+                }
                 DeathCause::Fuel => {
                     if self.maxc > 69 {
                         stdout.print("\r█▄█ █▀█ █░█   █▀█ ▄▀█ █▄░█   █▀█ █░█ ▀█▀   █▀█ █▀▀   █▀▀ █░█ █▀▀ █░░ ░\n\r░█░ █▄█ █▄█   █▀▄ █▀█ █░▀█   █▄█ █▄█ ░█░   █▄█ █▀░   █▀░ █▄█ ██▄ █▄▄ ▄\n\r")?;
@@ -135,20 +337,198 @@ impl World {
                     }
                 }
             }
-        } else {
-            // Quit
-            if self.player.status != PlayerStatus::Quit {
-                unreachable!("Undead player has no death cause!")
-            }
         }
 
-        stdout.move_cursor((2, self.maxl - 2))?;
+        stdout.draw(
+            (2, arith::sub(self.maxl, 3)),
+            format!(
+                "Played {} ({} including pauses), seed {}",
+                self.locale.duration(self.play_time),
+                self.locale.duration(self.wall_time()),
+                self.seed
+            ),
+        )?;
+
+        stdout.move_cursor((2, arith::sub(self.maxl, 2)))?;
         thread::sleep(Duration::from_millis(2000));
         stdout.print("Press any key to continue...")?;
         stdout.flush()?;
         loop {
             if poll(Duration::from_millis(0)).unwrap() {
                 read()?;
+                self.feedback.emit(FeedbackKind::MenuSelect);
+                self.feedback.flush(stdout)?;
+                break;
+            }
+        }
+
+        self.clear_screen(stdout)?;
+        Ok(())
+    }
+    /// Show the summary card of the game, offering to save it (and, with the
+    /// `clipboard` feature, to copy it) for sharing.
+    pub fn card_screen(&mut self, stdout: &mut impl Write) -> Result<(), std::io::Error> {
+        let card = self.summary_card();
+        let mut hint = if cfg!(feature = "clipboard") {
+            "c: copy, s: save, any other key: continue"
+        } else {
+            "s: save, any other key: continue"
+        }
+        .to_string();
+
+        // the goodbye screen drew behind the canvas' back
+        self.canvas.invalidate();
+        loop {
+            self.canvas.clear_all();
+            self.canvas.draw(&card);
+            self.canvas
+                .draw_styled_line((2, arith::sub(self.maxl, 2)), hint.as_str(), None);
+            self.canvas.draw_map(stdout)?;
+
+            let Event::Key(event) = read()? else {
+                continue;
+            };
+            if event.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match event.code {
+                #[cfg(feature = "clipboard")]
+                KeyCode::Char('c') => {
+                    card.copy(stdout)?;
+                    hint = "Copied to the clipboard".to_string();
+                }
+                KeyCode::Char('s') => {
+                    hint = match card.save(CARD_FILE) {
+                        Ok(()) => format!("Saved to {CARD_FILE}"),
+                        Err(error) => format!("Could not save the card: {error}"),
+                    };
+                }
+                _ => break,
+            }
+        }
+
+        self.feedback.emit(FeedbackKind::MenuSelect);
+        self.feedback.flush(stdout)?;
+        self.clear_screen(stdout)?;
+        Ok(())
+    }
+
+    fn summary_card(&self) -> SummaryCard {
+        SummaryCard {
+            score: self.player.score,
+            traveled: self.traveled,
+            seed: self.seed,
+            date: today(),
+            assisted: self.assisted,
+            locale: self.locale,
+        }
+    }
+
+    /// Ask for a name to put on the high-score board.
+    fn ask_name(&mut self, stdout: &mut impl Write) -> Result<String, std::io::Error> {
+        let mut name = String::new();
+        let score = self.locale.number(self.player.score);
+        loop {
+            self.clear_screen(stdout)?
+                .draw((2, 2), format!("New high score: {score}!"))?
+                .draw((2, 4), format!("Your name: {name}_"))?
+                .draw((2, 6), "Press Enter when done")?;
+            stdout.flush()?;
+
+            let Event::Key(event) = read()? else {
+                continue;
+            };
+            if event.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match event.code {
+                KeyCode::Enter | KeyCode::Esc => break,
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Char(ch) if name.chars().count() < MAX_NAME_LEN => name.push(ch),
+                _ => {}
+            }
+        }
+
+        let name = name.trim();
+        Ok(if name.is_empty() { "anonymous" } else { name }.to_string())
+    }
+
+    /// Show the best scores, asking for a name first if this game made it.
+    pub fn highscore_screen(&mut self, stdout: &mut impl Write) -> Result<(), std::io::Error> {
+        let mut board = HighScores::load(HIGHSCORES_FILE).unwrap_or_default();
+        let mut rank = None;
+        if board.places(self.player.score) {
+            let name = self.ask_name(stdout)?;
+            rank = Some(board.insert(HighScore {
+                name,
+                score: self.player.score,
+                traveled: self.traveled,
+                seconds: self.play_time.as_secs(),
+                assisted: self.assisted,
+            }));
+            // Failing to save only loses the board, not worth stopping here.
+            let _ = board.save(HIGHSCORES_FILE);
+        }
+
+        // columns as wide as their longest number in this locale
+        let cells: Vec<[String; 3]> = board
+            .entries
+            .iter()
+            .map(|entry| {
+                [
+                    self.locale.number(entry.score),
+                    self.locale.number(entry.traveled),
+                    self.locale.duration(Duration::from_secs(entry.seconds)),
+                ]
+            })
+            .collect();
+        let width = |column: usize, title: &str| {
+            cells
+                .iter()
+                .map(|row| row[column].chars().count())
+                .fold(title.len(), usize::max)
+        };
+        let (score, distance, time) = (width(0, "Score"), width(1, "Distance"), width(2, "Time"));
+
+        self.clear_screen(stdout)?
+            .draw((2, 1), "HIGH SCORES")?
+            .draw(
+                (2, 3),
+                format!(
+                    "    {:<MAX_NAME_LEN$} {:>score$} {:>distance$} {:>time$}",
+                    "Name", "Score", "Distance", "Time"
+                ),
+            )?;
+        for (index, (entry, [points, traveled, played])) in
+            board.entries.iter().zip(cells).enumerate()
+        {
+            let marker = if rank == Some(index) { '▶' } else { ' ' };
+            stdout.draw(
+                (2, 4 + index as u16),
+                format!(
+                    "{marker}{:>2} {:<MAX_NAME_LEN$} {points:>score$} {traveled:>distance$} \
+                     {played:>time$}{}",
+                    index + 1,
+                    entry.name,
+                    if entry.assisted { " assisted" } else { "" }
+                ),
+            )?;
+        }
+
+        stdout.draw(
+            (2, arith::sub(self.maxl, 2)),
+            "Press any key to continue...",
+        )?;
+        stdout.flush()?;
+        loop {
+            if poll(Duration::from_millis(0)).unwrap() {
+                read()?;
+                self.feedback.emit(FeedbackKind::MenuSelect);
+                self.feedback.flush(stdout)?;
                 break;
             }
         }
@@ -0,0 +1,185 @@
+//! A canonical hash of the gameplay state, for the determinism harness,
+//! desync checks between players and verifying replays.
+//!
+//! The state is fed to an FNV-1a hasher in a fixed order and encoding, so the
+//! hash is the same on every platform, build and Rust version. Anything only
+//! for show (sparks, the HUD) or wall-clock time is left out.
+
+use std::io::{self, Write};
+
+use crate::{
+    entities::{EntityRecord, PlayerStatus},
+    World,
+};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+struct StateHasher(u64);
+
+impl StateHasher {
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// Entities go in as their tagged JSON, see [`EntityRecord`], behind
+    /// their count so they can not run into what follows.
+    fn entities(&mut self, entities: &[EntityRecord]) {
+        self.u32(entities.len() as u32);
+        serde_json::to_writer(&mut *self, entities).expect("entities serialize");
+    }
+} // end of StateHasher implementation.
+
+impl Write for StateHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hash of `bytes` with the same hasher, for files both players of a
+/// versus race need the same of, see [`crate::versus::Level`].
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hasher = StateHasher(FNV_OFFSET);
+    hasher.bytes(bytes);
+    hasher.0
+}
+
+impl World {
+    /// Fold a new line of river into [`World::river_hash`]. Versus rivals
+    /// compare it to make sure they fly the same river.
+    pub(super) fn hash_line(&mut self, banks: (u16, u16), island: Option<(u16, u16)>) {
+        let mut hasher = StateHasher(self.river_hash ^ FNV_OFFSET);
+        let (start, end) = island.unwrap_or_default();
+        for value in [banks.0, banks.1, start, end] {
+            hasher.u16(value);
+        }
+        self.river_hash = hasher.0;
+    }
+
+    /// Hash of everything that decides how the game goes on: the player, the
+    /// entities, the river and the counters. Two worlds with the same hash
+    /// play the same from here, given the same inputs. The seed is left out:
+    /// it only shows through the river it made.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher(FNV_OFFSET);
+
+        let player = &self.player;
+        let (status, detail) = match player.status {
+            PlayerStatus::Alive => (0, 0),
+            PlayerStatus::Gliding { ticks_left } => (1, ticks_left),
+            PlayerStatus::Dead(cause) => (2, cause as u16),
+            PlayerStatus::Quit { abandoned } => (3, u16::from(abandoned)),
+            PlayerStatus::RaceOver { won } => (4, u16::from(won)),
+            PlayerStatus::Completed => (5, 0),
+        };
+        hasher.bytes(&[
+            status,
+            player.lives,
+            player.shield,
+            player.bombs,
+            self.bomb_flash,
+        ]);
+        hasher.bytes(&[player.missiles]);
+        for value in [
+            detail,
+            player.location.c,
+            player.location.l,
+            player.invulnerable,
+        ] {
+            hasher.u16(value);
+        }
+        hasher.u32(player.gas);
+        hasher.u32(player.score);
+
+        hasher.entities(&self.entity_records());
+
+        hasher.u32(self.map.len() as u32);
+        for (left, right) in &self.map {
+            hasher.u16(*left);
+            hasher.u16(*right);
+        }
+        for (start, end) in self.islands.iter().flatten() {
+            hasher.u16(*start);
+            hasher.u16(*end);
+        }
+        for value in [
+            self.next_left,
+            self.next_right,
+            self.section,
+            self.settings.warp_distance,
+        ] {
+            hasher.u16(value);
+        }
+        hasher.u32(self.traveled);
+        hasher.u32(self.last_id);
+        hasher.u32(self.scroll_speed.to_bits());
+        hasher.u32(self.scroll_progress.to_bits());
+
+        hasher.0
+    }
+
+    /// Every entity but the player, in the order they are hashed.
+    fn entity_records(&self) -> Vec<EntityRecord> {
+        let enemies = self.enemies.iter().cloned().map(EntityRecord::Enemy);
+        let fuels = self.fuels.iter().cloned().map(EntityRecord::Fuel);
+        let bullets = self.bullets.iter().cloned().map(EntityRecord::Bullet);
+        let enemy_bullets = self
+            .enemy_bullets
+            .iter()
+            .cloned()
+            .map(EntityRecord::EnemyBullet);
+        let bridges = self.bridges.iter().cloned().map(EntityRecord::Bridge);
+        let tunnels = self.tunnels.iter().cloned().map(EntityRecord::Tunnel);
+        let pads = self.pads.iter().cloned().map(EntityRecord::Pad);
+        let bombs = self.bomb_crates.iter().cloned().map(EntityRecord::Bomb);
+        let obstacles = self.obstacles.iter().cloned().map(EntityRecord::Obstacle);
+        enemies
+            .chain(fuels)
+            .chain(bullets)
+            .chain(enemy_bullets)
+            .chain(bridges)
+            .chain(tunnels)
+            .chain(pads)
+            .chain(bombs)
+            .chain(obstacles)
+            .collect()
+    }
+} // end of World implementation.
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entities::{Location, Spark},
+        World,
+    };
+
+    #[test]
+    fn hash_follows_gameplay_only() {
+        let mut world = World::new(80, 24);
+        world.set_seed(7);
+        let hash = world.state_hash();
+
+        world.sparks = Spark::burst(&Location::new(40, 10));
+        world.set_seed(8);
+        assert_eq!(world.state_hash(), hash);
+
+        world.player.score += 1;
+        assert_ne!(world.state_hash(), hash);
+    }
+}
@@ -0,0 +1,443 @@
+//! The status HUD.
+//!
+//! The HUD is a stack of [`HudWidget`]s on each side of the screen. The game
+//! registers its own [`StatusWidget`], [`ComboWidget`], [`ChallengeWidget`],
+//! [`EffectsWidget`] and [`MinimapWidget`]; anything else can add more with
+//! [`World::add_hud_widget`] without touching `draw_status`.
+
+use crate::{
+    challenge::Goal,
+    combo::DECAY_TICKS,
+    entities::{Player, PlayerStatus},
+    track::TrackLine,
+    World,
+};
+
+pub enum HudPosition {
+    Left,
+    Right,
+}
+
+pub trait HudWidget {
+    /// Side of the screen the widget is stacked on
+    fn position(&self) -> HudPosition {
+        HudPosition::Left
+    }
+
+    /// Refresh from the world, called before every drawn frame.
+    fn update(&mut self, world: &World);
+
+    /// Lines to show, top to bottom.
+    fn draw(&self) -> Vec<String>;
+}
+
+/// Seasonal event, holiday pack, score, fuel, enemies, lives, time, section
+/// with its biome, distance and stage.
+#[derive(Default)]
+pub struct StatusWidget {
+    lines: Vec<String>,
+}
+
+impl HudWidget for StatusWidget {
+    fn update(&mut self, world: &World) {
+        self.lines = vec![];
+        if let Some(event) = world.event {
+            self.lines.push(format!(" {} ", event.banner()));
+        }
+        if let Some(pack) = &world.pack {
+            self.lines
+                .push(format!(" {} ", pack.banner.as_ref().unwrap_or(&pack.name)));
+        }
+        self.lines.extend([
+            format!(" Score: {} ", world.locale.number(world.player.score)),
+            match world.player.status {
+                PlayerStatus::Gliding { ticks_left } => {
+                    format!(" Fuel: EMPTY, gliding {ticks_left} ")
+                }
+                _ => format!(" Fuel: {} ", world.player.gas / 100),
+            },
+            format!(" Enemies: {} ", world.enemies.len()),
+            format!(" Lives: {} ", world.player.lives),
+            format!(" Time: {} ", world.locale.duration(world.play_time)),
+            format!(" Section: {} {} ", world.section, world.biome().name),
+            format!(" Distance: {} ", world.locale.number(world.traveled)),
+        ]);
+        if let Some(stage) = world.stages.stage() {
+            self.lines.push(format!(" Stage: {} ", stage.name));
+        }
+    }
+
+    fn draw(&self) -> Vec<String> {
+        self.lines.clone()
+    }
+}
+
+/// The multiplier of the kill streak and the time left to keep it up, while
+/// there is one.
+#[derive(Default)]
+pub struct ComboWidget {
+    line: Option<String>,
+}
+
+impl HudWidget for ComboWidget {
+    fn update(&mut self, world: &World) {
+        let combo = &world.combo;
+        self.line = (combo.multiplier() > 1).then(|| {
+            let left = pips(combo.ticks_left(), DECAY_TICKS);
+            format!(" Combo x{} {left} ", combo.multiplier())
+        });
+    }
+
+    fn draw(&self) -> Vec<String> {
+        self.line.iter().cloned().collect()
+    }
+}
+
+/// The challenge on, how far along it is and the seconds it has left.
+#[derive(Default)]
+pub struct ChallengeWidget {
+    line: Option<String>,
+}
+
+impl HudWidget for ChallengeWidget {
+    fn update(&mut self, world: &World) {
+        self.line = world.challenges.current().map(|progress| {
+            let left = progress.deadline.saturating_sub(world.play_time).as_secs();
+            match progress.challenge.goal {
+                Goal::Kills(goal) => {
+                    format!(" Challenge: {}/{goal} kills, {left}s ", progress.count)
+                }
+                Goal::Pickups(goal) => {
+                    format!(" Challenge: {}/{goal} pickups, {left}s ", progress.count)
+                }
+                Goal::HoldFire => format!(" Challenge: hold fire, {left}s "),
+            }
+        });
+    }
+
+    fn draw(&self) -> Vec<String> {
+        self.line.iter().cloned().collect()
+    }
+}
+
+/// Pips left of `left` out of `total`, rounded up so a running effect
+/// never shows empty.
+fn pips(left: u16, total: u16) -> String {
+    const PIPS: u32 = 5;
+    let full = (u32::from(left) * PIPS)
+        .div_ceil(u32::from(total.max(1)))
+        .min(PIPS);
+    format!(
+        "{}{}",
+        "●".repeat(full as usize),
+        "○".repeat((PIPS - full) as usize)
+    )
+}
+
+/// A strip of the effects on the player (shield, bombs, missiles,
+/// invulnerability, glide, recharging) with what is left of each.
+#[derive(Default)]
+pub struct EffectsWidget {
+    line: Option<String>,
+}
+
+impl HudWidget for EffectsWidget {
+    fn position(&self) -> HudPosition {
+        HudPosition::Right
+    }
+
+    fn update(&mut self, world: &World) {
+        let player = &world.player;
+        let mut effects = Vec::new();
+
+        if player.shield > 0 {
+            effects.push(format!(
+                "Shield {}{}",
+                "■".repeat(player.shield as usize),
+                "□".repeat((Player::MAX_SHIELD - player.shield) as usize)
+            ));
+        }
+        if player.bombs > 0 {
+            effects.push(format!("Bombs {}", "✸".repeat(player.bombs as usize)));
+        }
+        if player.missiles > 0 {
+            effects.push(format!("Missiles {}", "⇡".repeat(player.missiles as usize)));
+        }
+        if player.invulnerable > 0 {
+            effects.push(format!(
+                "Safe {}",
+                pips(player.invulnerable, Player::RESPAWN_INVULNERABLE)
+            ));
+        }
+        if let PlayerStatus::Gliding { ticks_left } = player.status {
+            effects.push(format!("Glide {}", pips(ticks_left, Player::GLIDE_TICKS)));
+        }
+        if world.recharging {
+            effects.push("Charging ⚡".to_string());
+        }
+        if world.input_macro.is_recording() {
+            effects.push("● Rec".to_string());
+        }
+        if world.input_macro.is_playing() {
+            effects.push("▶ Macro".to_string());
+        }
+
+        self.line = (!effects.is_empty()).then(|| format!(" {} ", effects.join(" │ ")));
+    }
+
+    fn draw(&self) -> Vec<String> {
+        self.line.iter().cloned().collect()
+    }
+}
+
+/// The rival of a versus race, to the right of the screen.
+#[derive(Default)]
+pub struct VersusWidget {
+    lines: Vec<String>,
+}
+
+impl HudWidget for VersusWidget {
+    fn position(&self) -> HudPosition {
+        HudPosition::Right
+    }
+
+    fn update(&mut self, world: &World) {
+        self.lines.clear();
+        let Some(versus) = &world.versus else {
+            return;
+        };
+        let locale = &world.locale;
+        self.lines
+            .push(format!(" Race to {} ", locale.number(versus.target)));
+        self.lines
+            .push(format!(" You: {} ", locale.number(world.traveled)));
+        match versus.rival {
+            Some(rival) => {
+                self.lines
+                    .push(format!(" Rival: {} ", locale.number(rival.traveled)));
+                self.lines
+                    .push(format!(" Rival score: {} ", locale.number(rival.score)));
+                if !versus.connected {
+                    self.lines.push(" Rival left ".to_string());
+                } else if !rival.flying {
+                    self.lines.push(" Rival crashed ".to_string());
+                }
+            }
+            None => self.lines.push(" Waiting for rival ".to_string()),
+        }
+        if versus.desynced {
+            self.lines.push(" Rivers differ! ".to_string());
+        }
+    }
+
+    fn draw(&self) -> Vec<String> {
+        self.lines.clone()
+    }
+}
+
+/// Rows and columns the [`MinimapWidget`] shrinks the river into
+const MINIMAP_ROWS: usize = 6;
+const MINIMAP_WIDTH: u16 = 12;
+
+/// Lines of a custom track shown beyond the screen
+const MINIMAP_LOOKAHEAD: usize = 40;
+
+/// The river ahead of the jet shrunk into a few rows at the right edge, the
+/// jet at the bottom, and the lines left to the next bridge. A custom track
+/// shows some of what is beyond the screen too.
+#[derive(Default)]
+pub struct MinimapWidget {
+    lines: Vec<String>,
+}
+
+impl MinimapWidget {
+    /// Columns of the screen per column of the map
+    fn scale(maxc: u16) -> u16 {
+        maxc.max(MINIMAP_WIDTH) / MINIMAP_WIDTH
+    }
+
+    /// A row of the map: `▓` for land, `═` for a bridge over the water.
+    fn row(line: &TrackLine, maxc: u16) -> String {
+        let (left, right) = line.banks;
+        let scale = MinimapWidget::scale(maxc);
+        (0..MINIMAP_WIDTH)
+            .map(|x| {
+                let c = x * scale + scale / 2;
+                let on_island = line
+                    .island
+                    .is_some_and(|(start, end)| start <= c && c < end);
+                match (left <= c && c < right && !on_island, line.bridge) {
+                    (true, true) => '═',
+                    (true, false) => ' ',
+                    (false, _) => '▓',
+                }
+            })
+            .collect()
+    }
+} // end of MinimapWidget implementation.
+
+impl HudWidget for MinimapWidget {
+    fn position(&self) -> HudPosition {
+        HudPosition::Right
+    }
+
+    fn update(&mut self, world: &World) {
+        let jet = world.player.location.l;
+        let bridge_on = |l: u16| world.bridges.iter().any(|bridge| bridge.location.l == l);
+        // nearest first, from the jet's line up
+        let mut ahead: Vec<TrackLine> = (0..=jet)
+            .rev()
+            .filter_map(|l| {
+                Some(TrackLine {
+                    banks: *world.map.get(l as usize)?,
+                    island: world.islands.get(l as usize).copied().flatten(),
+                    bridge: bridge_on(l),
+                    spawns: Vec::new(),
+                })
+            })
+            .collect();
+        if let Some((track, next)) = &world.track {
+            ahead.extend((0..MINIMAP_LOOKAHEAD).map(|k| track.line(next + k).clone()));
+        }
+
+        self.lines.clear();
+        let per_row = ahead.len().div_ceil(MINIMAP_ROWS).max(1);
+        for (index, lines) in ahead.chunks(per_row).enumerate().rev() {
+            let line = TrackLine {
+                bridge: lines.iter().any(|line| line.bridge),
+                ..lines[0].clone()
+            };
+            let mut row = MinimapWidget::row(&line, world.maxc);
+            if index == 0 {
+                let x = world.player.location.c / MinimapWidget::scale(world.maxc);
+                let x = x.min(MINIMAP_WIDTH - 1) as usize;
+                row = row
+                    .chars()
+                    .enumerate()
+                    .map(|(at, glyph)| if at == x { '▲' } else { glyph })
+                    .collect();
+            }
+            self.lines.push(format!(" {row} "));
+        }
+
+        // a bridge on screen, or the next one the spawner has in store
+        let on_screen = world
+            .bridges
+            .iter()
+            .filter(|bridge| bridge.location.l < jet)
+            .map(|bridge| u32::from(jet - bridge.location.l))
+            .min();
+        let to_spawn = || {
+            let spawner = world.spawner.as_ref()?;
+            Some(spawner.lines_to_bridge(world)? + u32::from(jet))
+        };
+        if let Some(lines) = on_screen.or_else(to_spawn) {
+            self.lines
+                .push(format!(" Bridge in {} ", world.locale.number(lines)));
+        }
+    }
+
+    fn draw(&self) -> Vec<String> {
+        self.lines.clone()
+    }
+}
+
+/// Entity cap, culling, frame skipping counters and the real frame rate.
+#[cfg(debug_assertions)]
+#[derive(Default)]
+pub struct DebugStatsWidget {
+    line: String,
+}
+
+#[cfg(debug_assertions)]
+impl HudWidget for DebugStatsWidget {
+    fn position(&self) -> HudPosition {
+        HudPosition::Right
+    }
+
+    fn update(&mut self, world: &World) {
+        self.line = format!(
+            " Entities: {}/{} Culled: {} Skipped frames: {} FPS: {:.0}/{} ",
+            world.entity_count(),
+            world.settings.entity_cap,
+            world.culled,
+            world.skipped_frames,
+            world.pacer.fps(),
+            1000 / world.settings.tick_ms.max(1)
+        );
+    }
+
+    fn draw(&self) -> Vec<String> {
+        vec![self.line.clone()]
+    }
+}
+
+/// Allocations since the previous frame and peak RSS.
+#[cfg(feature = "alloc-stats")]
+#[derive(Default)]
+pub struct AllocStatsWidget {
+    last_total: usize,
+    line: String,
+}
+
+#[cfg(feature = "alloc-stats")]
+impl HudWidget for AllocStatsWidget {
+    fn position(&self) -> HudPosition {
+        HudPosition::Right
+    }
+
+    fn update(&mut self, _world: &World) {
+        let total = crate::alloc_stats::allocations();
+        let rss = match crate::alloc_stats::peak_rss_kib() {
+            Some(kib) => format!("{kib} KiB"),
+            None => "n/a".to_string(),
+        };
+        self.line = format!(
+            " Allocs/frame: {} Peak RSS: {rss} ",
+            total - self.last_total
+        );
+        self.last_total = total;
+    }
+
+    fn draw(&self) -> Vec<String> {
+        vec![self.line.clone()]
+    }
+}
+
+impl World {
+    pub fn add_hud_widget(&mut self, widget: impl HudWidget + 'static) {
+        self.hud_widgets.push(Box::new(widget));
+    }
+
+    pub(super) fn draw_status(&mut self) {
+        // Widgets read the whole world, so take them out while they update.
+        let mut widgets = std::mem::take(&mut self.hud_widgets);
+        for widget in widgets.iter_mut() {
+            widget.update(self);
+        }
+
+        let status_style = self.canvas.theme().hud;
+        let (mut left_l, mut right_l) = (2, 2);
+        for widget in widgets.iter() {
+            for line in widget.draw() {
+                let line: String = line.chars().take(self.maxc as usize).collect();
+                let l = match widget.position() {
+                    HudPosition::Left => &mut left_l,
+                    HudPosition::Right => &mut right_l,
+                };
+                if *l >= self.maxl {
+                    break;
+                }
+
+                let c = match widget.position() {
+                    HudPosition::Left => 2,
+                    HudPosition::Right => self.maxc.saturating_sub(line.chars().count() as u16 + 2),
+                };
+                self.canvas.draw_styled_line((c, *l), line, status_style);
+                *l += 1;
+            }
+        }
+
+        self.hud_widgets = widgets;
+    }
+}
@@ -0,0 +1,107 @@
+//! Stable entity ids and looking entities up by them.
+//!
+//! Entities keep their spawn order in their vectors (new ones are pushed,
+//! gone ones removed), and ids are handed out in that order, so a lookup is a
+//! binary search per kind.
+
+use crate::{
+    entities::{BombCrate, Bridge, Enemy, EntityId, Fuel, Obstacle, RechargePad, Tunnel},
+    World,
+};
+
+/// An entity found by [`World::entity`]
+// only the debug build's inspector looks past enemies
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+pub enum EntityRef<'a> {
+    Enemy(&'a Enemy),
+    Fuel(&'a Fuel),
+    Bridge(&'a Bridge),
+    Tunnel(&'a Tunnel),
+    Pad(&'a RechargePad),
+    Bomb(&'a BombCrate),
+    Obstacle(&'a Obstacle),
+}
+
+/// Give the entities pushed since the last call the next ids.
+macro_rules! assign {
+    ($next:expr, $entities:expr) => {
+        let start = $entities
+            .iter()
+            .rposition(|entity| entity.id != EntityId::default())
+            .map_or(0, |index| index + 1);
+        for entity in &mut $entities[start..] {
+            $next += 1;
+            entity.id = EntityId($next);
+        }
+    };
+}
+
+/// Index of `id` among `entities`, sorted by id.
+macro_rules! find {
+    ($entities:expr, $id:expr) => {
+        $entities
+            .binary_search_by_key(&$id, |entity| entity.id)
+            .ok()
+    };
+}
+
+impl World {
+    /// Hand out ids to newly spawned entities, whoever spawned them.
+    pub(super) fn assign_ids(&mut self) {
+        assign!(self.last_id, self.enemies);
+        assign!(self.last_id, self.fuels);
+        assign!(self.last_id, self.bridges);
+        assign!(self.last_id, self.tunnels);
+        assign!(self.last_id, self.pads);
+        assign!(self.last_id, self.bomb_crates);
+        assign!(self.last_id, self.obstacles);
+    }
+
+    /// The entity with `id`, if it is still around.
+    pub fn entity(&self, id: EntityId) -> Option<EntityRef<'_>> {
+        if let Some(index) = find!(self.enemies, id) {
+            Some(EntityRef::Enemy(&self.enemies[index]))
+        } else if let Some(index) = find!(self.fuels, id) {
+            Some(EntityRef::Fuel(&self.fuels[index]))
+        } else if let Some(index) = find!(self.bridges, id) {
+            Some(EntityRef::Bridge(&self.bridges[index]))
+        } else if let Some(index) = find!(self.tunnels, id) {
+            Some(EntityRef::Tunnel(&self.tunnels[index]))
+        } else if let Some(index) = find!(self.pads, id) {
+            Some(EntityRef::Pad(&self.pads[index]))
+        } else if let Some(index) = find!(self.bomb_crates, id) {
+            Some(EntityRef::Bomb(&self.bomb_crates[index]))
+        } else {
+            find!(self.obstacles, id).map(|index| EntityRef::Obstacle(&self.obstacles[index]))
+        }
+    }
+} // end of World implementation.
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::EntityRef;
+    use crate::{
+        entities::{Enemy, EntityId, EntityStatus},
+        World,
+    };
+
+    #[test]
+    fn ids_follow_entities_across_ticks() {
+        let mut world = World::new(80, 24);
+        for c in 30..33 {
+            world.enemies.push(Enemy::new(c, 5, EntityStatus::Alive));
+        }
+        world.assign_ids();
+
+        let id = world.enemies[1].id;
+        assert_ne!(id, EntityId::default());
+        world.enemies.remove(0);
+        world.tick(&[], Duration::from_millis(60));
+
+        world.enemies[0].fire_rate = 99;
+        assert!(matches!(world.entity(id), Some(EntityRef::Enemy(enemy)) if enemy.fire_rate == 99));
+        assert!(world.entity(EntityId(u32::MAX)).is_none());
+    }
+}
@@ -0,0 +1,224 @@
+//! Entity inspector, in debug builds: from the pause menu, move a cursor
+//! over the paused scene and a side panel tells what is under it.
+
+use crate::{
+    behavior::{Movement, Strafing, ZigZag},
+    entities::{Direction, EntityId, EntityStatus, Location, Player},
+    World, WorldStatus,
+};
+
+use super::ids::EntityRef;
+
+/// Columns of the side panel
+const PANEL_WIDTH: usize = 28;
+
+fn status_name(status: &EntityStatus) -> &'static str {
+    match status {
+        EntityStatus::Alive => "alive",
+        EntityStatus::DeadBody => "wreck",
+        EntityStatus::Dead => "dead",
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+impl World {
+    /// Start inspecting, the cursor on the player.
+    pub fn inspect(&mut self) {
+        self.status = WorldStatus::Inspect(self.player.location.clone());
+    }
+
+    /// Move the inspector's cursor by `dc` columns and `dl` lines, staying
+    /// on screen.
+    pub fn move_inspector(&mut self, dc: i16, dl: i16) {
+        if let WorldStatus::Inspect(cursor) = &mut self.status {
+            let c = cursor.c.saturating_add_signed(dc).min(self.maxc - 1);
+            let l = cursor.l.saturating_add_signed(dl).min(self.maxl - 1);
+            *cursor = Location::new(c, l);
+        }
+    }
+
+    /// The entity taking up cell `c`, `l`, if any.
+    fn entity_at(&self, c: u16, l: u16) -> Option<EntityId> {
+        let here = |location: &Location| location.c == c && location.l == l;
+        self.enemies
+            .iter()
+            .find(|enemy| enemy.hitbox().contains(c, l))
+            .map(|enemy| enemy.id)
+            .or_else(|| {
+                self.fuels
+                    .iter()
+                    .find(|fuel| here(&fuel.location))
+                    .map(|fuel| fuel.id)
+            })
+            .or_else(|| {
+                let mut bridges = self.bridges.iter();
+                bridges
+                    .find(|bridge| bridge.hitbox().contains(c, l))
+                    .map(|bridge| bridge.id)
+            })
+            .or_else(|| {
+                self.tunnels
+                    .iter()
+                    .find(|tunnel| here(&tunnel.location))
+                    .map(|t| t.id)
+            })
+            .or_else(|| {
+                self.pads
+                    .iter()
+                    .find(|pad| here(&pad.location))
+                    .map(|pad| pad.id)
+            })
+            .or_else(|| {
+                let mut bombs = self.bomb_crates.iter();
+                bombs.find(|bomb| here(&bomb.location)).map(|bomb| bomb.id)
+            })
+            .or_else(|| {
+                let mut obstacles = self.obstacles.iter();
+                obstacles
+                    .find(|obstacle| here(&obstacle.location))
+                    .map(|obstacle| obstacle.id)
+            })
+    }
+
+    /// What is at cell `c`, `l`, one line per fact.
+    fn describe(&self, c: u16, l: u16) -> Vec<String> {
+        let mut lines = vec![format!("Cell {c},{l}")];
+
+        let player = &self.player;
+        if player
+            .sprite()
+            .cells(&player.location)
+            .any(|(pc, pl, _)| (pc, pl) == (c, l))
+        {
+            lines.push("Player".to_string());
+            lines.push(format!("gas {} lives {}", player.gas, player.lives));
+            lines.push(format!("shield {}/{}", player.shield, Player::MAX_SHIELD));
+            lines.push(format!("bombs {}/{}", player.bombs, Player::MAX_BOMBS));
+            lines.push(format!(
+                "missiles {}/{}",
+                player.missiles,
+                Player::MAX_MISSILES
+            ));
+            lines.push(format!("invulnerable {} ticks", player.invulnerable));
+            return lines;
+        }
+
+        let Some(id) = self.entity_at(c, l) else {
+            let (left, right) = self.map.get(l as usize).copied().unwrap_or_default();
+            let ground = if (left..right).contains(&c) {
+                "river"
+            } else {
+                "bank"
+            };
+            lines.push(ground.to_string());
+            return lines;
+        };
+
+        match self.entity(id) {
+            Some(EntityRef::Enemy(enemy)) => {
+                let kind = enemy.kind.name();
+                let state = match &enemy.movement {
+                    Movement::Straight(_) => "straight".to_string(),
+                    Movement::ZigZag(ZigZag { direction }) => {
+                        format!("zig-zag {}", direction_name(*direction))
+                    }
+                    Movement::Homing(_) => "homing".to_string(),
+                    Movement::Strafing(Strafing { direction, dashing }) => format!(
+                        "{} {}",
+                        if *dashing { "dashing" } else { "waiting" },
+                        direction_name(*direction)
+                    ),
+                    Movement::Centering(_) => "centering".to_string(),
+                };
+                lines.push(format!("{kind} #{}", id.0));
+                lines.push(format!("status {}", status_name(&enemy.status)));
+                lines.push(state);
+                lines.push(format!(
+                    "fire rate {} cooldown {}",
+                    enemy.fire_rate, enemy.cooldown
+                ));
+                lines.push(format!("reward {}", enemy.reward()));
+            }
+            Some(EntityRef::Fuel(fuel)) => {
+                lines.push(format!("Fuel #{}", id.0));
+                lines.push(format!("status {}", status_name(&fuel.status)));
+                lines.push(format!("stock {}", fuel.stock));
+            }
+            Some(EntityRef::Bridge(bridge)) => {
+                lines.push(format!("Bridge #{}", id.0));
+                lines.push(format!("status {}", status_name(&bridge.status)));
+                lines.push(format!("width {}", bridge.width));
+            }
+            Some(EntityRef::Tunnel(tunnel)) => {
+                lines.push(format!("Tunnel #{}", id.0));
+                lines.push(format!("at {} {}", tunnel.location.c, tunnel.location.l));
+            }
+            Some(EntityRef::Pad(pad)) => {
+                lines.push(format!("Recharge pad #{}", id.0));
+                lines.push(format!("charging {}", pad.charging));
+            }
+            Some(EntityRef::Bomb(bomb)) => {
+                lines.push(format!("Bomb crate #{}", id.0));
+                lines.push(format!("at {} {}", bomb.location.c, bomb.location.l));
+            }
+            Some(EntityRef::Obstacle(obstacle)) => {
+                lines.push(format!("{:?} #{}", obstacle.kind, id.0));
+            }
+            None => {}
+        }
+        lines
+    }
+
+    /// Mark the cursor and show what is under it in a side panel.
+    pub(super) fn draw_inspector(&mut self, cursor: &Location) {
+        let focus = self.canvas.theme().popup_focus;
+        let (c, l) = (cursor.c, cursor.l);
+        if c > 0 {
+            self.canvas.draw_styled_char((c - 1, l), '▸', focus);
+        }
+        self.canvas.draw_styled_char((c + 1, l), '◂', focus);
+
+        let style = self.canvas.theme().popup;
+        let left = self.maxc.saturating_sub(PANEL_WIDTH as u16 + 1);
+        for (index, line) in self.describe(c, l).into_iter().enumerate() {
+            let line: String = format!(" {line:<width$}", width = PANEL_WIDTH - 1)
+                .chars()
+                .take(PANEL_WIDTH)
+                .collect();
+            self.canvas
+                .draw_styled_line((left, 2 + index as u16), line, style);
+        }
+    }
+} // end of World implementation.
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        entities::{Enemy, EntityStatus},
+        World,
+    };
+
+    #[test]
+    fn describes_what_is_under_the_cursor() {
+        let mut world = World::new(80, 24);
+        world.enemies.push(Enemy::ship(30, 5));
+        world.enemies.push(Enemy::new(50, 5, EntityStatus::Alive));
+        world.assign_ids();
+
+        // ships are three cells wide
+        assert_eq!(
+            world.describe(31, 5)[1],
+            format!("Ship #{}", world.enemies[0].id.0)
+        );
+        assert!(world.describe(50, 5)[1].starts_with("Enemy #"));
+        assert_eq!(world.describe(40, 5)[1], "river");
+        let player = world.player.location.clone();
+        assert_eq!(world.describe(player.c, player.l - 1)[1], "Player");
+    }
+}
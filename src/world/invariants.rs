@@ -0,0 +1,143 @@
+//! Debug-only sanity checks run every tick.
+//!
+//! Violations are drawn at the bottom of the screen so logic bugs show up
+//! while playing instead of as a panic (or silently) a few ticks later.
+
+use crossterm::style::{ContentStyle, Stylize};
+
+use crate::{
+    entities::{Location, Player},
+    World,
+};
+
+impl World {
+    fn on_screen(&self, location: &Location) -> bool {
+        location.c < self.maxc && location.l < self.maxl
+    }
+
+    /// Returns a description of every broken invariant.
+    pub(super) fn check_invariants(&self) -> Vec<String> {
+        let mut violations = vec![];
+
+        if self.map.len() != self.maxl as usize {
+            violations.push(format!(
+                "map has {} lines, screen has {}",
+                self.map.len(),
+                self.maxl
+            ));
+        }
+
+        for (l, (left, right)) in self.map.iter().enumerate() {
+            if left >= right || *right > self.maxc {
+                violations.push(format!("map line {l} is invalid: {left}..{right}"));
+            }
+            if let Some(Some((start, end))) = self.islands.get(l) {
+                if start <= left || end >= right || start >= end {
+                    violations.push(format!("island on line {l} is invalid: {start}..{end}"));
+                }
+            }
+        }
+        if self.islands.len() != self.map.len() {
+            violations.push(format!(
+                "{} lines of islands for {} lines of map",
+                self.islands.len(),
+                self.map.len()
+            ));
+        }
+        if self.scenery.len() != self.map.len() {
+            violations.push(format!(
+                "{} lines of scenery for {} lines of map",
+                self.scenery.len(),
+                self.map.len()
+            ));
+        }
+
+        if !self.on_screen(&self.player.location) {
+            violations.push("player is off screen".to_string());
+        }
+
+        for enemy in &self.enemies {
+            if !self.on_screen(&enemy.location) {
+                violations.push("enemy is off screen".to_string());
+            }
+        }
+
+        for fuel in &self.fuels {
+            if !self.on_screen(&fuel.location) {
+                violations.push("fuel is off screen".to_string());
+            } else if !self.is_in_river(fuel.location.c, fuel.location.l) {
+                violations.push("fuel is outside the river".to_string());
+            }
+        }
+
+        for bridge in &self.bridges {
+            if !self.on_screen(&bridge.location) || bridge.location.c + bridge.width > self.maxc {
+                violations.push("bridge is off screen".to_string());
+            }
+        }
+
+        for pad in &self.pads {
+            if !self.on_screen(&pad.location) {
+                violations.push("recharge pad is off screen".to_string());
+            }
+        }
+
+        for obstacle in &self.obstacles {
+            if !self.on_screen(&obstacle.location) {
+                violations.push("obstacle is off screen".to_string());
+            } else if !self.is_in_river(obstacle.location.c, obstacle.location.l) {
+                violations.push("obstacle is outside the river".to_string());
+            }
+        }
+
+        if self.player.shield > Player::MAX_SHIELD {
+            violations.push("shield is over its maximum".to_string());
+        }
+
+        for tunnel in &self.tunnels {
+            if !self.on_screen(&tunnel.location) {
+                violations.push("tunnel is off screen".to_string());
+            }
+        }
+
+        for bullet in &self.bullets {
+            if !self.on_screen(&bullet.location) {
+                violations.push("bullet is off screen".to_string());
+            }
+        }
+
+        for bullet in &self.enemy_bullets {
+            if !self.on_screen(&bullet.location) {
+                violations.push("enemy bullet is off screen".to_string());
+            }
+        }
+
+        violations
+    }
+
+    pub(super) fn report_invariants(&mut self) {
+        let style = ContentStyle::new().white().on_red();
+        let violations = self.check_invariants();
+        #[cfg(feature = "arith-audit")]
+        let violations = [violations, crate::arith::reports()].concat();
+        let first_line = self.maxl.saturating_sub(violations.len() as u16);
+
+        for (offset, violation) in violations.into_iter().take(self.maxl as usize).enumerate() {
+            let mut line = format!(" ! {violation} ");
+            line.truncate(self.maxc as usize);
+            self.canvas
+                .draw_styled_line((0, first_line + offset as u16), line, style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    #[test]
+    fn new_world_has_no_violations() {
+        let world = World::new(80, 24);
+        assert!(world.check_invariants().is_empty());
+    }
+}
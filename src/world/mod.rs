@@ -1,19 +1,133 @@
-use std::{collections::VecDeque, io::Stdout, thread, time::Duration};
+use std::{
+    collections::VecDeque,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::mpsc::Receiver,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use rand::{rngs::ThreadRng, thread_rng};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 
 use crate::{
+    biome::{Biome, BIOME_LINES},
     canvas::Canvas,
-    entities::{Bullet, Enemy, Fuel, Location, Player, PlayerStatus},
-    handle_pressed_keys,
+    challenge::{Challenges, Outcome},
+    color_depth::ColorDepth,
+    combat_log::{CombatEvent, CombatLog, COMBAT_LOG_DIR},
+    combo::Combo,
+    control::{Command, SCREENSHOT_DIR},
+    entities::{
+        BombCrate, Bridge, Bullet, DeathCause, Enemy, EnemyBullet, Fuel, Location, Obstacle,
+        Player, PlayerStatus, RechargePad, Spark, Tunnel,
+    },
+    feedback::{Feedback, FeedbackKind},
+    handle_action, handle_pressed_keys,
+    history::{RunDetail, RunRecord, Sample, Sampler, HISTORY_FILE, MAX_LANE},
+    input_macro::InputMacro,
+    keymap::{Action, KeyMap},
+    locale::Locale,
+    menu::Menu,
+    pacing::FramePacer,
+    pack::ContentPack,
+    renderer::Renderer,
+    season::SeasonalEvent,
+    settings::Settings,
+    stage::StageManager,
+    tips::{Tip, Tips, TIPS_FILE},
+    track::Track,
+    versus::{RivalStatus, Versus},
+    webhook::RunSummary,
 };
 
+mod collision;
+#[cfg(test)]
+mod determinism;
 mod drawings;
+mod hashing;
+mod hud;
+mod ids;
+#[cfg(debug_assertions)]
+mod inspector;
+#[cfg(debug_assertions)]
+mod invariants;
 mod physics;
+mod river;
+mod scenery;
+mod spawner;
+mod track;
+
+use collision::LineGrid;
+pub use collision::Region;
+pub use hashing::content_hash;
+#[cfg(feature = "alloc-stats")]
+use hud::AllocStatsWidget;
+#[cfg(debug_assertions)]
+use hud::DebugStatsWidget;
+pub use hud::{
+    ChallengeWidget, ComboWidget, EffectsWidget, HudWidget, MinimapWidget, StatusWidget,
+    VersusWidget,
+};
+pub use scenery::Scenery;
+pub use spawner::{parse_chances, RandomSpawner, SpawnKind, SpawnTable, Spawner, TrackSpawner};
+
+/// Smallest screen the world is laid out for
+pub(crate) const MIN_COLUMNS: u16 = 20;
+pub(crate) const MIN_LINES: u16 = 10;
 
 pub enum WorldStatus {
     Fluent,
     Paused,
+    /// The settings screen, opened from the pause menu
+    Settings,
+    /// The paused scene without the HUD or any menu, for screenshots
+    Photo,
+    /// Past runs, opened from the pause menu
+    History,
+    /// Graphs of the past run at this index of `World::history`
+    RunDetail(usize),
+    /// Asking whether to really quit the run
+    ConfirmQuit,
+    /// Flying through a tunnel, `lines_left` of river still to skip
+    Warping {
+        lines_left: u16,
+    },
+    /// The paused scene with the entity inspector's cursor at this cell
+    #[cfg(debug_assertions)]
+    Inspect(Location),
+}
+
+/// How a game ended.
+#[derive(Debug)]
+pub enum GameOutcome {
+    /// Quit with nothing to lose, or `abandoned` a run in progress
+    PlayerQuit {
+        abandoned: bool,
+    },
+    Died(DeathCause),
+    /// Someone flew the distance of a versus race
+    Race {
+        won: bool,
+    },
+    /// The last stage was flown to its end
+    Completed,
+    /// Drawing or reading input failed
+    Error(std::io::Error),
+}
+
+impl GameOutcome {
+    /// Short name of the ending, e.g. `Fuel` or `Quit`
+    pub fn ending(&self) -> String {
+        match self {
+            GameOutcome::PlayerQuit { abandoned: false } => "Quit".to_string(),
+            GameOutcome::PlayerQuit { abandoned: true } => "Abandoned".to_string(),
+            GameOutcome::Died(cause) => format!("{cause:?}"),
+            GameOutcome::Race { won: true } => "Won".to_string(),
+            GameOutcome::Race { won: false } => "Lost".to_string(),
+            GameOutcome::Completed => "Completed".to_string(),
+            GameOutcome::Error(_) => "Error".to_string(),
+        }
+    }
 }
 
 pub struct World {
@@ -21,6 +135,12 @@ pub struct World {
     pub status: WorldStatus,
     pub player: Player,
     pub map: VecDeque<(u16, u16)>,
+    /// Island of each line of `map`, if any, as its first and end columns
+    pub islands: VecDeque<Option<(u16, u16)>>,
+    /// Scenery on the banks of each line of `map`, by first column
+    pub scenery: VecDeque<Vec<(u16, Scenery)>>,
+    /// Island being laid at the top of the river and the lines it has left
+    pub island_ahead: Option<((u16, u16), u16)>,
     pub maxc: u16,
     pub maxl: u16,
     pub next_right: u16,
@@ -28,12 +148,119 @@ pub struct World {
     pub enemies: Vec<Enemy>,
     pub fuels: Vec<Fuel>,
     pub bullets: Vec<Bullet>,
-    pub rng: ThreadRng, // Local rng for the whole world
+    pub enemy_bullets: Vec<EnemyBullet>,
+    /// Muzzle flashes, bullet trails and explosions
+    pub sparks: Vec<Spark>,
+    pub bridges: Vec<Bridge>,
+    pub tunnels: Vec<Tunnel>,
+    pub pads: Vec<RechargePad>,
+    pub bomb_crates: Vec<BombCrate>,
+    pub obstacles: Vec<Obstacle>,
+    /// Ticks left of the flash of the last bomb
+    pub bomb_flash: u8,
+    /// The current kill streak
+    pub combo: Combo,
+    /// Micro-challenges offered along the run
+    pub challenges: Challenges,
+    /// The player is recharging at a pad this tick
+    pub recharging: bool,
+    /// Number of bridges destroyed so far
+    pub section: u16,
+    /// Colors the terminal can show, themes are fitted to them
+    pub color_depth: ColorDepth,
+    /// Biomes the river goes through, see [`World::biome`]
+    pub biomes: Vec<Biome>,
+    /// Index of the biome the theme was last dressed for
+    themed_biome: usize,
+    /// Lines of river scrolled per tick, may be fractional
+    pub scroll_speed: f32,
+    /// Fraction of a line scrolled but not applied yet
+    scroll_progress: f32,
+    /// Lines of river flown so far, tunnels included
+    pub traveled: u32,
+    /// Running hash of every line of river laid, see [`World::hash_line`]
+    pub river_hash: u64,
+    /// How many entities were culled to respect
+    /// [`crate::settings::Settings::entity_cap`]
+    pub culled: usize,
+    /// Last [`crate::entities::EntityId`] handed out
+    last_id: u32,
+    /// Bullet targets by line, see [`collision`]
+    grid: LineGrid,
+    /// Frames not rendered because the terminal could not keep up
+    pub skipped_frames: usize,
+    pub pacer: FramePacer,
+    pub feedback: Feedback,
+    hud_widgets: Vec<Box<dyn HudWidget>>,
+    pub pause_menu: Menu,
+    pub settings_menu: Menu,
+    pub history_menu: Menu,
+    /// Asks before a run is thrown away, see [`World::has_progress`]
+    pub quit_menu: Menu,
+    /// Where the quit dialog goes back to when cancelled
+    pub quit_from: Option<WorldStatus>,
+    /// Where the free camera of photo mode looks from, see
+    /// [`World::pan_camera`]
+    pub camera: (i16, i16),
+    /// Past runs listed in `history_menu`, most recent first
+    pub history: Vec<RunRecord>,
+    /// Samples of the current run, saved to the history when it ends
+    pub sampler: Sampler,
+    pub combat_log: CombatLog,
+    pub settings: Settings,
+    /// How numbers and durations are written, from the settings
+    pub locale: Locale,
+    pub keymap: KeyMap,
+    pub tips: Tips,
+    pub input_macro: InputMacro,
+    /// A macro was played this run, see [`crate::input_macro`]
+    pub assisted: bool,
+    /// Seasonal event running, see [`crate::season`]
+    pub event: Option<SeasonalEvent>,
+    /// Where the seasonal event comes from while it is being fetched, only
+    /// taken in when a run starts
+    pub event_feed: Option<Receiver<SeasonalEvent>>,
+    /// Holiday pack dressing up the river, see [`crate::pack`]
+    pub pack: Option<ContentPack>,
+    /// Levels of the run, see [`crate::stage`]
+    pub stages: StageManager,
+    /// The versus race being flown, if any
+    pub versus: Option<Versus>,
+    /// Commands coming in from outside, see [`crate::control`]
+    pub control: Option<Receiver<Command>>,
+    /// Spawn chances set through the control interface
+    pub chances: Vec<(SpawnKind, u16)>,
+    #[cfg(feature = "gamepad")]
+    pub gamepad: Option<crate::gamepad::Gamepad>,
+    /// Game mode script reacting to the run, see [`crate::scripting`]
+    #[cfg(feature = "scripting")]
+    pub script: Option<crate::scripting::Script>,
+    pub spawner: Option<Box<dyn Spawner>>,
+    /// Custom track flown instead of a random river, and the next line of it
+    /// to lay; see [`crate::track`]
+    pub track: Option<(Track, usize)>,
+    /// The river of the run so far, when it is being saved as a track
+    pub recording: Option<Track>,
+    pub started_at: Instant,
+    /// Time spent actually playing, pauses excluded.
+    pub play_time: Duration,
+    /// Draws for the line of river being laid, see [`World::line_rng`]
+    pub map_rng: StdRng,
+    /// Draws for what spawns on the first line, see [`World::line_rng`]
+    pub spawn_rng: StdRng,
+    /// Seed of the river and its spawns, shown at the end so the run can be
+    /// replayed
+    pub seed: u64,
+    /// Restarts replay `seed` instead of picking a new one
+    pub fixed_seed: bool,
 }
 
 impl World {
     pub fn new(maxc: u16, maxl: u16) -> World {
-        World {
+        // too small to fly in, laid out as in `resize`
+        let (maxc, maxl) = (maxc.max(MIN_COLUMNS), maxl.max(MIN_LINES));
+        let seed = thread_rng().gen();
+        let mut world = World {
             status: WorldStatus::Fluent,
             canvas: Canvas::new(maxc, maxl),
             player: Player {
@@ -41,34 +268,1541 @@ impl World {
                 status: PlayerStatus::Alive,
                 score: 0,
                 gas: 1700,
+                lives: 3,
+                invulnerable: 0,
+                shield: 0,
+                bombs: 1,
+                missiles: Player::MAX_MISSILES,
             },
             map: VecDeque::from(vec![(maxc / 2 - 5, maxc / 2 + 5); maxl as usize]),
+            islands: VecDeque::from(vec![None; maxl as usize]),
+            scenery: VecDeque::new(),
+            island_ahead: None,
             maxc,
             maxl,
             next_left: maxc / 2 - 7,
             next_right: maxc / 2 + 7,
             enemies: Vec::new(),
             bullets: Vec::new(),
+            sparks: Vec::new(),
             fuels: Vec::new(),
-            rng: thread_rng(),
+            enemy_bullets: Vec::new(),
+            bridges: Vec::new(),
+            tunnels: Vec::new(),
+            pads: Vec::new(),
+            bomb_crates: Vec::new(),
+            obstacles: Vec::new(),
+            bomb_flash: 0,
+            combo: Combo::default(),
+            challenges: Challenges::default(),
+            recharging: false,
+            section: 0,
+            color_depth: ColorDepth::detect(),
+            biomes: Biome::built_in(),
+            themed_biome: 0,
+            scroll_speed: 1.0,
+            scroll_progress: 0.0,
+            traveled: 0,
+            river_hash: 0,
+            culled: 0,
+            last_id: 0,
+            grid: LineGrid::default(),
+            skipped_frames: 0,
+            pacer: FramePacer::new(),
+            feedback: Feedback::new(),
+            hud_widgets: Vec::new(),
+            pause_menu: Menu::new(
+                "Game Paused",
+                &[
+                    "Resume",
+                    "Restart",
+                    "Settings",
+                    "Photo",
+                    "History",
+                    "Quit",
+                    #[cfg(debug_assertions)]
+                    "Inspect",
+                ],
+            ),
+            settings_menu: Menu::new("Settings", &["Sound", "Speed", "Theme", "Back"]),
+            history_menu: Menu::new("History", &["Back"]),
+            quit_menu: Menu::new("Quit this run?", &["Cancel", "Quit"]),
+            quit_from: None,
+            camera: (0, 0),
+            history: Vec::new(),
+            sampler: Sampler::default(),
+            combat_log: CombatLog::new(),
+            settings: Settings::default(),
+            locale: Locale::default(),
+            keymap: KeyMap::default(),
+            tips: Tips::default(),
+            input_macro: InputMacro::default(),
+            assisted: false,
+            event: None,
+            event_feed: None,
+            pack: None,
+            stages: StageManager::default(),
+            versus: None,
+            control: None,
+            chances: Vec::new(),
+            #[cfg(feature = "gamepad")]
+            gamepad: crate::gamepad::Gamepad::new(),
+            #[cfg(feature = "scripting")]
+            script: None,
+            spawner: Some(Box::new(RandomSpawner::new())),
+            track: None,
+            recording: None,
+            started_at: Instant::now(),
+            play_time: Duration::ZERO,
+            map_rng: StdRng::seed_from_u64(seed),
+            spawn_rng: StdRng::seed_from_u64(seed),
+            seed,
+            fixed_seed: false,
+        };
+
+        world.scenery = (0..world.maxl).map(|l| world.lay_scenery(l)).collect();
+        world.apply_settings();
+        world.add_hud_widget(StatusWidget::default());
+        world.add_hud_widget(ComboWidget::default());
+        world.add_hud_widget(ChallengeWidget::default());
+        world.add_hud_widget(EffectsWidget::default());
+        world.add_hud_widget(MinimapWidget::default());
+        #[cfg(debug_assertions)]
+        world.add_hud_widget(DebugStatsWidget::default());
+        #[cfg(feature = "alloc-stats")]
+        world.add_hud_widget(AllocStatsWidget::default());
+        world
+    }
+
+    pub fn game_loop(&mut self, stdout: &mut impl Write) -> GameOutcome {
+        self.tips.start_run();
+        if let Err(error) = self.run(stdout) {
+            return GameOutcome::Error(error);
         }
+
+        let outcome = match self.player.status {
+            PlayerStatus::Dead(cause) => GameOutcome::Died(cause),
+            PlayerStatus::Quit { abandoned } => GameOutcome::PlayerQuit { abandoned },
+            PlayerStatus::RaceOver { won } => GameOutcome::Race { won },
+            PlayerStatus::Completed => GameOutcome::Completed,
+            PlayerStatus::Alive | PlayerStatus::Gliding { .. } => {
+                unreachable!("the game loop only ends when the player is gone")
+            }
+        };
+        if let GameOutcome::Died(DeathCause::Fuel) = outcome {
+            self.tips.notice(Tip::OutOfFuel);
+        }
+        self.save_run(&outcome.ending());
+        outcome
     }
 
-    pub fn game_loop(&mut self, stdout: &mut Stdout, slowness: u64) -> Result<(), std::io::Error> {
-        while self.player.status == PlayerStatus::Alive {
+    fn run(&mut self, stdout: &mut impl Write) -> Result<(), std::io::Error> {
+        let mut frames_to_skip = 0;
+        let mut last_tick = Instant::now();
+        while self.player.is_flying() {
             handle_pressed_keys(self);
-            match self.status {
-                WorldStatus::Fluent => {
-                    self.physics();
-                    self.draw_on_canvas();
+            // the speed can change from the settings screen
+            let tick_budget = Duration::from_millis(self.settings.tick_ms);
+
+            let now = Instant::now();
+            let delta = now - last_tick;
+            last_tick = now;
+            self.tick(&[], delta);
+            self.present(stdout, &mut frames_to_skip, tick_budget)?;
+            self.feedback.flush(stdout)?;
+            self.pacer.wait(tick_budget);
+        }
+
+        Ok(())
+    }
+
+    /// Draw the frame on `renderer`, unless `frames_to_skip` are left. A slow
+    /// terminal (e.g. over SSH) gets fewer frames, but the simulation keeps
+    /// its pace: a frame taking longer than `tick_budget` to draw skips the
+    /// next ones until the game has caught up.
+    fn present(
+        &mut self,
+        renderer: &mut impl Renderer,
+        frames_to_skip: &mut u128,
+        tick_budget: Duration,
+    ) -> Result<(), std::io::Error> {
+        if *frames_to_skip > 0 {
+            *frames_to_skip -= 1;
+            self.skipped_frames += 1;
+            return Ok(());
+        }
+        self.render();
+
+        let started = Instant::now();
+        self.canvas.draw_map(renderer)?;
+        let took = started.elapsed();
+        if took > tick_budget {
+            *frames_to_skip = took.as_millis() / tick_budget.as_millis().max(1);
+        }
+        Ok(())
+    }
+
+    /// Apply `actions`, then advance the world by one tick that took `delta`
+    /// of play. No terminal I/O, so bots, benchmarks and tests can drive the
+    /// world headless. Once the game is over this does nothing.
+    pub fn tick(&mut self, actions: &[Action], delta: Duration) {
+        if !self.player.is_flying() {
+            return;
+        }
+
+        // An event arriving mid-run waits for the next one, so it never
+        // changes the spawns of a run under way.
+        if self.play_time.is_zero() {
+            if let Some(event) = self
+                .event_feed
+                .as_ref()
+                .and_then(|feed| feed.try_recv().ok())
+            {
+                self.event = Some(event);
+                self.event_feed = None;
+            }
+        }
+        while let Some(command) = self
+            .control
+            .as_ref()
+            .and_then(|control| control.try_recv().ok())
+        {
+            self.run_command(command);
+        }
+
+        for action in actions {
+            handle_action(self, *action);
+        }
+
+        match self.status {
+            WorldStatus::Fluent => {
+                self.play_time += delta;
+                for action in self.input_macro.next_tick() {
+                    handle_action(self, action);
                 }
-                WorldStatus::Paused => self.pause_screen(),
+                self.step();
+                self.input_macro.end_tick();
+                self.sample();
+                self.race();
+            }
+            WorldStatus::Warping { .. } => {
+                self.play_time += delta;
+                self.warp_step();
+                self.sample();
+                self.race();
             }
+            WorldStatus::Paused
+            | WorldStatus::Settings
+            | WorldStatus::Photo
+            | WorldStatus::History
+            | WorldStatus::RunDetail(_)
+            | WorldStatus::ConfirmQuit => {}
+            #[cfg(debug_assertions)]
+            WorldStatus::Inspect(_) => {}
+        }
+    }
 
-            self.canvas.draw_map(stdout)?;
-            thread::sleep(Duration::from_millis(slowness));
+    /// Advance the simulation by one tick.
+    fn step(&mut self) {
+        self.physics();
+        self.canvas.advance_clock();
+        self.tips.tick();
+        if let PlayerStatus::Dead(cause) = self.player.status {
+            let location = self.player.location.clone();
+            self.explode(&location);
+            self.feedback.emit(FeedbackKind::Death);
+            self.log(CombatEvent::Death(cause));
         }
+        if let PlayerStatus::Dead(
+            DeathCause::Enemy | DeathCause::Ground | DeathCause::Bridge | DeathCause::Obstacle,
+        ) = self.player.status
+        {
+            self.player.lives -= 1;
+            if self.player.lives > 0 {
+                self.respawn();
+            }
+        }
+    }
 
-        Ok(())
+    /// Draw the current state on the canvas.
+    fn render(&mut self) {
+        match self.status {
+            WorldStatus::Fluent => {
+                self.draw_on_canvas();
+
+                #[cfg(debug_assertions)]
+                self.report_invariants();
+            }
+            WorldStatus::Warping { .. } => {
+                self.draw_on_canvas();
+                self.warp_screen();
+            }
+            WorldStatus::Paused => {
+                self.draw_on_canvas();
+                self.canvas.draw(&self.pause_menu);
+            }
+            WorldStatus::Settings => {
+                self.draw_on_canvas();
+                self.canvas.draw(&self.settings_menu);
+            }
+            WorldStatus::Photo => self.draw_on_canvas(),
+            WorldStatus::History => {
+                self.draw_on_canvas();
+                self.canvas.draw(&self.history_menu);
+            }
+            WorldStatus::ConfirmQuit => {
+                self.draw_on_canvas();
+                self.canvas.draw(&self.quit_menu);
+            }
+            WorldStatus::RunDetail(index) => {
+                self.draw_on_canvas();
+                if let Some(record) = self.history.get(index) {
+                    let locale = self.locale;
+                    self.canvas.draw(&RunDetail { record, locale });
+                }
+            }
+            #[cfg(debug_assertions)]
+            WorldStatus::Inspect(ref cursor) => {
+                let cursor = cursor.clone();
+                self.draw_on_canvas();
+                self.draw_inspector(&cursor);
+            }
+        }
+    }
+
+    /// Push `settings` to where they take effect and refresh the settings
+    /// screen.
+    pub fn apply_settings(&mut self) {
+        self.feedback.muted = !self.settings.sound;
+        self.feedback.terminal.flash = self.settings.flash;
+        self.feedback.quiet = self.settings.quiet.clone();
+        self.feedback.menu_volume = self.settings.menu_volume;
+        self.feedback.popup_volume = self.settings.popup_volume;
+        self.feedback.gameplay_volume = self.settings.gameplay_volume;
+        self.combat_log.enabled = self.settings.combat_log;
+        self.locale = self.settings.locale();
+        self.combat_log.locale = self.locale;
+        self.settings_menu.items[0].label =
+            format!("Sound: {}", if self.settings.sound { "On" } else { "Off" });
+        self.settings_menu.items[1].label = format!("Speed: {}", self.settings.speed_name());
+
+        self.settings_menu.items[2].label =
+            format!("Theme: {}", self.settings.theme(self.color_depth).name);
+        self.refresh_theme();
+    }
+
+    /// The biome the river is in.
+    pub fn biome(&self) -> &Biome {
+        &self.biomes[self.biome_index()]
+    }
+
+    /// Index of [`World::biome`] in `biomes`: the stages take the biomes in
+    /// turn, a long stage moving on every [`BIOME_LINES`], wrapping around.
+    pub(super) fn biome_index(&self) -> usize {
+        let lines = self.stages.flown(self.traveled) / BIOME_LINES;
+        (self.stages.index() + lines as usize) % self.biomes.len()
+    }
+
+    /// Draw with the chosen theme in the current biome, in colors the
+    /// terminal can show.
+    pub(super) fn refresh_theme(&mut self) {
+        self.themed_biome = self.biome_index();
+        let theme = self
+            .settings
+            .theme(self.color_depth)
+            .in_biome(self.biome())
+            .fitted(self.color_depth);
+        self.canvas.set_theme(theme);
+    }
+
+    /// Note `event` in the combat log, at the current play time, and let
+    /// the script react to it.
+    pub(super) fn log(&mut self, event: CombatEvent) {
+        #[cfg(feature = "scripting")]
+        self.run_script(&event);
+        if let Some(outcome) = self.challenges.note(&event, self.play_time) {
+            self.settle_challenge(outcome);
+        }
+        self.combat_log.note(self.play_time, event);
+    }
+
+    /// The player fired a bullet or a missile.
+    pub(crate) fn note_shot(&mut self) {
+        if let Some(outcome) = self.challenges.shot(self.play_time) {
+            self.settle_challenge(outcome);
+        }
+    }
+
+    /// Announce what became of a challenge, paying its bonus if met.
+    pub(super) fn settle_challenge(&mut self, outcome: Outcome) {
+        let text = match outcome {
+            Outcome::Offered(challenge) => format!("Challenge: {challenge}"),
+            Outcome::Met(challenge) => {
+                self.player.earn(challenge.bonus);
+                self.feedback.emit(FeedbackKind::Pickup);
+                format!("Challenge met! +{}", self.locale.number(challenge.bonus))
+            }
+            Outcome::Missed(_) => "Challenge missed".to_string(),
+        };
+        self.stages.announce(text);
+    }
+
+    /// Carry out what the script's handlers of `event` ask for.
+    #[cfg(feature = "scripting")]
+    fn run_script(&mut self, event: &CombatEvent) {
+        let Some(script) = &mut self.script else {
+            return;
+        };
+        for command in script.fire(event) {
+            self.run_command(command);
+        }
+    }
+
+    /// Carry out a command of a script or of the control interface.
+    pub fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Spawn { kind } => self.stages.queue(kind),
+            Command::SetChance { kind, chance } => {
+                self.chances.retain(|(set, _)| *set != kind);
+                self.chances.push((kind, chance.min(1000)));
+            }
+            Command::Popup { text } => self.stages.announce(text),
+            Command::Pause => handle_action(self, Action::Pause),
+            Command::Screenshot => {
+                let text = match self.screenshot() {
+                    Ok(path) => format!("Screenshot saved to {}", path.display()),
+                    Err(error) => format!("Could not save the screenshot: {error}"),
+                };
+                self.stages.announce(text);
+            }
+        }
+    }
+
+    /// Spawn chance of `kind` set through the control interface, if any
+    pub fn chance_override(&self, kind: SpawnKind) -> Option<u16> {
+        self.chances
+            .iter()
+            .find(|(set, _)| *set == kind)
+            .map(|(_, chance)| *chance)
+    }
+
+    /// Save what is on the screen as text in [`SCREENSHOT_DIR`], named after
+    /// the time, and numbered if shots come within the same millisecond.
+    fn screenshot(&self) -> std::io::Result<PathBuf> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        fs::create_dir_all(SCREENSHOT_DIR)?;
+        for n in 0.. {
+            let name = match n {
+                0 => format!("{}.txt", now.as_millis()),
+                n => format!("{}-{n}.txt", now.as_millis()),
+            };
+            let path = Path::new(SCREENSHOT_DIR).join(name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(self.canvas.text().as_bytes())?;
+                    return Ok(path);
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error),
+            }
+        }
+        unreachable!("some name is always free")
+    }
+
+    /// Feed the sampler when a sample is due.
+    fn sample(&mut self) {
+        if self.sampler.due(self.play_time) {
+            self.sampler.push(Sample {
+                fuel: self.player.gas,
+                score: self.player.score,
+                entities: self.entity_count() as u16,
+                column: self.player.location.c,
+                lane: self.lane(),
+            });
+        }
+    }
+
+    /// Where the player is across the river (or its channel), from `0` on
+    /// the left bank to [`MAX_LANE`] on the right one.
+    fn lane(&self) -> u16 {
+        let Location { c, l } = self.player.location;
+        if l >= self.maxl {
+            return MAX_LANE / 2;
+        }
+        let (left, right) = self.channel(c, l);
+        let span = right.saturating_sub(left + 1).max(1);
+        let across = u32::from(c.clamp(left, left + span) - left);
+        (across * u32::from(MAX_LANE) / u32::from(span)) as u16
+    }
+
+    /// Append the current run to the history file, ended by `ending`.
+    fn save_run(&mut self, ending: &str) {
+        let state = self.state_hash();
+        let record = &mut self.sampler.record;
+        record.seconds = self.play_time.as_secs();
+        record.score = self.player.score;
+        record.ending = ending.to_string();
+        let header = format!(
+            "Run with seed {}: {}, score {}, {} lines flown, final state {:016x}",
+            self.seed, record.ending, record.score, self.traveled, state
+        );
+        // tests play runs that are not worth keeping
+        if cfg!(test) {
+            return;
+        }
+        // The run is over either way; losing its record is not worth a crash.
+        let _ = record.append(HISTORY_FILE);
+        let _ = self.combat_log.save(COMBAT_LOG_DIR, &header);
+        let _ = self.tips.save(TIPS_FILE);
+    }
+
+    /// Read past runs back and list the most recent ones in `history_menu`.
+    pub fn load_history(&mut self) {
+        const LISTED_RUNS: usize = 10;
+
+        let mut history = RunRecord::load_all(HISTORY_FILE).unwrap_or_default();
+        history.reverse();
+        history.truncate(LISTED_RUNS);
+
+        let mut labels: Vec<String> = history
+            .iter()
+            .map(|record| record.label(&self.locale))
+            .collect();
+        labels.push("Back".to_string());
+        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+        self.history_menu = Menu::new("History", &labels);
+        self.history = history;
+    }
+
+    /// Start over with a fresh world, keeping the player's setup (keys,
+    /// settings, feedback volumes, controller, HUD widgets, tips, seasonal
+    /// event, holiday pack, stages, script, control interface, custom track,
+    /// input macro, versus race) and the screen. A recording starts over.
+    pub fn restart(&mut self) {
+        self.save_run("Restart");
+        let mut fresh = World::new(self.maxc, self.maxl);
+        std::mem::swap(&mut fresh.canvas, &mut self.canvas);
+        std::mem::swap(&mut fresh.keymap, &mut self.keymap);
+        std::mem::swap(&mut fresh.feedback, &mut self.feedback);
+        std::mem::swap(&mut fresh.settings, &mut self.settings);
+        std::mem::swap(&mut fresh.hud_widgets, &mut self.hud_widgets);
+        std::mem::swap(&mut fresh.tips, &mut self.tips);
+        std::mem::swap(&mut fresh.biomes, &mut self.biomes);
+        std::mem::swap(&mut fresh.input_macro, &mut self.input_macro);
+        std::mem::swap(&mut fresh.event, &mut self.event);
+        std::mem::swap(&mut fresh.event_feed, &mut self.event_feed);
+        std::mem::swap(&mut fresh.pack, &mut self.pack);
+        std::mem::swap(&mut fresh.stages, &mut self.stages);
+        std::mem::swap(&mut fresh.versus, &mut self.versus);
+        std::mem::swap(&mut fresh.control, &mut self.control);
+        std::mem::swap(&mut fresh.chances, &mut self.chances);
+        if let Some((track, _)) = self.track.take() {
+            fresh.set_track(track);
+        }
+        if self.recording.is_some() {
+            fresh.record_track();
+        }
+        if self.fixed_seed {
+            fresh.set_seed(self.seed);
+        }
+        #[cfg(feature = "gamepad")]
+        std::mem::swap(&mut fresh.gamepad, &mut self.gamepad);
+        #[cfg(feature = "scripting")]
+        std::mem::swap(&mut fresh.script, &mut self.script);
+        *self = fresh;
+        self.stages.restart();
+        self.apply_settings();
+        self.tips.start_run();
+    }
+
+    /// What to tell about the game once it ended with `outcome`.
+    pub fn summary(&self, outcome: &GameOutcome) -> RunSummary {
+        RunSummary {
+            score: self.player.score,
+            section: self.section,
+            traveled: self.traveled,
+            ending: outcome.ending(),
+            seed: self.seed,
+            played: self.play_time,
+            assisted: self.assisted,
+            locale: self.locale,
+        }
+    }
+
+    /// Play with `seed` from now on, restarts included.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.fixed_seed = true;
+    }
+
+    /// Dress the river up with `pack`.
+    pub fn set_pack(&mut self, pack: ContentPack) {
+        self.canvas.set_glyphs(pack.glyphs.clone());
+        self.pack = Some(pack);
+    }
+
+    /// Follow a terminal resized to `maxc` columns and `maxl` lines: the
+    /// river is cut or stretched at the bottom and squeezed between the new
+    /// banks, and whatever ends up off screen is dropped.
+    pub fn resize(&mut self, maxc: u16, maxl: u16) {
+        // too small to fly in; keep things sane and let the terminal clip
+        let (maxc, maxl) = (maxc.max(MIN_COLUMNS), maxl.max(MIN_LINES));
+        self.canvas.resize(maxc, maxl);
+        self.maxc = maxc;
+        self.maxl = maxl;
+
+        let last = *self.map.back().expect("the map is never empty");
+        self.map.resize(maxl as usize, last);
+        for (left, right) in self.map.iter_mut() {
+            *right = (*right).min(maxc - 1);
+            *left = (*left).min(right.saturating_sub(3));
+        }
+        // islands squeezed out of the river go, the one ahead is called off
+        let last = *self.islands.back().expect("the map is never empty");
+        self.islands.resize(maxl as usize, last);
+        for (island, (left, right)) in self.islands.iter_mut().zip(&self.map) {
+            if island.is_some_and(|(start, end)| start <= *left || end >= *right) {
+                *island = None;
+            }
+        }
+        self.island_ahead = None;
+        self.scenery.resize(maxl as usize, Vec::new());
+        for l in 0..maxl {
+            self.trim_scenery(l);
+        }
+        self.next_right = self.next_right.min(maxc - 1);
+        self.next_left = self.next_left.min(self.next_right.saturating_sub(3));
+
+        self.player.location.c = self.player.location.c.min(maxc - 1);
+        self.player.location.l = self.player.location.l.min(maxl - 1);
+
+        let on_screen = |location: &Location| location.c < maxc && location.l < maxl;
+        self.enemies.retain(|enemy| on_screen(&enemy.location));
+        self.fuels.retain(|fuel| on_screen(&fuel.location));
+        self.bullets.retain(|bullet| on_screen(&bullet.location));
+        self.enemy_bullets
+            .retain(|bullet| on_screen(&bullet.location));
+        self.sparks.retain(|spark| on_screen(&spark.location));
+        self.bridges.retain(|bridge| on_screen(&bridge.location));
+        self.tunnels.retain(|tunnel| on_screen(&tunnel.location));
+        self.pads.retain(|pad| on_screen(&pad.location));
+        self.bomb_crates.retain(|bomb| on_screen(&bomb.location));
+        self.obstacles
+            .retain(|obstacle| on_screen(&obstacle.location));
+    }
+
+    /// Trade progress with the rival of a versus race, and end the race
+    /// once it is known who flew the target distance first.
+    fn race(&mut self) {
+        let Some(versus) = &mut self.versus else {
+            return;
+        };
+        let played = self.play_time.as_millis() as u64;
+        if self.traveled >= versus.target && versus.finished.is_none() {
+            versus.finished = Some(played);
+        }
+        versus.send(RivalStatus {
+            score: self.player.score,
+            traveled: self.traveled,
+            flying: self.player.is_flying(),
+            played,
+            finished: versus.finished,
+        });
+        versus.poll();
+
+        if !self.player.is_flying() {
+            return;
+        }
+        if let Some(won) = versus.outcome(played) {
+            self.player.status = PlayerStatus::RaceOver { won };
+        }
+    }
+
+    /// Move the photo camera `dc` columns and `dl` lines, no further than
+    /// half the screen away from the river.
+    pub fn pan_camera(&mut self, dc: i16, dl: i16) {
+        let (reach_c, reach_l) = ((self.maxc / 2) as i16, (self.maxl / 2) as i16);
+        self.camera = (
+            (self.camera.0 + dc).clamp(-reach_c, reach_c),
+            (self.camera.1 + dl).clamp(-reach_l, reach_l),
+        );
+    }
+
+    /// The run got somewhere, quitting would throw it away.
+    pub fn has_progress(&self) -> bool {
+        self.traveled > 0 || self.player.score > 0
+    }
+
+    /// Time since the world was created, pauses included.
+    pub fn wall_time(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Bring the player back at the center of the river, briefly invulnerable.
+    fn respawn(&mut self) {
+        let l = self.maxl - 1;
+        self.player.location = Location::new(self.river_center(l), l);
+        self.player.status = PlayerStatus::Alive;
+        self.player.invulnerable = Player::RESPAWN_INVULNERABLE;
+        self.combo.miss();
+        self.bullets.clear();
+        self.enemy_bullets.clear();
+        self.sparks.clear();
+    }
+} // end of World implementation.
+
+#[cfg(test)]
+impl World {
+    /// Fly `ticks` ticks on a full tank, out of harm's way, so tests can watch
+    /// the river go by without the run ending.
+    pub fn fly(&mut self, ticks: u16) {
+        for _ in 0..ticks {
+            self.tick(&[], Duration::from_millis(60));
+            self.player.invulnerable = u16::MAX;
+            self.player.gas = 1700;
+        }
     }
 } // end of World implementation.
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc, sync::mpsc, time::Duration};
+
+    use crossterm::{event::KeyCode, style::ContentStyle};
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    use super::{
+        hud::HudPosition, DeathCause, GameOutcome, HudWidget, MinimapWidget, RandomSpawner,
+        Spawner, World,
+    };
+    use crate::{
+        biome::BIOME_LINES,
+        challenge::Goal,
+        entities::{
+            Bridge, Bullet, Direction, Enemy, EnemyBullet, EntityStatus, Fuel, Location, Obstacle,
+            ObstacleKind, Player, PlayerStatus, RechargePad, SparkKind,
+        },
+        feedback::{assert_event_fired, FeedbackKind},
+        history::{heatmap, MAX_LANE},
+        keymap::Action,
+        locale::Locale,
+        renderer::Renderer,
+        season::SeasonalEvent,
+        stage::{Stage, StageManager},
+        track::{Track, TrackLine},
+        WorldStatus,
+    };
+
+    /// In photo mode the moves pan the camera over the screen, shooting
+    /// recenters it and leaving photo mode always shows the game centered.
+    #[test]
+    fn photo_camera_pans_and_recenters() {
+        let mut world = World::new(80, 24);
+        world.fly(1);
+        // no blinking
+        world.player.invulnerable = 0;
+        let Location { c, l } = world.player.location;
+        let glyph_at = |world: &World, c: u16| {
+            let text = world.canvas.text();
+            text.lines()
+                .nth(l as usize)
+                .and_then(|line| line.chars().nth(c as usize))
+        };
+        world.status = WorldStatus::Photo;
+        world.render();
+        let glyph = glyph_at(&world, c);
+        assert!(glyph.is_some_and(|glyph| glyph != ' '));
+
+        world.tick(&[Action::MoveRight, Action::MoveRight], Duration::ZERO);
+        world.render();
+        assert_eq!(world.camera, (4, 0));
+        assert_eq!(glyph_at(&world, c - 4), glyph);
+
+        // the camera stops half a screen away
+        for _ in 0..100 {
+            world.tick(&[Action::MoveUp], Duration::ZERO);
+        }
+        assert_eq!(world.camera, (4, -12));
+
+        world.tick(&[Action::Shoot], Duration::ZERO);
+        world.render();
+        assert_eq!(world.camera, (0, 0));
+        assert_eq!(glyph_at(&world, c), glyph);
+
+        world.pan_camera(6, 0);
+        world.status = WorldStatus::Paused;
+        world.render();
+        assert_eq!(glyph_at(&world, c), glyph);
+    }
+
+    /// Run the world headless at many terminal sizes, flying around at random
+    /// and resizing now and then, to flush out overflows and out of bounds
+    /// indexing.
+    #[test]
+    fn survives_many_sizes() {
+        // seeded, so a failing size and input sequence can be reproduced
+        let mut rng = StdRng::seed_from_u64(2519);
+        let actions = [
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::Shoot,
+        ];
+        for maxc in [0, 20, 21, 33, 80, 157, 250] {
+            for maxl in [1, 10, 11, 24, 61] {
+                let mut world = World::new(maxc, maxl);
+                world.set_seed(u64::from(maxc) << 16 | u64::from(maxl));
+                for tick in 0..1000 {
+                    let action = *actions.choose(&mut rng).unwrap();
+                    world.tick(&[action], Duration::from_millis(60));
+                    if tick % 10 == 0 {
+                        world.render();
+                    }
+                    if tick % 250 == 249 {
+                        world.resize(rng.gen_range(0..300), rng.gen_range(0..80));
+                    }
+
+                    if !world.player.is_flying() {
+                        world.player.status = PlayerStatus::Alive;
+                        world.player.lives = 3;
+                        world.player.gas = 1700;
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "arith-audit")]
+        assert_eq!(crate::arith::reports(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn biomes_change_with_the_stages() {
+        let mut world = World::new(80, 24);
+        world.stages = StageManager::new(Stage::built_in());
+        assert_eq!(world.biome().name, "Delta");
+        let bank = world.canvas.theme().bank;
+
+        world.traveled = 400;
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.biome().name, "Forest");
+        assert_ne!(world.canvas.theme().bank, bank);
+        // bridges alone do not change it
+        world.section += 10;
+        assert_eq!(world.biome().name, "Forest");
+
+        // the open river never ends, its biomes go on changing with distance
+        let last = Stage::built_in().pop().unwrap();
+        world.stages = StageManager::new(vec![last]);
+        world.traveled = 0;
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.biome().name, "Delta");
+        world.traveled = BIOME_LINES;
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.biome().name, "Forest");
+        assert_ne!(world.canvas.theme().bank, bank);
+        // four biomes, so five moves on the river is back in the forest
+        world.traveled = 5 * BIOME_LINES;
+        assert_eq!(world.biome().name, "Forest");
+    }
+
+    #[test]
+    fn same_seed_same_river() {
+        let mut worlds = [World::new(80, 24), World::new(80, 24)];
+        for world in worlds.iter_mut() {
+            world.set_seed(42);
+            for _ in 0..500 {
+                world.tick(&[], Duration::from_millis(60));
+            }
+        }
+
+        assert_eq!(worlds[0].map, worlds[1].map);
+        assert_eq!(worlds[0].enemies.len(), worlds[1].enemies.len());
+    }
+
+    #[test]
+    fn pausing_is_recorded_even_muted() {
+        let mut world = World::new(80, 24);
+        world.feedback.muted = true;
+        world.tick(&[Action::Pause], Duration::from_millis(60));
+        assert!(matches!(world.status, WorldStatus::Paused));
+        assert_event_fired!(world, FeedbackKind::PopupOpen);
+    }
+
+    #[test]
+    fn screenshots_taken_together_do_not_overwrite_each_other() {
+        let world = World::new(80, 24);
+        let shots: Vec<_> = (0..3).map(|_| world.screenshot().unwrap()).collect();
+        for shot in &shots {
+            std::fs::remove_file(shot).unwrap();
+        }
+        assert!(shots[0] != shots[1] && shots[1] != shots[2] && shots[0] != shots[2]);
+    }
+
+    #[test]
+    fn quitting_a_run_asks_first() {
+        let mut world = World::new(80, 24);
+        world.tick(&[], Duration::from_millis(60));
+        world.tick(&[Action::Quit], Duration::from_millis(60));
+        assert!(matches!(world.status, WorldStatus::ConfirmQuit));
+        assert!(world.player.is_flying());
+
+        // asked already, abandon the run
+        world.tick(&[Action::Quit], Duration::from_millis(60));
+        assert!(!world.player.is_flying());
+        assert!(world.player.status == PlayerStatus::Quit { abandoned: true });
+
+        // nothing to lose, quit at once
+        let mut world = World::new(80, 24);
+        world.tick(&[Action::Quit], Duration::from_millis(60));
+        assert!(world.player.status == PlayerStatus::Quit { abandoned: false });
+    }
+
+    #[test]
+    fn cancelling_a_quit_goes_back_into_the_tunnel() {
+        let mut world = World::new(80, 24);
+        world.tick(&[], Duration::from_millis(60));
+        world.status = WorldStatus::Warping { lines_left: 30 };
+        world.tick(&[Action::Quit], Duration::from_millis(60));
+        assert!(matches!(world.status, WorldStatus::ConfirmQuit));
+        world.tick(&[Action::Pause], Duration::from_millis(60));
+        assert!(matches!(world.status, WorldStatus::Warping { .. }));
+    }
+
+    #[test]
+    fn a_bomb_clears_the_screen() {
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        for c in [30, 40, 50] {
+            world.enemies.push(Enemy::new(c, 5, EntityStatus::Alive));
+        }
+        world.tick(&[Action::Bomb], Duration::from_millis(60));
+        assert!(world
+            .enemies
+            .iter()
+            .all(|enemy| !matches!(enemy.status, EntityStatus::Alive)));
+        assert_eq!(world.player.bombs, 0);
+        assert!(world.player.score > 0 && world.bomb_flash > 0);
+
+        // out of bombs
+        world.enemies.push(Enemy::new(40, 5, EntityStatus::Alive));
+        world.tick(&[Action::Bomb], Duration::from_millis(60));
+        assert!(matches!(
+            world.enemies.last().unwrap().status,
+            EntityStatus::Alive
+        ));
+    }
+
+    #[test]
+    fn missiles_home_in() {
+        let mut world = World::new(80, 24);
+        world.set_seed(1);
+        world.spawner = None;
+        let mut enemy = Enemy::new(43, 8, EntityStatus::Alive);
+        enemy.fire_rate = 0;
+        world.enemies.push(enemy);
+
+        world.tick(&[Action::Missile], Duration::from_millis(60));
+        assert_eq!(world.player.missiles, Player::MAX_MISSILES - 1);
+        for _ in 0..8 {
+            world.tick(&[], Duration::from_millis(60));
+        }
+        assert_eq!(world.player.score, 10);
+        assert!(world.bullets.is_empty());
+
+        world.player.score = u32::MAX - 5;
+        world.player.earn(10);
+        assert_eq!(world.player.score, u32::MAX);
+    }
+
+    #[test]
+    fn seasonal_events_wait_for_the_next_run() {
+        let (sender, feed) = mpsc::channel();
+        let mut world = World::new(80, 24);
+        world.event_feed = Some(feed);
+        world.tick(&[], Duration::from_millis(60));
+        sender.send(SeasonalEvent::EnemyRush).unwrap();
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.event, None);
+
+        let mut next = World::new(80, 24);
+        next.event_feed = world.event_feed.take();
+        next.tick(&[], Duration::from_millis(60));
+        assert_eq!(next.event, Some(SeasonalEvent::EnemyRush));
+    }
+
+    #[test]
+    fn the_least_valuable_entities_are_culled_first() {
+        let mut world = World::new(80, 24);
+        world.settings.entity_cap = 4;
+        world.enemy_bullets.push(EnemyBullet::new(40, 5));
+        world
+            .enemies
+            .push(Enemy::helicopter(30, 3, Direction::Left));
+        world.enemies.push(Enemy::new(35, 4, EntityStatus::Alive));
+        world.enemies.push(Enemy::new(45, 4, EntityStatus::Alive));
+        world.fuels.push(Fuel::new(38, 2, EntityStatus::Alive));
+        world.fuels.push(Fuel::new(42, 2, EntityStatus::Alive));
+        world.assign_ids();
+
+        // the bullet, then the older of the plain enemies
+        world.cull_entities();
+        assert_eq!(world.entity_count(), 4);
+        assert_eq!(world.culled, 2);
+        assert!(world.enemy_bullets.is_empty());
+        let columns = |world: &World| {
+            let enemies = world.enemies.iter().map(|enemy| enemy.location.c);
+            enemies
+                .chain(world.fuels.iter().map(|fuel| fuel.location.c))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(columns(&world), [30, 45, 38, 42]);
+
+        // depots go before the helicopter, worth more to shoot down
+        world.settings.entity_cap = 2;
+        world.cull_entities();
+        assert_eq!(world.culled, 4);
+        assert_eq!(columns(&world), [30, 42]);
+    }
+
+    #[test]
+    fn shells_stop_at_rocks_they_fly_past() {
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        world.player.invulnerable = u16::MAX;
+        // the rock comes down a line as the shell flies up two
+        world
+            .obstacles
+            .push(Obstacle::new(40, 8, ObstacleKind::Rock));
+        world.bullets.push(Bullet::new(40, 10, 20));
+        world.tick(&[], Duration::from_millis(60));
+        assert!(world.bullets.is_empty());
+        assert_eq!(world.obstacles[0].location.l, 9);
+
+        // faster than a line per tick, the river scrolls in substeps
+        world.scroll_speed = 2.0;
+        let traveled = world.traveled;
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.traveled, traveled + 2);
+        assert_eq!(world.obstacles[0].location.l, 11);
+
+        // a brisk stage speeds the river up as well
+        world.scroll_speed = 1.0;
+        let mut rapids = Stage::built_in().remove(0);
+        rapids.end = None;
+        rapids.speed = 2.0;
+        world.stages = StageManager::new(vec![rapids]);
+        let traveled = world.traveled;
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.traveled, traveled + 2);
+    }
+
+    #[test]
+    fn caps_hold_spawns_and_jam_the_gun() {
+        let mut world = World::new(80, 24);
+        world.set_seed(3);
+        world.settings.max_enemies = 2;
+        for _ in 0..300 {
+            world.tick(&[], Duration::from_millis(60));
+            assert!(world.enemies.len() <= 2);
+        }
+
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        world.settings.max_bullets = 0;
+        world.tick(&[Action::Shoot], Duration::from_millis(60));
+        assert!(world.bullets.is_empty());
+        assert!(world
+            .sparks
+            .iter()
+            .any(|spark| spark.kind == SparkKind::Jammed));
+    }
+
+    #[test]
+    fn minimap_counts_down_to_the_next_bridge() {
+        let mut world = World::new(80, 24);
+        world.locale = Locale::default();
+        let jet = world.player.location.l;
+        let mut minimap = MinimapWidget::default();
+
+        // the first bridge of a random river is a whole interval away
+        minimap.update(&world);
+        let lines = minimap.draw();
+        let bridge = u32::from(RandomSpawner::BRIDGE_INTERVAL + jet);
+        assert_eq!(lines.last().unwrap(), &format!(" Bridge in {bridge} "));
+        assert!(lines.len() - 1 <= 6);
+        assert!(lines[lines.len() - 2].contains('▲'));
+
+        let (left, right) = world.map[3];
+        world.bridges.push(Bridge::new(left, 3, right - left));
+        minimap.update(&world);
+        let lines = minimap.draw();
+        assert_eq!(lines.last().unwrap(), &format!(" Bridge in {} ", jet - 3));
+        assert!(lines.iter().any(|line| line.contains('═')));
+    }
+
+    #[test]
+    fn tunnels_keep_the_river_of_the_seed() {
+        let fly = |warp_at: Option<u32>| {
+            let mut world = World::new(80, 24);
+            world.set_seed(11);
+            world.record_track();
+            for tick in 0..300 {
+                if Some(tick) == warp_at {
+                    world.status = WorldStatus::Warping { lines_left: 60 };
+                }
+                world.fly(1);
+            }
+            let lines = world.recording.unwrap().lines;
+            lines
+                .into_iter()
+                .map(|line| (line.banks, line.island))
+                .collect::<Vec<_>>()
+        };
+        let (straight, warped) = (fly(None), fly(Some(50)));
+        let flown = straight.len().min(warped.len());
+        assert!(flown > 300);
+        assert_eq!(straight[..flown], warped[..flown]);
+    }
+
+    #[test]
+    fn refuelling_meets_a_pickups_challenge() {
+        let mut world = World::new(80, 24);
+        world.set_seed(3);
+        world.spawner = None;
+        while world
+            .challenges
+            .current()
+            .map(|progress| progress.challenge.goal)
+            != Some(Goal::Pickups(2))
+        {
+            world.play_time += Duration::from_secs(5);
+            world.challenges.tick(world.play_time);
+        }
+
+        // two depots coming down the jet's column
+        let Location { c, l } = world.player.location;
+        world.fuels.push(Fuel::new(c, l - 4, EntityStatus::Alive));
+        world.fuels.push(Fuel::new(c, l - 9, EntityStatus::Alive));
+        let score = world.player.score;
+        for _ in 0..12 {
+            world.tick(&[], Duration::from_millis(60));
+            world.player.invulnerable = u16::MAX;
+        }
+        assert!(world.challenges.current().is_none());
+        assert_eq!(world.player.score, score + 100);
+        assert!(world
+            .stages
+            .popup()
+            .is_some_and(|text| text.starts_with("Challenge met")));
+    }
+
+    #[test]
+    fn menus_and_popups_ring_once_per_frame() {
+        let mut world = World::new(80, 24);
+        let mut out = Vec::new();
+        world.tick(
+            &[Action::Pause, Action::MoveDown, Action::MoveUp],
+            Duration::from_millis(60),
+        );
+        assert_event_fired!(world, FeedbackKind::PopupOpen);
+        assert_event_fired!(world, FeedbackKind::MenuMove);
+        world.feedback.flush(&mut out).unwrap();
+        assert_eq!(out, b"\x07");
+
+        // a category at volume 0 is silent, the others still ring
+        world.feedback.popup_volume = 0;
+        world.tick(&[Action::Pause], Duration::from_millis(60));
+        assert!(matches!(world.status, WorldStatus::Fluent));
+        assert_event_fired!(world, FeedbackKind::PopupClose);
+        out.clear();
+        world.feedback.flush(&mut out).unwrap();
+        assert!(out.is_empty());
+        world.tick(
+            &[Action::Pause, Action::MoveDown],
+            Duration::from_millis(60),
+        );
+        world.feedback.flush(&mut out).unwrap();
+        assert_eq!(out, b"\x07");
+    }
+
+    /// Drops an enemy in the middle of every new line of river, counting
+    /// its calls
+    struct EnemyPerLine(Rc<Cell<u16>>);
+
+    impl Spawner for EnemyPerLine {
+        fn spawn(&mut self, world: &mut World) {
+            self.0.set(self.0.get() + 1);
+            let (left, right) = world.map[0];
+            let enemy = Enemy::new((left + right) / 2, 0, EntityStatus::Alive);
+            world.enemies.push(enemy);
+        }
+    }
+
+    #[test]
+    fn custom_spawners_replace_the_random_one() {
+        let calls = Rc::new(Cell::new(0));
+        let mut world = World::new(80, 24);
+        world.spawner = Some(Box::new(EnemyPerLine(calls.clone())));
+        world.fly(6);
+        assert_eq!(calls.get(), 6);
+        assert_eq!(world.enemies.len(), 6);
+        assert!(world.fuels.is_empty() && world.bridges.is_empty());
+    }
+
+    #[test]
+    fn crashing_costs_a_life_until_none_are_left() {
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        assert_eq!(world.player.lives, 3);
+        for lives in (0..3).rev() {
+            world.player.invulnerable = 0;
+            let Location { c, l } = world.player.location;
+            world.enemies.push(Enemy::new(c, l, EntityStatus::Alive));
+            world.tick(&[], Duration::from_millis(60));
+            assert_eq!(world.player.lives, lives);
+            if lives > 0 {
+                // back in the middle of the river, out of harm's way for a while
+                assert!(world.player.is_flying());
+                assert_eq!(world.player.location.c, world.river_center(23));
+                assert_eq!(world.player.invulnerable, Player::RESPAWN_INVULNERABLE);
+            }
+        }
+        assert!(!world.player.is_flying());
+    }
+
+    #[test]
+    fn enemies_shoot_down_the_river() {
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        // a wide straight river, nothing to crash into for a while
+        world.map.iter_mut().for_each(|banks| *banks = (10, 70));
+        world.islands.iter_mut().for_each(|island| *island = None);
+        world.player.invulnerable = 0;
+        let Location { c, .. } = world.player.location;
+        let mut silent = Enemy::new(c - 8, 2, EntityStatus::Alive);
+        silent.fire_rate = 0;
+        let mut gunner = Enemy::new(c, 2, EntityStatus::Alive);
+        gunner.cooldown = 0;
+        world.enemies.extend([silent, gunner]);
+
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.enemy_bullets.len(), 1);
+        assert_eq!(world.enemy_bullets[0].location.c, c);
+
+        // the bullet outruns its enemy and takes a life
+        for _ in 0..12 {
+            world.tick(&[], Duration::from_millis(60));
+        }
+        assert_eq!(world.player.lives, 2);
+        assert!(world.enemies.iter().all(|enemy| enemy.location.l < 20));
+    }
+
+    #[test]
+    fn helicopters_patrol_from_bank_to_bank() {
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        world.map.iter_mut().for_each(|banks| *banks = (30, 40));
+        world.islands.iter_mut().for_each(|island| *island = None);
+        world
+            .enemies
+            .push(Enemy::helicopter(36, 0, Direction::Right));
+        let mut columns = Vec::new();
+        for _ in 0..16 {
+            world.fly(1);
+            columns.push(world.enemies[0].location.c);
+        }
+        // over the water all along, turning back at either bank
+        assert!(columns.iter().all(|c| (30..40).contains(c)));
+        let peak = columns.iter().position(|c| *c == 39).unwrap();
+        assert!(columns[peak..].contains(&30));
+        assert!(columns.windows(2).any(|pair| pair[1] > pair[0]));
+        assert!(world.enemies[0].reward() > Enemy::new(0, 0, EntityStatus::Alive).reward());
+    }
+
+    #[test]
+    fn ships_steer_to_the_middle_and_hit_with_their_width() {
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        world.map.iter_mut().for_each(|banks| *banks = (10, 70));
+        world.islands.iter_mut().for_each(|island| *island = None);
+        world.enemies.push(Enemy::ship(20, 0));
+        world.fly(5);
+        assert_eq!(world.enemies[0].location.c, 25);
+
+        // two columns off the jet's middle, only the ship's bow reaches it
+        let crash = |enemy: fn(u16, u16) -> Enemy| {
+            let mut world = World::new(80, 24);
+            world.spawner = None;
+            world.player.invulnerable = 0;
+            let Location { c, l } = world.player.location;
+            world.enemies.push(enemy(c + 2, l));
+            world.tick(&[], Duration::from_millis(60));
+            world.player.lives < 3
+        };
+        assert!(crash(Enemy::ship));
+        assert!(!crash(|c, l| Enemy::new(c, l, EntityStatus::Alive)));
+    }
+
+    #[test]
+    fn depots_run_dry_as_the_jet_refuels() {
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        world.map.iter_mut().for_each(|banks| *banks = (10, 70));
+        world.islands.iter_mut().for_each(|island| *island = None);
+        world.player.gas = 100;
+        let mut depot = Fuel::new(0, 0, EntityStatus::Alive);
+        depot.stock = 50;
+        world.fuels.push(depot);
+
+        // hovering over the depot, which scrolls down to the jet every tick;
+        // a tick burns a unit of gas
+        let mut gas = Vec::new();
+        for _ in 0..4 {
+            let Location { c, l } = world.player.location;
+            if let Some(depot) = world.fuels.first_mut() {
+                depot.location = Location::new(c, l - 1);
+            }
+            world.tick(&[], Duration::from_millis(60));
+            gas.push(world.player.gas);
+        }
+        assert_eq!(gas, [119, 138, 147, 146]);
+        // drained, the depot is a wreck on its way out
+        assert!(world
+            .fuels
+            .iter()
+            .all(|depot| depot.stock == 0 && !matches!(depot.status, EntityStatus::Alive)));
+        assert_event_fired!(world, FeedbackKind::Pickup);
+    }
+
+    /// A terminal taking `delay` to show each frame
+    struct SlowTerminal {
+        delay: Duration,
+        frames: usize,
+    }
+
+    impl Renderer for SlowTerminal {
+        fn clear(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn draw_styled(
+            &mut self,
+            _loc: (u16, u16),
+            _content: &str,
+            _style: Option<ContentStyle>,
+        ) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            std::thread::sleep(self.delay);
+            self.frames += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn slow_terminals_skip_frames_but_not_ticks() {
+        let budget = Duration::from_millis(10);
+        let play = |delay| {
+            let mut world = World::new(80, 24);
+            let mut terminal = SlowTerminal { delay, frames: 0 };
+            let mut frames_to_skip = 0;
+            for _ in 0..9 {
+                world.fly(1);
+                world
+                    .present(&mut terminal, &mut frames_to_skip, budget)
+                    .unwrap();
+            }
+            assert_eq!(world.traveled, 9);
+            (terminal.frames, world.skipped_frames)
+        };
+        assert_eq!(play(Duration::ZERO), (9, 0));
+
+        // a frame taking over two ticks to show skips at least the next two
+        let (frames, skipped) = play(Duration::from_millis(25));
+        assert_eq!(frames + skipped, 9);
+        assert!(frames <= 3 && skipped >= 6);
+    }
+
+    /// Shows the distance flown on the right, counting its updates
+    struct FlownWidget {
+        line: String,
+        updates: Rc<Cell<u16>>,
+    }
+
+    impl HudWidget for FlownWidget {
+        fn position(&self) -> HudPosition {
+            HudPosition::Right
+        }
+
+        fn update(&mut self, world: &World) {
+            self.updates.set(self.updates.get() + 1);
+            self.line = format!("Flown {}", world.traveled);
+        }
+
+        fn draw(&self) -> Vec<String> {
+            vec![self.line.clone()]
+        }
+    }
+
+    #[test]
+    fn added_hud_widgets_are_drawn_every_frame() {
+        let updates = Rc::new(Cell::new(0));
+        let mut world = World::new(80, 24);
+        world.add_hud_widget(FlownWidget {
+            line: String::new(),
+            updates: updates.clone(),
+        });
+        world.fly(5);
+        assert_eq!(updates.get(), 0);
+        world.render();
+        world.fly(2);
+        world.render();
+        assert_eq!(updates.get(), 2);
+        // stacked under the other widgets on the right, two columns in
+        let text = world.canvas.text();
+        let line = text
+            .lines()
+            .position(|line| line.ends_with("Flown 7") && line.chars().count() == 78);
+        assert!(line.is_some_and(|l| l >= 2), "{text}");
+    }
+
+    #[test]
+    fn pads_charge_the_shield_that_takes_a_crash() {
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        world.map.iter_mut().for_each(|banks| *banks = (10, 70));
+        world.islands.iter_mut().for_each(|island| *island = None);
+        world.player.invulnerable = 0;
+        world.pads.push(RechargePad::new(0, 0));
+
+        // hovering next to the pad, which scrolls down beside the jet
+        for _ in 0..RechargePad::TICKS_PER_CHARGE {
+            assert_eq!(world.player.shield, 0);
+            let Location { c, l } = world.player.location;
+            world.pads[0].location = Location::new(c + 2, l - 1);
+            world.tick(&[], Duration::from_millis(60));
+            assert!(world.recharging);
+        }
+        assert_eq!(world.player.shield, 1);
+
+        let Location { c, l } = world.player.location;
+        world.enemies.push(Enemy::new(c, l, EntityStatus::Alive));
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!((world.player.shield, world.player.lives), (0, 3));
+        assert_event_fired!(world, FeedbackKind::Hit);
+    }
+
+    #[test]
+    fn restarting_keeps_the_setup_but_not_the_run() {
+        let mut world = World::new(80, 24);
+        world.keymap.bind(&[KeyCode::Char('x')], Action::Bomb);
+        world.settings.menu_volume = 2;
+        world.settings.tick_ms = 40;
+        world.add_hud_widget(MinimapWidget::default());
+        let widgets = world.hud_widgets.len();
+        world.fly(30);
+        world.player.score = 120;
+
+        // Restart is right under Resume
+        world.tick(
+            &[Action::Pause, Action::MoveDown, Action::Shoot],
+            Duration::from_millis(60),
+        );
+        // the fresh run already flies the rest of the tick
+        assert!(matches!(world.status, WorldStatus::Fluent));
+        assert_eq!((world.traveled, world.player.score), (1, 0));
+        assert_eq!(world.player.lives, 3);
+        assert_eq!(world.keymap.action(KeyCode::Char('x')), Some(Action::Bomb));
+        assert_eq!(world.feedback.menu_volume, 2);
+        assert_eq!(world.settings.tick_ms, 40);
+        assert_eq!(world.hud_widgets.len(), widgets);
+        assert_eq!((world.maxc, world.maxl), (80, 24));
+    }
+
+    #[test]
+    fn an_empty_tank_glides_down_until_refuelled_or_down() {
+        let straight = || {
+            let mut world = World::new(80, 24);
+            world.set_track(Track {
+                lines: vec![TrackLine {
+                    banks: (10, 70),
+                    ..TrackLine::default()
+                }],
+            });
+            world.player.location.l = 10;
+            world.player.invulnerable = 0;
+            world.player.gas = 0;
+            world
+        };
+
+        let mut world = straight();
+        world.tick(&[], Duration::from_millis(60));
+        assert!(matches!(world.player.status, PlayerStatus::Gliding { .. }));
+        // no shooting, and drifting down a line every few ticks
+        for _ in 0..Player::GLIDE_DRIFT * 2 {
+            world.tick(&[Action::Shoot], Duration::from_millis(60));
+        }
+        assert!(world.bullets.is_empty());
+        assert_eq!(world.player.location.l, 12);
+        // a depot in time saves the run
+        let Location { c, l } = world.player.location;
+        world.fuels.push(Fuel::new(c, l - 1, EntityStatus::Alive));
+        world.tick(&[], Duration::from_millis(60));
+        world.tick(&[], Duration::from_millis(60));
+        assert!(world.player.status == PlayerStatus::Alive);
+
+        // otherwise the glide ends the game, lives left or not
+        let mut world = straight();
+        for _ in 0..=Player::GLIDE_TICKS + 1 {
+            world.tick(&[], Duration::from_millis(60));
+        }
+        assert!(world.player.status == PlayerStatus::Dead(DeathCause::Fuel));
+        assert_eq!(world.player.lives, 3);
+    }
+
+    #[test]
+    fn long_runs_count_past_u16_and_scores_saturate() {
+        let mut world = World::new(80, 24);
+        world.traveled = u32::from(u16::MAX) - 1;
+        world.player.score = u32::MAX - 5;
+        world.enemies.push(Enemy::new(40, 5, EntityStatus::Alive));
+        world.tick(&[Action::Bomb], Duration::from_millis(60));
+        assert_eq!(world.player.score, u32::MAX);
+
+        world.fly(20);
+        assert_eq!(world.traveled, u32::from(u16::MAX) + 20);
+        assert_eq!(world.sampler.record.scores.last(), Some(&u32::MAX));
+        let summary = world.summary(&GameOutcome::PlayerQuit { abandoned: true });
+        assert_eq!(
+            (summary.score, summary.traveled),
+            (u32::MAX, u32::from(u16::MAX) + 20)
+        );
+    }
+
+    #[test]
+    fn the_heatmap_shows_where_the_run_was_flown() {
+        let mut world = World::new(80, 24);
+        world.set_track(Track {
+            lines: vec![TrackLine {
+                banks: (10, 70),
+                ..TrackLine::default()
+            }],
+        });
+        // three seconds along the left bank, one along the right one
+        for (c, ticks) in [(11, 50), (69, 17)] {
+            for _ in 0..ticks {
+                world.player.location.c = c;
+                world.fly(1);
+            }
+        }
+        // a sample a second, the darker the longer
+        let lanes = &world.sampler.record.lanes;
+        assert_eq!(lanes, &[1, 1, 1, 1, 100]);
+        assert_eq!(heatmap(lanes, MAX_LANE, 5), "█   ░");
+    }
+}
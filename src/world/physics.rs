@@ -1,21 +1,72 @@
-use crate::World;
+use crate::{
+    arith, behavior::Surroundings, combat_log::CombatEvent, feedback::FeedbackKind,
+    stage::RiverMode, versus::SYNC_LINES, Region, World, WorldStatus,
+};
+
+use std::cmp::Ordering;
 
 use rand::Rng;
-use std::num::Wrapping;
 
-use crate::entities::{DeathCause, Enemy, EntityStatus, Fuel, PlayerStatus};
+use super::{ids::EntityRef, river::MAP_STREAM};
+use crate::entities::{
+    BombCrate, Bullet, BulletKind, DeathCause, Enemy, EnemyBullet, EntityStatus, Fuel, Location,
+    Player, PlayerStatus, RechargePad, Spark, SparkKind,
+};
+
+/// Lines per tick the river speeds up by for every bridge destroyed
+const SCROLL_SPEEDUP: f32 = 0.05;
+const MAX_SCROLL_SPEED: f32 = 2.0;
 
 impl World {
-    /// check if player hit the ground
+    /// check if player hit the ground, with any cell of the jet
     fn check_player_status(&mut self) {
-        if self.player.location.c < self.map[self.player.location.l as usize].0
-            || self.player.location.c >= self.map[self.player.location.l as usize].1
-        {
+        let sprite = self.player.sprite();
+        let mut grazing = false;
+        let mut grounded = false;
+        let mut struck = false;
+        for (c, l, _) in sprite.cells(&self.player.location) {
+            let Some(&(left, right)) = self.map.get(l as usize) else {
+                continue;
+            };
+            grounded |= !self.is_in_river(c, l);
+            struck |= self.obstacle_at(c, l);
+            grazing |= c == left || c + 1 == right;
+            if let Some((start, end)) = self.islands[l as usize] {
+                grazing |= c + 1 == start || c == end;
+            }
+        }
+
+        if self.player.invulnerable == 0 && grounded {
             self.player.status = PlayerStatus::Dead(DeathCause::Ground);
+        } else if self.player.invulnerable == 0 && struck {
+            self.player.status = PlayerStatus::Dead(DeathCause::Obstacle);
         }
+        self.tips.graze(grazing);
+    }
 
-        if self.player.gas == 0 {
-            self.player.status = PlayerStatus::Dead(DeathCause::Fuel);
+    /// start, keep up or end a glide on an empty tank
+    fn check_gas(&mut self) {
+        match self.player.status {
+            PlayerStatus::Alive if self.player.gas == 0 => {
+                self.player.status = PlayerStatus::Gliding {
+                    ticks_left: Player::GLIDE_TICKS,
+                };
+            }
+            PlayerStatus::Gliding { .. } if self.player.gas > 0 => {
+                self.player.status = PlayerStatus::Alive;
+            }
+            PlayerStatus::Gliding { ticks_left: 0 } => {
+                self.player.status = PlayerStatus::Dead(DeathCause::Fuel);
+            }
+            PlayerStatus::Gliding { ticks_left } => {
+                if ticks_left % Player::GLIDE_DRIFT == 0 && self.player.location.l < self.maxl - 1 {
+                    self.player.location.l += 1;
+                }
+                self.player.status = PlayerStatus::Gliding {
+                    ticks_left: ticks_left - 1,
+                };
+            }
+            _ => {}
         }
     }
 
@@ -25,146 +76,590 @@ impl World {
         self.enemies
             .retain(|f| !matches!(f.status, EntityStatus::Dead));
 
+        let player = self.player.sprite();
+        let mut rams = Vec::new();
         for enemy in self.enemies.iter_mut().rev() {
+            let mut hitbox = enemy.hitbox();
+            // dashing strafers move two columns per tick, widen their hitbox
+            if enemy.is_dashing() {
+                hitbox.left = hitbox.left.saturating_sub(1);
+                hitbox.right += 1;
+            }
+
             match enemy.status {
-                EntityStatus::Alive if self.player.location.hit(&enemy.location) => {
-                    self.player.status = PlayerStatus::Dead(DeathCause::Enemy);
+                EntityStatus::Alive
+                    if self.player.invulnerable == 0
+                        && player.hits(&self.player.location, &hitbox) =>
+                {
+                    if self.player.absorb_hit() {
+                        self.feedback.emit(FeedbackKind::Hit);
+                        enemy.status = EntityStatus::DeadBody;
+                        self.sparks.extend(Spark::burst(&enemy.location));
+                        rams.push(CombatEvent::Hit {
+                            shield_left: self.player.shield,
+                        });
+                    } else {
+                        self.player.status = PlayerStatus::Dead(DeathCause::Enemy);
+                    }
                 }
                 EntityStatus::DeadBody => {
                     enemy.status = EntityStatus::Dead;
                 }
                 _ => {}
             }
+        }
+        // the enemies are borrowed above
+        for event in rams {
+            self.log(event);
+        }
+    }
+
+    /// check bridges hit something
+    fn check_bridge_status(&mut self) {
+        // Remove dead
+        self.bridges
+            .retain(|b| !matches!(b.status, EntityStatus::Dead));
 
-            for bullet in self.bullets.iter().rev() {
-                if bullet.location.hit_with_margin(&enemy.location, 1, 0, 1, 0) {
-                    enemy.status = EntityStatus::DeadBody;
-                    self.player.score += 10;
+        let section = self.section;
+        let player = self.player.sprite();
+        for bridge in self.bridges.iter_mut() {
+            match bridge.status {
+                EntityStatus::Alive
+                    if self.player.invulnerable == 0
+                        && player.hits(&self.player.location, &bridge.hitbox()) =>
+                {
+                    self.player.status = PlayerStatus::Dead(DeathCause::Bridge);
+                }
+                EntityStatus::DeadBody => {
+                    bridge.status = EntityStatus::Dead;
+                }
+                _ => {}
+            }
+
+            let mut shot = false;
+            if matches!(bridge.status, EntityStatus::Alive) {
+                let hits = |bullet: &&mut Bullet| bridge.covers(&bullet.location, 1);
+                for bullet in self.bullets.iter_mut().filter(hits) {
+                    bullet.hit = true;
+                    shot = true;
                 }
             }
+            if shot {
+                bridge.status = EntityStatus::DeadBody;
+                self.player.earn(100);
+                self.player.missiles = Player::MAX_MISSILES;
+                self.section += 1;
+                // every section the river runs a little faster
+                self.scroll_speed = (self.scroll_speed + SCROLL_SPEEDUP).min(MAX_SCROLL_SPEED);
+            }
+        }
+
+        if section != self.section {
+            let biome = self.biome().name.clone();
+            self.log(CombatEvent::Section {
+                section: self.section,
+                biome,
+            });
+        }
+    }
+
+    /// check if the player flew into a tunnel
+    fn check_tunnel_status(&mut self) {
+        if self.tunnels.iter().any(|tunnel| {
+            tunnel
+                .location
+                .hit_with_margin(&self.player.location, 1, 0, 1, 0)
+        }) {
+            self.tunnels.clear();
+            self.bullets.clear();
+            self.log(CombatEvent::Warp);
+            self.status = WorldStatus::Warping {
+                lines_left: self.settings.warp_distance,
+            };
         }
     }
 
+    /// Move tunnels along with the map
+    fn move_tunnels(&mut self) {
+        self.tunnels.retain_mut(|tunnel| {
+            tunnel.location.l += 1;
+            // Retain tunnels within the screen
+            tunnel.location.l < self.maxl
+        });
+    }
+
+    /// Rush the river by while inside a tunnel. Nothing can be hit, the
+    /// player comes out in the middle of the river.
+    pub(super) fn warp_step(&mut self) {
+        const WARP_SPEED: u16 = 4;
+
+        let WorldStatus::Warping { lines_left } = self.status else {
+            return;
+        };
+
+        for _ in 0..WARP_SPEED.min(lines_left) {
+            self.scroll_line();
+        }
+
+        let lines_left = lines_left.saturating_sub(WARP_SPEED);
+        if lines_left > 0 {
+            self.status = WorldStatus::Warping { lines_left };
+        } else {
+            self.player.location.c = self.river_center(self.player.location.l);
+            self.player.invulnerable = 20;
+            self.status = WorldStatus::Fluent;
+        }
+    }
+
+    /// Move bridges along with the map
+    fn move_bridges(&mut self) {
+        self.bridges.retain_mut(|bridge| {
+            bridge.location.l += 1;
+            // Retain bridges within the screen
+            bridge.location.l < self.maxl
+        });
+    }
+
     /// Update the map
     fn update_map(&mut self) {
         use std::cmp::Ordering::*;
 
         // move the map downward using VecDeque
         self.map.pop_back();
+        self.islands.pop_back();
+        self.scenery.pop_back();
+        if let Some(line) = self.next_track_line() {
+            (self.next_left, self.next_right) = line.banks;
+            self.push_line(line.banks, line.island);
+            return;
+        }
+
+        self.map_rng = self.line_rng(MAP_STREAM);
         let (mut left, mut right) = self.map[0];
         match self.next_left.cmp(&left) {
-            Greater => left += 1,
-            Less => left -= 1,
+            Greater => left = arith::add(left, 1),
+            Less => left = arith::sub(left, 1),
             Equal => {}
         };
 
         match self.next_right.cmp(&right) {
-            Greater => right += 1,
-            Less => right -= 1,
+            Greater => right = arith::add(right, 1),
+            Less => right = arith::sub(right, 1),
             Equal => {}
         };
 
-        if self.next_left == self.map[0].0 && self.rng.gen_range(0..10) >= 7 {
+        if self.next_left == self.map[0].0 && self.map_rng.gen_range(0..10) >= 7 {
             self.next_left = self
-                .rng
+                .map_rng
                 .gen_range(self.next_left.saturating_sub(5)..self.next_left + 5);
             if self.next_left == 0 {
                 self.next_left = 1;
             }
         }
 
-        if self.next_right == self.map[0].1 && self.rng.gen_range(0..10) >= 7 {
-            self.next_right = self.rng.gen_range(self.next_right - 5..self.next_right + 5);
-            if self.next_right > self.maxc {
-                self.next_right = Wrapping(self.maxc).0 - 1;
+        if self.next_right == self.map[0].1 && self.map_rng.gen_range(0..10) >= 7 {
+            self.next_right = self
+                .map_rng
+                .gen_range(self.next_right.saturating_sub(5)..arith::add(self.next_right, 5));
+            if self.next_right >= self.maxc {
+                self.next_right = arith::sub(self.maxc, 1);
             }
         }
 
-        if self.next_right.abs_diff(self.next_left) < 3 {
-            self.next_right += 3;
+        // keep the river open, without pushing it past the screen edge, and
+        // as wide as the stage wants it
+        let (min_width, max_width) = self
+            .stages
+            .stage()
+            .map_or(RiverMode::Normal, |stage| stage.river)
+            .widths();
+        if self.next_right < arith::add(self.next_left, min_width) {
+            self.next_right = arith::add(self.next_left, min_width).min(arith::sub(self.maxc, 1));
+            self.next_left = self
+                .next_left
+                .min(self.next_right.saturating_sub(min_width));
         }
+        if let Some(max_width) = max_width {
+            self.next_right = self.next_right.min(arith::add(self.next_left, max_width));
+        }
+
+        let island = self.lay_island(left, right);
+        self.push_line((left, right), island);
+    }
 
-        self.map.push_front((left, right))
+    /// Add a new first line of river to the map.
+    fn push_line(&mut self, banks: (u16, u16), island: Option<(u16, u16)>) {
+        self.map.push_front(banks);
+        self.islands.push_front(island);
+        self.traveled = self.traveled.saturating_add(1);
+        let scenery = self.lay_scenery(0);
+        self.scenery.push_front(scenery);
+        self.record_line(banks, island);
+        self.hash_line(banks, island);
+        if let Some(versus) = &mut self.versus {
+            if self.traveled.is_multiple_of(SYNC_LINES) {
+                versus.sync(self.traveled, self.river_hash);
+            }
+        }
     }
 
     /// Move enemies on the river
     fn move_enemies(&mut self) {
+        let around = Surroundings {
+            map: &self.map,
+            islands: &self.islands,
+            player: &self.player.location,
+            maxc: self.maxc,
+        };
         self.enemies.retain_mut(|enemy| {
-            enemy.location.l += 1;
-            // Retain enemies within the screen
-            enemy.location.l < self.maxl
+            enemy.movement.behavior().act(&mut enemy.location, &around)
+                // Retain enemies within the screen
+                && enemy.location.l < self.maxl
+                && enemy.location.c < self.maxc
+        });
+    }
+
+    /// Aim the missile at `index` at the nearest alive enemy ahead, keeping
+    /// its target while that lives, and steer it a column toward it.
+    fn steer_missile(&mut self, index: usize) {
+        let missile = &self.bullets[index];
+        let BulletKind::Missile { target } = missile.kind else {
+            return;
+        };
+
+        let ahead = |enemy: &&Enemy| {
+            matches!(enemy.status, EntityStatus::Alive) && enemy.location.l < missile.location.l
+        };
+        let kept = target
+            .and_then(|id| match self.entity(id) {
+                Some(EntityRef::Enemy(enemy)) => Some(enemy),
+                _ => None,
+            })
+            .filter(ahead);
+        let enemy = kept.or_else(|| {
+            self.enemies.iter().filter(ahead).min_by_key(|enemy| {
+                enemy.location.c.abs_diff(missile.location.c) + missile.location.l
+                    - enemy.location.l
+            })
         });
+        let (target, column) = match enemy {
+            Some(enemy) => (Some(enemy.id), Some(enemy.location.c)),
+            None => (None, None),
+        };
+
+        let missile = &mut self.bullets[index];
+        missile.kind = BulletKind::Missile { target };
+        match column.map(|column| missile.location.c.cmp(&column)) {
+            Some(Ordering::Less) => missile.location.c += 1,
+            Some(Ordering::Greater) => missile.location.c -= 1,
+            _ => {}
+        }
     }
 
-    /// Move Bullets
-    fn move_bullets(&mut self) {
+    /// Fly the bullets on by a line in substep `step` of the tick, those that
+    /// fly that fast. A bullet stops at the first bank or obstacle on its
+    /// way, not only where it ends up.
+    fn move_bullets(&mut self, step: u16) {
         for index in (0..self.bullets.len()).rev() {
-            if self.bullets[index].energy == 0 || self.bullets[index].location.l <= 2 {
-                self.bullets.remove(index);
-            } else {
-                self.bullets[index].location.l -= 2;
+            let Bullet { speed, energy, .. } = self.bullets[index];
+            if step >= speed {
+                continue;
+            }
+            // a shell is two lines tall, keep it on screen
+            if energy == 0 || self.bullets[index].location.l <= 1 {
+                self.remove_bullet(index);
+                continue;
+            }
+            if step == 0 {
+                self.steer_missile(index);
                 self.bullets[index].energy -= 1;
+            }
+            // mark the cells flown through
+            let Location { c, l } = self.bullets[index].location;
+            self.sparks.push(Spark::new(c, l, SparkKind::Trail));
+            self.bullets[index].location.l -= 1;
 
-                if self.bullets[index].location.c
-                    < self.map[self.bullets[index].location.l as usize].0
-                    || self.bullets[index].location.c
-                        >= self.map[self.bullets[index].location.l as usize].1
-                {
-                    self.bullets.remove(index);
-                }
+            if !self.is_in_river(c, l - 1) || self.obstacle_at(c, l - 1) {
+                self.remove_bullet(index);
             }
         }
     }
 
+    /// Drop the bullet at `index`, ending the kill streak if it hit nothing
+    fn remove_bullet(&mut self, index: usize) {
+        if !self.bullets.remove(index).hit {
+            self.combo.miss();
+        }
+    }
+
+    /// Blow up whatever was at `location`
+    pub(super) fn explode(&mut self, location: &Location) {
+        self.sparks.extend(Spark::burst(location));
+    }
+
+    /// Age and move flashes, trails and explosions, dropping the burnt out ones
+    fn fade_sparks(&mut self) {
+        let (maxc, maxl) = (self.maxc, self.maxl);
+        self.sparks.retain_mut(|spark| spark.update(maxc, maxl));
+    }
+
     /// check if fuel is hit / moved over
     fn check_fuel_status(&mut self) {
         // Remove dead
         self.fuels
             .retain(|f| !matches!(f.status, EntityStatus::Dead));
 
+        let mut tapped = 0;
         for fuel in self.fuels.iter_mut().rev() {
             match fuel.status {
-                EntityStatus::Alive if self.player.location.hit(&fuel.location) => {
-                    fuel.status = EntityStatus::DeadBody;
-                    self.player.gas += 200;
+                // The depot scrolls by a line per tick, so be lenient vertically
+                EntityStatus::Alive
+                    if self
+                        .player
+                        .location
+                        .hit_with_margin(&fuel.location, 1, 0, 1, 0) =>
+                {
+                    let amount = fuel.stock.min(Fuel::REFUEL_RATE);
+                    fuel.stock -= amount;
+                    self.player.refuel(amount);
+                    // a depot counts as picked up once, drained or not
+                    if !fuel.tapped {
+                        fuel.tapped = true;
+                        tapped += 1;
+                    }
+                    if fuel.stock == 0 {
+                        fuel.status = EntityStatus::DeadBody;
+                        self.feedback.emit(FeedbackKind::Pickup);
+                    }
                 }
                 EntityStatus::DeadBody => {
                     fuel.status = EntityStatus::Dead;
                 }
                 _ => {}
             }
+        }
+        for _ in 0..tapped {
+            self.log(CombatEvent::Pickup("fuel depot"));
+        }
+    }
 
-            for bullet in self.bullets.iter().rev() {
-                if bullet.location.hit_with_margin(&fuel.location, 1, 0, 1, 0) {
-                    fuel.status = EntityStatus::DeadBody;
-                    self.player.score += 20;
+    /// Let alive enemies shoot down the river
+    fn fire_enemy_bullets(&mut self) {
+        for enemy in self.enemies.iter_mut() {
+            if enemy.fire_rate == 0 || !matches!(enemy.status, EntityStatus::Alive) {
+                continue;
+            }
+
+            if enemy.cooldown > 0 {
+                enemy.cooldown -= 1;
+            } else if enemy.location.l + 1 < self.maxl
+                && self.bullets.len() + self.enemy_bullets.len() < self.settings.max_bullets
+            {
+                enemy.cooldown = enemy.fire_rate;
+                self.enemy_bullets
+                    .push(EnemyBullet::new(enemy.location.c, enemy.location.l + 1));
+            }
+        }
+    }
+
+    /// Move enemy bullets toward the player
+    fn move_enemy_bullets(&mut self) {
+        self.enemy_bullets.retain_mut(|bullet| {
+            bullet.location.l += 2;
+            // Retain bullets within the screen
+            bullet.location.l < self.maxl
+        });
+    }
+
+    /// check if an enemy bullet hit the player
+    fn check_enemy_bullets_status(&mut self) {
+        if self.player.invulnerable > 0 {
+            return;
+        }
+
+        // bullets move two lines per tick, widen the hitbox vertically
+        let (sprite, location) = (self.player.sprite(), self.player.location.clone());
+        let hit =
+            |bullet: &EnemyBullet| sprite.hits(&location, &Region::around(&bullet.location, 0, 1));
+        let hits = self
+            .enemy_bullets
+            .iter()
+            .filter(|bullet| hit(bullet))
+            .count();
+
+        if hits > 0 {
+            if self.player.absorb_hit() {
+                self.feedback.emit(FeedbackKind::Hit);
+                self.enemy_bullets.retain(|bullet| !hit(bullet));
+                let shield_left = self.player.shield;
+                self.log(CombatEvent::Hit { shield_left });
+            } else {
+                self.player.status = PlayerStatus::Dead(DeathCause::Enemy);
+            }
+        }
+    }
+
+    /// Recharge the shield while the player hovers next to a pad
+    fn check_pad_status(&mut self) {
+        self.recharging = false;
+        let mut charges = 0;
+        for pad in self.pads.iter_mut() {
+            if !pad
+                .location
+                .hit_with_margin(&self.player.location, 1, 2, 1, 2)
+            {
+                continue;
+            }
+
+            self.recharging = true;
+            pad.charging += 1;
+            if pad.charging >= RechargePad::TICKS_PER_CHARGE {
+                pad.charging = 0;
+                if self.player.shield < Player::MAX_SHIELD {
+                    self.player.shield += 1;
+                    self.feedback.emit(FeedbackKind::Pickup);
+                    charges += 1;
                 }
             }
         }
+        for _ in 0..charges {
+            self.log(CombatEvent::Pickup("shield charged"));
+        }
     }
 
-    /// Create a new fuel; maybe
-    fn create_fuel(&mut self) {
-        // Possibility
-        if self.rng.gen_range(0..100) >= 99 {
-            self.fuels.push(Fuel::new(
-                self.rng.gen_range(self.map[0].0..self.map[0].1),
-                0,
-                EntityStatus::Alive,
-            ));
+    /// Pick up the bomb crates the player flies over
+    fn check_bomb_crate_status(&mut self) {
+        let player = &self.player;
+        let picked = |bomb: &BombCrate| bomb.location.hit_with_margin(&player.location, 1, 1, 1, 1);
+        if player.bombs < Player::MAX_BOMBS && self.bomb_crates.iter().any(picked) {
+            self.bomb_crates.retain(|bomb| !picked(bomb));
+            self.player.bombs += 1;
+            self.feedback.emit(FeedbackKind::Pickup);
+            self.log(CombatEvent::Pickup("bomb crate"));
         }
     }
 
-    /// Create a new enemy
-    fn create_enemy(&mut self) {
-        // Possibility
-        if self.rng.gen_range(0..10) >= 9 {
-            self.enemies.push(Enemy::new(
-                self.rng.gen_range(self.map[0].0..self.map[0].1),
-                0,
-                EntityStatus::Alive,
-            ));
+    /// Move bomb crates along with the map
+    fn move_bomb_crates(&mut self) {
+        self.bomb_crates.retain_mut(|bomb| {
+            bomb.location.l += 1;
+            // Retain bomb crates within the screen
+            bomb.location.l < self.maxl
+        });
+    }
+
+    /// Set a bomb off: every alive enemy on screen is wrecked and scored,
+    /// and the screen flashes. Does nothing without bombs left.
+    pub fn drop_bomb(&mut self) {
+        const FLASH_TICKS: u8 = 2;
+
+        if self.player.bombs == 0 || !self.player.is_flying() {
+            return;
+        }
+        self.player.bombs -= 1;
+        self.bomb_flash = FLASH_TICKS;
+        self.feedback.emit(FeedbackKind::Hit);
+
+        let mut wrecked = Vec::new();
+        for enemy in &mut self.enemies {
+            if matches!(enemy.status, EntityStatus::Alive) {
+                enemy.status = EntityStatus::DeadBody;
+                self.player.earn(self.combo.kill(enemy.reward()));
+                wrecked.push(enemy.location.clone());
+            }
+        }
+        for location in &wrecked {
+            self.explode(location);
+        }
+        self.log(CombatEvent::Bomb {
+            kills: wrecked.len(),
+        });
+    }
+
+    /// Move pads along with the map
+    fn move_pads(&mut self) {
+        self.pads.retain_mut(|pad| {
+            pad.location.l += 1;
+            // Retain pads within the screen
+            pad.location.l < self.maxl
+        });
+    }
+
+    /// Move rocks and buoys along with the map
+    fn move_obstacles(&mut self) {
+        self.obstacles.retain_mut(|obstacle| {
+            obstacle.location.l += 1;
+            obstacle.location.l < self.maxl
+        });
+    }
+
+    /// A rock or a buoy stands at `c`, `l`
+    pub(super) fn obstacle_at(&self, c: u16, l: u16) -> bool {
+        let here = |location: &Location| location.c == c && location.l == l;
+        self.obstacles
+            .iter()
+            .any(|obstacle| here(&obstacle.location))
+    }
+
+    /// The river holds all the enemies it may, more wait to spawn
+    pub(super) fn enemies_capped(&self) -> bool {
+        self.enemies.len() >= self.settings.max_enemies
+    }
+
+    /// As many bullets fly as may: enemies hold fire, the player's gun jams
+    pub fn bullets_capped(&self) -> bool {
+        self.bullets.len() + self.enemy_bullets.len() >= self.settings.max_bullets
+    }
+
+    /// Number of entities that count toward `entity_cap`
+    pub(super) fn entity_count(&self) -> usize {
+        self.enemies.len() + self.fuels.len() + self.enemy_bullets.len()
+    }
+
+    /// Drop entities over the entity cap, lowest value first and oldest
+    /// first on a tie. Enemy bullets are worth nothing, enemies and depots
+    /// what shooting them scores, wrecks nothing. Bridges are never culled.
+    pub(super) fn cull_entities(&mut self) {
+        let mut excess = self.entity_count().saturating_sub(self.settings.entity_cap);
+        if excess == 0 {
+            return;
+        }
+        self.culled += excess;
+
+        // Entities are pushed in spawn order, so the front is the oldest and
+        // the closest to leaving the screen anyway.
+        let n = excess.min(self.enemy_bullets.len());
+        self.enemy_bullets.drain(..n);
+        excess -= n;
+        if excess == 0 {
+            return;
+        }
+
+        let value = |alive: bool, reward: u16| if alive { reward } else { 0 };
+        let enemies = self.enemies.iter().enumerate().map(|(index, enemy)| {
+            let alive = matches!(enemy.status, EntityStatus::Alive);
+            (value(alive, enemy.reward()), enemy.id, true, index)
+        });
+        let fuels = self.fuels.iter().enumerate().map(|(index, fuel)| {
+            let alive = matches!(fuel.status, EntityStatus::Alive);
+            (value(alive, Fuel::REWARD), fuel.id, false, index)
+        });
+        let mut ranked: Vec<_> = enemies.chain(fuels).collect();
+        ranked.sort_unstable();
+
+        let mut cull_enemy = vec![false; self.enemies.len()];
+        let mut cull_fuel = vec![false; self.fuels.len()];
+        for &(_, _, is_enemy, index) in ranked.iter().take(excess) {
+            if is_enemy {
+                cull_enemy[index] = true;
+            } else {
+                cull_fuel[index] = true;
+            }
         }
+        let mut culls = cull_enemy.into_iter();
+        self.enemies.retain(|_| !culls.next().unwrap_or_default());
+        let mut culls = cull_fuel.into_iter();
+        self.fuels.retain(|_| !culls.next().unwrap_or_default());
     }
 
     /// Move fuels on the river
@@ -176,28 +671,114 @@ impl World {
         });
     }
 
-    pub(super) fn physics(&mut self) {
+    /// Every check of what hit what, once a tick.
+    fn check_collisions(&mut self) {
+        self.check_crashes();
+
+        // pickups and pads go by the tick, however many substeps it has
+        self.check_pad_status();
+        self.check_bomb_crate_status();
+        self.check_fuel_status();
+
+        // bullets last, what they hit stays on screen as a wreck this tick
+        self.check_bullet_hits();
+    }
+
+    /// The checks of what may have run into what between two substeps.
+    fn check_crashes(&mut self) {
         // check if player hit the ground
         self.check_player_status();
 
         // check enemy hit something
         self.check_enemy_status();
-        self.check_fuel_status();
+        self.check_enemy_bullets_status();
+        self.check_bridge_status();
+        self.check_tunnel_status();
+    }
+
+    /// Lines the river scrolls this tick, carrying the fraction of
+    /// `scroll_speed`, at the pace of the stage, over to the next ticks.
+    fn scroll_lines(&mut self) -> u16 {
+        let pace = self.stages.stage().map_or(1.0, |stage| stage.speed);
+        self.scroll_progress += (self.scroll_speed * pace).min(MAX_SCROLL_SPEED);
+        let lines = self.scroll_progress.floor();
+        self.scroll_progress -= lines;
+        lines as u16
+    }
 
-        // move the map Downward
+    /// Substeps to split this tick into, when the river scrolls `lines`
+    /// lines: one per line anything moves, so nothing skips past a bend or
+    /// an entity between two checks.
+    fn substeps(&self, lines: u16) -> u16 {
+        let fastest_bullet = self.bullets.iter().map(|bullet| bullet.speed).max();
+        lines.max(fastest_bullet.unwrap_or(0)).max(1)
+    }
+
+    /// Move the map a line downward, and what stands on it.
+    fn scroll_line(&mut self) {
         self.update_map();
+        self.move_obstacles();
 
-        // create new enemy
-        self.create_enemy();
-        self.create_fuel();
+        // create new entities
+        self.spawn();
 
         // Move elements along map movements
+        self.move_bridges();
+        self.move_tunnels();
+        self.move_pads();
+        self.move_bomb_crates();
         self.move_enemies();
         self.move_fuel();
-        self.move_bullets();
+        self.move_enemy_bullets();
+    }
+
+    pub(super) fn physics(&mut self) {
+        self.check_collisions();
+        self.check_gas();
+
+        let lines = self.scroll_lines();
+        for step in 0..self.substeps(lines) {
+            // whatever moved on the previous substep may have run into the player
+            if step > 0 {
+                self.check_crashes();
+                self.check_bullet_hits();
+            }
+            if step < lines {
+                self.scroll_line();
+            }
+            self.move_bullets(step);
+        }
+
+        self.bomb_flash = self.bomb_flash.saturating_sub(1);
+        self.combo.tick();
+        if self.biome_index() != self.themed_biome {
+            self.refresh_theme();
+        }
+        if self.stages.tick(self.traveled) {
+            // a new stage brings its biome
+            self.refresh_theme();
+            let name = self
+                .stages
+                .stage()
+                .map(|stage| stage.name.clone())
+                .unwrap_or_default();
+            let biome = self.biome().name.clone();
+            self.log(CombatEvent::Stage { name, biome });
+        }
+        if self.stages.completed() && self.player.is_flying() {
+            self.player.status = PlayerStatus::Completed;
+        }
+        if let Some(outcome) = self.challenges.tick(self.play_time) {
+            self.settle_challenge(outcome);
+        }
+        self.fade_sparks();
+        self.fire_enemy_bullets();
+        self.cull_entities();
 
         if self.player.gas >= 1 {
             self.player.gas -= 1;
         }
+
+        self.player.invulnerable = self.player.invulnerable.saturating_sub(1);
     }
 }
@@ -0,0 +1,151 @@
+//! Islands: now and then the river splits around one for a stretch, and
+//! the player has to pick a channel. The island of each line of the map is
+//! kept in [`World::islands`], next to its banks.
+
+use std::ops::Range;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{arith, World};
+
+/// Narrowest river an island may start in
+const ISLAND_MIN_RIVER: u16 = 18;
+
+/// Chance of an island starting on a new line wide enough, per mille
+const ISLAND_CHANCE: u16 = 4;
+
+/// How many lines an island lasts
+const ISLAND_LINES: Range<u16> = 30..80;
+
+/// Narrowest a channel next to an island gets
+const CHANNEL_MIN: u16 = 3;
+
+/// Streams of [`World::line_rng`]: the banks and islands of a line, and what
+/// spawns on it. Spawners may use the streams past `SPAWN_STREAM` too.
+pub(super) const MAP_STREAM: u64 = 0;
+pub(super) const SPAWN_STREAM: u64 = 1;
+
+impl World {
+    /// A generator for the line of river being laid, drawing on `stream` of
+    /// the seed. Each line and stream gets its own, so draws skipped on one
+    /// (a tunnel rushing by, a spawn held back) change nothing on the others.
+    pub(super) fn line_rng(&self, stream: u64) -> StdRng {
+        let mix = |value: u64| {
+            let value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            let value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            value ^ (value >> 31)
+        };
+        let line = mix(u64::from(self.traveled) ^ mix(stream));
+        StdRng::seed_from_u64(mix(self.seed ^ line))
+    }
+
+    /// `(c, l)` is water: between the banks and off any island.
+    pub fn is_in_river(&self, c: u16, l: u16) -> bool {
+        let Some(&(left, right)) = self.map.get(l as usize) else {
+            return false;
+        };
+        let on_island = matches!(
+            self.islands.get(l as usize),
+            Some(Some((start, end))) if (*start..*end).contains(&c)
+        );
+        (left..right).contains(&c) && !on_island
+    }
+
+    /// A random column of water on the first line of the river.
+    pub(super) fn random_river_column(&mut self) -> u16 {
+        let (left, right) = self.map[0];
+        match self.islands[0] {
+            Some((start, end)) => {
+                let c = self.spawn_rng.gen_range(left..right - (end - start));
+                if c >= start {
+                    c + (end - start)
+                } else {
+                    c
+                }
+            }
+            None => self.spawn_rng.gen_range(left..right),
+        }
+    }
+
+    /// Banks of the water `c` is in on line `l`: the channel it is in if the
+    /// river splits there.
+    pub(super) fn channel(&self, c: u16, l: u16) -> (u16, u16) {
+        let (left, right) = self.map[l as usize];
+        match self.islands[l as usize] {
+            Some((start, _)) if c < start => (left, start),
+            Some((_, end)) => (end, right),
+            None => (left, right),
+        }
+    }
+
+    /// Middle of the river on line `l`, or of its wider channel.
+    pub(super) fn river_center(&self, l: u16) -> u16 {
+        let (left, right) = self.map[l as usize];
+        let (left, right) = match self.islands[l as usize] {
+            Some((start, end)) if start - left >= right - end => (left, start),
+            Some((_, end)) => (end, right),
+            None => (left, right),
+        };
+        left + (right - left) / 2
+    }
+
+    /// The island on the new line of river between `left` and `right`, if
+    /// one is being laid or starts here. The banks ahead are kept clear of
+    /// it.
+    pub(super) fn lay_island(&mut self, left: u16, right: u16) -> Option<(u16, u16)> {
+        if self.island_ahead.is_none()
+            && right - left >= ISLAND_MIN_RIVER
+            && self.map_rng.gen_range(0..1000) < ISLAND_CHANCE
+        {
+            let third = (right - left) / 3;
+            let lines = self.map_rng.gen_range(ISLAND_LINES);
+            self.island_ahead = Some(((left + third, right - third), lines));
+        }
+
+        let (island, lines_left) = self.island_ahead.as_mut()?;
+        let island = *island;
+        *lines_left -= 1;
+        if *lines_left == 0 {
+            self.island_ahead = None;
+        }
+        self.next_left = self.next_left.min(arith::sub(island.0, CHANNEL_MIN));
+        self.next_right = self.next_right.max(arith::add(island.1, CHANNEL_MIN));
+        Some(island)
+    }
+} // end of World implementation.
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, time::Duration};
+
+    use crate::World;
+
+    #[test]
+    fn islands_split_the_river() {
+        let mut world = World::new(80, 24);
+        world.set_seed(5);
+        world.spawner = None;
+        world.map = VecDeque::from(vec![(20, 60); 24]);
+        world.islands = VecDeque::from(vec![Some((35, 50)); 24]);
+
+        assert!(world.is_in_river(34, 3) && world.is_in_river(50, 3));
+        assert!(!world.is_in_river(35, 3) && !world.is_in_river(49, 3));
+        assert_eq!(world.river_center(3), 27);
+        assert!((0..200).all(|_| {
+            let c = world.random_river_column();
+            world.is_in_river(c, 0)
+        }));
+
+        world.island_ahead = Some(((30, 40), 2));
+        assert_eq!(world.lay_island(20, 60), Some((30, 40)));
+        assert!(world.next_left <= 27 && world.next_right >= 43);
+        assert_eq!(world.lay_island(20, 60), Some((30, 40)));
+        assert!(world.island_ahead.is_none());
+
+        world.player.location.c = 40;
+        world.player.invulnerable = 0;
+        let lives = world.player.lives;
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.player.lives, lives - 1);
+    }
+}
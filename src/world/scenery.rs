@@ -0,0 +1,106 @@
+//! Scenery on the banks: trees, houses and airstrips scattered next to the
+//! river. It is only for show; the pieces of each line of the map are kept
+//! in [`World::scenery`] and scroll down with it.
+
+use crate::World;
+
+/// Chance of a piece starting on a bank cell, per mille
+const SCENERY_CHANCE: u64 = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scenery {
+    Tree,
+    House,
+    Airstrip,
+}
+
+impl Scenery {
+    /// Glyphs of the piece, left to right from its column
+    pub fn glyphs(&self) -> &'static str {
+        match self {
+            Scenery::Tree => "♧",
+            Scenery::House => "⌂",
+            Scenery::Airstrip => "┅┅┅┅┅",
+        }
+    }
+
+    fn width(&self) -> u16 {
+        self.glyphs().chars().count() as u16
+    }
+} // end of Scenery implementation.
+
+impl World {
+    /// Scenery on the banks of line `l` of the map. The same cell of the
+    /// same line of river always gets the same piece.
+    pub(super) fn lay_scenery(&self, l: u16) -> Vec<(u16, Scenery)> {
+        // lines count from the start of the river, like decorations
+        let line = self.traveled.wrapping_sub(l as u32);
+        let (left, right) = self.map[l as usize];
+        let island = self.islands[l as usize].unwrap_or((0, 0));
+        let mut pieces = Vec::new();
+        for (start, end) in [(0, left), island, (right, self.maxc)] {
+            let mut c = start;
+            while c < end {
+                let mix = u64::from(line).wrapping_mul(0xd6e8_feb8_6659_fd93)
+                    ^ u64::from(c).wrapping_mul(0xa076_1d64_78bd_642f);
+                let mix = mix ^ (mix >> 31);
+                let piece = match (mix >> 32) % 10 {
+                    0..=5 => Scenery::Tree,
+                    6..=8 => Scenery::House,
+                    _ => Scenery::Airstrip,
+                };
+                if mix % 1000 < SCENERY_CHANCE && c + piece.width() <= end {
+                    pieces.push((c, piece));
+                    c += piece.width() + 1;
+                } else {
+                    c += 1;
+                }
+            }
+        }
+        pieces
+    }
+
+    /// The pieces of line `l` that are still all on the bank.
+    pub(super) fn trim_scenery(&mut self, l: u16) {
+        let mut pieces = std::mem::take(&mut self.scenery[l as usize]);
+        pieces.retain(|(c, piece)| {
+            (*c..c + piece.width()).all(|c| c < self.maxc && !self.is_in_river(c, l))
+        });
+        self.scenery[l as usize] = pieces;
+    }
+
+    /// Draw the scenery over the banks.
+    pub(super) fn draw_scenery(&mut self) {
+        let style = self.canvas.theme().decoration;
+        for (l, pieces) in self.scenery.iter().enumerate() {
+            for (c, piece) in pieces {
+                self.canvas
+                    .draw_styled_line((*c, l as u16), piece.glyphs().to_string(), style);
+            }
+        }
+    }
+} // end of World implementation.
+
+#[cfg(test)]
+mod tests {
+    use crate::World;
+
+    #[test]
+    fn scenery_stays_on_the_banks() {
+        let mut world = World::new(80, 24);
+        world.set_seed(4);
+        world.spawner = None;
+        world.fly(300);
+        world.resize(60, 30);
+
+        assert_eq!(world.scenery.len(), world.map.len());
+        assert!(world.scenery.iter().any(|pieces| !pieces.is_empty()));
+        for (l, pieces) in world.scenery.iter().enumerate() {
+            for (c, piece) in pieces {
+                let width = piece.glyphs().chars().count() as u16;
+                let on_bank = |c| c < world.maxc && !world.is_in_river(c, l as u16);
+                assert!((*c..c + width).all(on_bank));
+            }
+        }
+    }
+}
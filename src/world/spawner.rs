@@ -0,0 +1,616 @@
+//! Entity spawning.
+//!
+//! [`World`] asks its [`Spawner`] for new entities once per tick. The default
+//! [`RandomSpawner`] is the classic probabilistic one; replace
+//! [`World::spawner`] to script waves or drive difficulty from elsewhere.
+
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    entities::{
+        BombCrate, Bridge, Direction, Enemy, EntityStatus, Fuel, Obstacle, ObstacleKind,
+        RechargePad, Tunnel,
+    },
+    pack::PICKUP_BOOST,
+    season::SeasonalEvent,
+    World,
+};
+
+use super::river::SPAWN_STREAM;
+
+/// Narrowest water an obstacle stands in
+const OBSTACLE_MIN_WATER: u16 = 12;
+
+/// Water kept free on each side of an obstacle, room for the jet
+const OBSTACLE_CLEARANCE: u16 = 4;
+
+/// Fewest lines between two obstacles
+const OBSTACLE_SPACING: u16 = 6;
+
+pub trait Spawner {
+    /// Called once per tick, right after the map moved. New entities belong
+    /// on line `0`, inside `world.map[0]`.
+    fn spawn(&mut self, world: &mut World);
+
+    /// Lines until the next bridge spawns, if the spawner knows.
+    fn lines_to_bridge(&self, _world: &World) -> Option<u32> {
+        None
+    }
+}
+
+/// Entities the [`RandomSpawner`] rolls for every line
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpawnKind {
+    Enemy,
+    Strafer,
+    Helicopter,
+    Ship,
+    Tunnel,
+    Pad,
+    Fuel,
+    Bomb,
+    Obstacle,
+}
+
+impl SpawnKind {
+    /// In the order they are rolled for
+    pub const ALL: [SpawnKind; 9] = [
+        SpawnKind::Enemy,
+        SpawnKind::Strafer,
+        SpawnKind::Helicopter,
+        SpawnKind::Ship,
+        SpawnKind::Tunnel,
+        SpawnKind::Pad,
+        SpawnKind::Fuel,
+        SpawnKind::Bomb,
+        SpawnKind::Obstacle,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpawnKind::Enemy => "enemy",
+            SpawnKind::Strafer => "strafer",
+            SpawnKind::Helicopter => "helicopter",
+            SpawnKind::Ship => "ship",
+            SpawnKind::Tunnel => "tunnel",
+            SpawnKind::Pad => "pad",
+            SpawnKind::Fuel => "fuel",
+            SpawnKind::Bomb => "bomb",
+            SpawnKind::Obstacle => "obstacle",
+        }
+    }
+
+    /// Counts toward [`crate::settings::Settings::max_enemies`]
+    pub fn is_enemy(&self) -> bool {
+        matches!(
+            self,
+            SpawnKind::Enemy | SpawnKind::Strafer | SpawnKind::Helicopter | SpawnKind::Ship
+        )
+    }
+
+    pub fn from_name(name: &str) -> Option<SpawnKind> {
+        SpawnKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+} // end of SpawnKind implementation.
+
+/// `<kind> <chance>, ...`, as stages and biomes write spawn chances
+pub fn parse_chances(chances: &str) -> Option<Vec<(SpawnKind, u16)>> {
+    chances
+        .split(',')
+        .map(|chance| {
+            let (kind, value) = chance.trim().split_once(' ')?;
+            Some((SpawnKind::from_name(kind)?, value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Chance of each [`SpawnKind`] appearing on a new line, per mille.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SpawnTable {
+    pub enemy: u16,
+    pub strafer: u16,
+    pub helicopter: u16,
+    pub ship: u16,
+    pub tunnel: u16,
+    pub pad: u16,
+    pub fuel: u16,
+    pub bomb: u16,
+    pub obstacle: u16,
+}
+
+impl SpawnTable {
+    pub fn chance(&self, kind: SpawnKind) -> u16 {
+        match kind {
+            SpawnKind::Enemy => self.enemy,
+            SpawnKind::Strafer => self.strafer,
+            SpawnKind::Helicopter => self.helicopter,
+            SpawnKind::Ship => self.ship,
+            SpawnKind::Tunnel => self.tunnel,
+            SpawnKind::Pad => self.pad,
+            SpawnKind::Fuel => self.fuel,
+            SpawnKind::Bomb => self.bomb,
+            SpawnKind::Obstacle => self.obstacle,
+        }
+    }
+
+    pub fn chance_mut(&mut self, kind: SpawnKind) -> &mut u16 {
+        match kind {
+            SpawnKind::Enemy => &mut self.enemy,
+            SpawnKind::Strafer => &mut self.strafer,
+            SpawnKind::Helicopter => &mut self.helicopter,
+            SpawnKind::Ship => &mut self.ship,
+            SpawnKind::Tunnel => &mut self.tunnel,
+            SpawnKind::Pad => &mut self.pad,
+            SpawnKind::Fuel => &mut self.fuel,
+            SpawnKind::Bomb => &mut self.bomb,
+            SpawnKind::Obstacle => &mut self.obstacle,
+        }
+    }
+} // end of SpawnTable implementation.
+
+impl Default for SpawnTable {
+    fn default() -> Self {
+        SpawnTable {
+            enemy: 100,
+            strafer: 20,
+            helicopter: 30,
+            ship: 20,
+            tunnel: 2,
+            pad: 5,
+            fuel: 10,
+            bomb: 1,
+            obstacle: 6,
+        }
+    }
+}
+
+/// Spawns enemies, strafers, helicopters, ships, tunnels, recharge pads,
+/// fuels, bomb crates and obstacles with the chances in its [`SpawnTable`]
+/// (or in the current stage's, see [`crate::stage`]) as the biome changes
+/// them, and a bridge every [`RandomSpawner::BRIDGE_INTERVAL`] lines.
+pub struct RandomSpawner {
+    pub table: SpawnTable,
+    lines_since_bridge: u16,
+}
+
+impl RandomSpawner {
+    pub const BRIDGE_INTERVAL: u16 = 400;
+
+    pub fn new() -> Self {
+        RandomSpawner::with_table(SpawnTable::default())
+    }
+
+    pub fn with_table(table: SpawnTable) -> Self {
+        RandomSpawner {
+            table,
+            lines_since_bridge: 0,
+        }
+    }
+
+    /// Create a new bridge across the river every now and then
+    fn create_bridge(&mut self, world: &mut World) {
+        self.lines_since_bridge += 1;
+        if self.lines_since_bridge >= Self::BRIDGE_INTERVAL {
+            self.lines_since_bridge = 0;
+            let (left, right) = world.map[0];
+            world.bridges.push(Bridge::new(left, 0, right - left));
+        }
+    }
+
+    /// Chance of `kind` on this line, per mille, after what the world is up to
+    fn chance(&self, kind: SpawnKind, world: &World) -> u16 {
+        // the stage being played has its own chances
+        let table = world
+            .stages
+            .stage()
+            .map_or(&self.table, |stage| &stage.spawns);
+        let base = table.chance(kind);
+        // the biome may change some
+        let base = world.biome().spawn_chance(kind).unwrap_or(base);
+        // and a script may have the last word
+        #[cfg(feature = "scripting")]
+        let base = world
+            .script
+            .as_ref()
+            .and_then(|script| script.chance(kind))
+            .unwrap_or(base);
+        // unless set on the control pipe
+        let base = world.chance_override(kind).unwrap_or(base);
+        match kind {
+            // enemies swarm players recharging their shield
+            SpawnKind::Enemy => {
+                let mut chance = if world.recharging { base * 3 } else { base };
+                if world.event == Some(SeasonalEvent::EnemyRush) {
+                    chance += base;
+                }
+                chance
+            }
+            // holiday packs hand out more of their pickups, but a seeded run
+            // plays the same whatever the date
+            _ if !world.fixed_seed
+                && world
+                    .pack
+                    .as_ref()
+                    .is_some_and(|pack| pack.pickups.contains(&kind)) =>
+            {
+                base * PICKUP_BOOST
+            }
+            _ => base,
+        }
+    }
+
+    fn create(&self, kind: SpawnKind, world: &mut World) {
+        match kind {
+            SpawnKind::Enemy => self.create_enemy(world),
+            SpawnKind::Strafer => self.create_strafer(world),
+            SpawnKind::Helicopter => self.create_helicopter(world),
+            SpawnKind::Ship => self.create_ship(world),
+            SpawnKind::Tunnel => self.create_tunnel(world),
+            SpawnKind::Pad => self.create_pad(world),
+            SpawnKind::Fuel => self.create_fuel(world),
+            SpawnKind::Bomb => self.create_bomb_crate(world),
+            SpawnKind::Obstacle => self.create_obstacle(world),
+        }
+    }
+
+    /// Create a new fuel
+    fn create_fuel(&self, world: &mut World) {
+        let mut fuel = Fuel::new(world.random_river_column(), 0, EntityStatus::Alive);
+        if world.event == Some(SeasonalEvent::DoubleFuel) {
+            fuel.stock *= 2;
+        }
+        world.fuels.push(fuel);
+    }
+
+    /// Create a new enemy
+    fn create_enemy(&self, world: &mut World) {
+        let c = world.random_river_column();
+        world.enemies.push(Enemy::new(c, 0, EntityStatus::Alive));
+    }
+
+    /// Create a new helicopter
+    fn create_helicopter(&self, world: &mut World) {
+        let direction = if world.spawn_rng.gen_bool(0.5) {
+            Direction::Left
+        } else {
+            Direction::Right
+        };
+        let c = world.random_river_column();
+        world.enemies.push(Enemy::helicopter(c, 0, direction));
+    }
+
+    /// Create a new ship, if the river is wide enough for it and not split
+    fn create_ship(&self, world: &mut World) {
+        let (left, right) = world.map[0];
+        if right - left > 4 && world.islands[0].is_none() {
+            world.enemies.push(Enemy::ship(
+                world.spawn_rng.gen_range(left + 1..right - 1),
+                0,
+            ));
+        }
+    }
+
+    /// Create a new recharge pad on the bank next to the river
+    fn create_pad(&self, world: &mut World) {
+        let (left, right) = world.map[0];
+        if left > 0 {
+            world.pads.push(RechargePad::new(left - 1, 0));
+        } else if right < world.maxc {
+            world.pads.push(RechargePad::new(right, 0));
+        }
+    }
+
+    /// Create a new bomb crate
+    fn create_bomb_crate(&self, world: &mut World) {
+        let c = world.random_river_column();
+        world.bomb_crates.push(BombCrate::new(c, 0));
+    }
+
+    /// Create a rock or a buoy, where the water is wide enough to pass it on
+    /// either side and no other obstacle or bridge is close
+    fn create_obstacle(&self, world: &mut World) {
+        let crowded = world
+            .obstacles
+            .iter()
+            .any(|obstacle| obstacle.location.l < OBSTACLE_SPACING)
+            || world.bridges.iter().any(|bridge| bridge.location.l == 0);
+        if crowded {
+            return;
+        }
+
+        let c = world.random_river_column();
+        let (left, right) = world.channel(c, 0);
+        if right - left < OBSTACLE_MIN_WATER
+            || c < left + OBSTACLE_CLEARANCE
+            || c + OBSTACLE_CLEARANCE >= right
+        {
+            return;
+        }
+        let kind = if world.spawn_rng.gen_bool(0.5) {
+            ObstacleKind::Rock
+        } else {
+            ObstacleKind::Buoy
+        };
+        world.obstacles.push(Obstacle::new(c, 0, kind));
+    }
+
+    /// Create a new tunnel entrance
+    fn create_tunnel(&self, world: &mut World) {
+        let c = world.random_river_column();
+        world.tunnels.push(Tunnel::new(c, 0));
+    }
+
+    /// Create a new strafer on the bank right next to the river
+    fn create_strafer(&self, world: &mut World) {
+        let (left, right) = world.map[0];
+        let has_left_bank = left > 0;
+        let has_right_bank = right < world.maxc;
+
+        let direction = match (has_left_bank, has_right_bank) {
+            (true, true) if world.spawn_rng.gen_bool(0.5) => Direction::Left,
+            (true, _) => Direction::Right,
+            (false, true) => Direction::Left,
+            (false, false) => return,
+        };
+
+        let column = match direction {
+            // dashing to the right means it waits on the left bank
+            Direction::Right => left - 1,
+            Direction::Left => right,
+        };
+        world.enemies.push(Enemy::strafer(column, 0, direction));
+    }
+}
+
+impl Spawner for RandomSpawner {
+    fn spawn(&mut self, world: &mut World) {
+        self.create_bridge(world);
+        // a river full of enemies holds the next ones back
+        let capped = world.enemies_capped();
+        // waves of the stage come one per line
+        if let Some(kind) = world.stages.next_wave_spawn(capped) {
+            self.create(kind, world);
+        }
+        for (stream, kind) in (SPAWN_STREAM + 1..).zip(SpawnKind::ALL) {
+            // each kind rolls and lands on a stream of its own, so holding
+            // enemies back changes nothing else on the river
+            world.spawn_rng = world.line_rng(stream);
+            let roll = world.spawn_rng.gen_range(0..1000);
+            if roll < self.chance(kind, world) && !(capped && kind.is_enemy()) {
+                self.create(kind, world);
+            }
+        }
+    }
+
+    fn lines_to_bridge(&self, _world: &World) -> Option<u32> {
+        Some(u32::from(Self::BRIDGE_INTERVAL - self.lines_since_bridge))
+    }
+}
+
+/// Spawns what the markers of the custom track being flown put on each
+/// line, and its bridges; see [`crate::track`]. Waves, scripts and the
+/// control pipe still send theirs down the river.
+pub struct TrackSpawner;
+
+impl TrackSpawner {
+    /// Where a `kind` without a marker goes on the first line: on the bank
+    /// next to the river for those standing there, else somewhere on water.
+    fn column(kind: SpawnKind, world: &mut World) -> Option<u16> {
+        let (left, right) = world.map[0];
+        match kind {
+            SpawnKind::Strafer | SpawnKind::Pad => left
+                .checked_sub(1)
+                .or((right < world.maxc).then_some(right)),
+            _ => Some(world.random_river_column()),
+        }
+    }
+
+    fn place(kind: SpawnKind, c: u16, world: &mut World) {
+        // movers head for the far side of the river
+        let direction = if c < world.river_center(0) {
+            Direction::Right
+        } else {
+            Direction::Left
+        };
+        match kind {
+            SpawnKind::Enemy => world.enemies.push(Enemy::new(c, 0, EntityStatus::Alive)),
+            SpawnKind::Strafer => world.enemies.push(Enemy::strafer(c, 0, direction)),
+            SpawnKind::Helicopter => world.enemies.push(Enemy::helicopter(c, 0, direction)),
+            SpawnKind::Ship => world.enemies.push(Enemy::ship(c, 0)),
+            SpawnKind::Tunnel => world.tunnels.push(Tunnel::new(c, 0)),
+            SpawnKind::Pad => world.pads.push(RechargePad::new(c, 0)),
+            SpawnKind::Fuel => {
+                let mut fuel = Fuel::new(c, 0, EntityStatus::Alive);
+                if world.event == Some(SeasonalEvent::DoubleFuel) {
+                    fuel.stock *= 2;
+                }
+                world.fuels.push(fuel);
+            }
+            SpawnKind::Bomb => world.bomb_crates.push(BombCrate::new(c, 0)),
+            SpawnKind::Obstacle => world
+                .obstacles
+                .push(Obstacle::new(c, 0, ObstacleKind::Rock)),
+        }
+    }
+} // end of TrackSpawner implementation.
+
+impl Spawner for TrackSpawner {
+    fn spawn(&mut self, world: &mut World) {
+        let Some((track, next)) = &world.track else {
+            return;
+        };
+        let line = world.fitted(track.line(*next - 1));
+        if line.bridge {
+            let (left, right) = world.map[0];
+            world.bridges.push(Bridge::new(left, 0, right - left));
+        }
+        // a screen narrower than the track leaves some markers off it
+        for (kind, c) in &line.spawns {
+            if line.fits(*kind, *c) && *c < world.maxc {
+                TrackSpawner::place(*kind, *c, world);
+            }
+        }
+        if let Some(kind) = world.stages.next_wave_spawn(world.enemies_capped()) {
+            if let Some(c) = TrackSpawner::column(kind, world) {
+                TrackSpawner::place(kind, c, world);
+            }
+        }
+    }
+
+    fn lines_to_bridge(&self, world: &World) -> Option<u32> {
+        let (track, next) = world.track.as_ref()?;
+        // the line spawned last is `next - 1`, the track loops
+        (1..=track.lines.len())
+            .find(|ahead| track.line(next - 1 + ahead).bridge)
+            .map(|ahead| ahead as u32)
+    }
+}
+
+impl World {
+    /// Let the registered spawner add this tick's entities.
+    pub(super) fn spawn(&mut self) {
+        self.spawn_rng = self.line_rng(SPAWN_STREAM);
+        // The spawner needs the whole world, so take it out while it runs.
+        if let Some(mut spawner) = self.spawner.take() {
+            spawner.spawn(self);
+            self.spawner = Some(spawner);
+        }
+        self.record_spawns();
+        self.assign_ids();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        RandomSpawner, SpawnKind, SpawnTable, Spawner, OBSTACLE_CLEARANCE, OBSTACLE_MIN_WATER,
+        OBSTACLE_SPACING,
+    };
+    use crate::{
+        entities::{Location, Obstacle, ObstacleKind},
+        pack::{ContentPack, PICKUP_BOOST},
+        World,
+    };
+
+    #[test]
+    fn table_drives_the_spawns() {
+        let table = SpawnTable {
+            enemy: 0,
+            strafer: 0,
+            helicopter: 0,
+            ship: 0,
+            tunnel: 0,
+            pad: 0,
+            fuel: 1000,
+            bomb: 0,
+            obstacle: 0,
+        };
+
+        let mut world = World::new(80, 24);
+        let mut spawner = RandomSpawner::with_table(table);
+        for _ in 0..5 {
+            spawner.spawn(&mut world);
+        }
+        assert_eq!(world.fuels.len(), 5);
+        assert!(world.enemies.is_empty() && world.tunnels.is_empty() && world.pads.is_empty());
+    }
+
+    #[test]
+    fn packs_boost_pickups_of_unseeded_runs_only() {
+        let spawner = RandomSpawner::new();
+        let mut world = World::new(80, 24);
+        let base = spawner.chance(SpawnKind::Bomb, &world);
+        assert!(base > 0);
+        world.set_pack(ContentPack::active(ContentPack::built_in(), (10, 31)).unwrap());
+        assert_eq!(spawner.chance(SpawnKind::Bomb, &world), base * PICKUP_BOOST);
+        world.set_seed(7);
+        assert_eq!(spawner.chance(SpawnKind::Bomb, &world), base);
+    }
+
+    #[test]
+    fn capping_enemies_leaves_the_rest_of_the_river_alone() {
+        let fly = |max_enemies| {
+            let mut world = World::new(80, 24);
+            world.set_seed(42);
+            world.settings.max_enemies = max_enemies;
+            let mut lines = Vec::new();
+            for _ in 0..600 {
+                world.fly(1);
+                let places = |locations: Vec<&Location>| -> Vec<(u16, u16)> {
+                    locations.iter().map(|at| (at.c, at.l)).collect()
+                };
+                lines.push((
+                    world.traveled,
+                    world.map[0],
+                    places(world.fuels.iter().map(|fuel| &fuel.location).collect()),
+                    places(world.pads.iter().map(|pad| &pad.location).collect()),
+                    places(
+                        world
+                            .tunnels
+                            .iter()
+                            .map(|tunnel| &tunnel.location)
+                            .collect(),
+                    ),
+                    places(
+                        world
+                            .obstacles
+                            .iter()
+                            .map(|obstacle| &obstacle.location)
+                            .collect(),
+                    ),
+                ));
+            }
+            (lines, world.enemies.len())
+        };
+        let (capped, none) = fly(0);
+        let (uncapped, some) = fly(usize::MAX);
+        assert_eq!(none, 0);
+        assert!(some > 0);
+        assert_eq!(capped, uncapped);
+    }
+
+    #[test]
+    fn obstacles_leave_room_to_pass() {
+        let table = SpawnTable {
+            obstacle: 1000,
+            ..SpawnTable::default()
+        };
+        let mut world = World::new(80, 24);
+        world.set_seed(9);
+        world.spawner = Some(Box::new(RandomSpawner::with_table(table)));
+        world.map = vec![(10, 70); 24].into();
+        (world.next_left, world.next_right) = (10, 70);
+        let mut spawned = 0;
+        for _ in 0..200 {
+            world.fly(1);
+            for obstacle in &world.obstacles {
+                let Location { c, l } = obstacle.location;
+                let (left, right) = world.channel(c, l);
+                assert!(right - left >= OBSTACLE_MIN_WATER);
+                assert!(c >= left + OBSTACLE_CLEARANCE && c + OBSTACLE_CLEARANCE < right);
+            }
+            let mut lines: Vec<u16> = world.obstacles.iter().map(|o| o.location.l).collect();
+            lines.sort();
+            assert!(lines
+                .windows(2)
+                .all(|pair| pair[1] - pair[0] >= OBSTACLE_SPACING));
+            spawned += lines.first().is_some_and(|l| *l == 0) as usize;
+        }
+        assert!(spawned > 10);
+
+        // flying into one costs a life
+        let mut world = World::new(80, 24);
+        world.spawner = None;
+        world.player.invulnerable = 0;
+        let Location { c, l } = world.player.location;
+        world
+            .obstacles
+            .push(Obstacle::new(c, l - 1, ObstacleKind::Rock));
+        world.tick(&[], Duration::from_millis(60));
+        assert_eq!(world.player.lives, 2);
+    }
+}
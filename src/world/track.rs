@@ -0,0 +1,116 @@
+//! Flying a custom track instead of a random river, and recording the river
+//! of a run as one; see [`crate::track`].
+
+use crate::{
+    entities::{EnemyKind, Location},
+    track::{Track, TrackLine},
+    World,
+};
+
+use super::{SpawnKind, TrackSpawner};
+
+impl World {
+    /// Fly `track` from now on, its first line at the bottom of the screen.
+    /// Only its markers spawn anything.
+    pub fn set_track(&mut self, track: Track) {
+        for l in 0..self.maxl {
+            let line = track.line((self.maxl - 1 - l) as usize);
+            let line = self.fitted(line);
+            self.map[l as usize] = line.banks;
+            self.islands[l as usize] = line.island;
+        }
+        self.scenery = (0..self.maxl).map(|l| self.lay_scenery(l)).collect();
+        (self.next_left, self.next_right) = self.map[0];
+        self.player.location.c = self.river_center(self.player.location.l);
+        self.track = Some((track, self.maxl as usize));
+        self.spawner = Some(Box::new(TrackSpawner));
+    }
+
+    /// Record the river from now on, starting with what is on screen; see
+    /// [`World::recording`].
+    pub fn record_track(&mut self) {
+        let lines = (0..self.maxl as usize).rev().map(|l| TrackLine {
+            banks: self.map[l],
+            island: self.islands[l],
+            ..TrackLine::default()
+        });
+        self.recording = Some(Track {
+            lines: lines.collect(),
+        });
+    }
+
+    /// The next line of the track being flown, if any.
+    pub(super) fn next_track_line(&mut self) -> Option<TrackLine> {
+        let (track, next) = self.track.as_mut()?;
+        *next += 1;
+        let line = track.line(*next - 1).clone();
+        Some(self.fitted(&line))
+    }
+
+    /// Note a new line of river in the recording.
+    pub(super) fn record_line(&mut self, banks: (u16, u16), island: Option<(u16, u16)>) {
+        if let Some(recording) = &mut self.recording {
+            recording.lines.push(TrackLine {
+                banks,
+                island,
+                ..TrackLine::default()
+            });
+        }
+    }
+
+    /// Note what just spawned on the first line in the recording.
+    pub(super) fn record_spawns(&mut self) {
+        let Some(line) = self
+            .recording
+            .as_mut()
+            .and_then(|track| track.lines.last_mut())
+        else {
+            return;
+        };
+        // everything else already moved down
+        let mut mark = |kind, location: &Location| {
+            if location.l == 0 {
+                line.spawns.push((kind, location.c));
+            }
+        };
+        for enemy in &self.enemies {
+            let kind = match enemy.kind {
+                EnemyKind::Strafer => SpawnKind::Strafer,
+                EnemyKind::Helicopter => SpawnKind::Helicopter,
+                EnemyKind::Ship => SpawnKind::Ship,
+                EnemyKind::Static => SpawnKind::Enemy,
+            };
+            mark(kind, &enemy.location);
+        }
+        for tunnel in &self.tunnels {
+            mark(SpawnKind::Tunnel, &tunnel.location);
+        }
+        for pad in &self.pads {
+            mark(SpawnKind::Pad, &pad.location);
+        }
+        for fuel in &self.fuels {
+            mark(SpawnKind::Fuel, &fuel.location);
+        }
+        for bomb in &self.bomb_crates {
+            mark(SpawnKind::Bomb, &bomb.location);
+        }
+        for obstacle in &self.obstacles {
+            mark(SpawnKind::Obstacle, &obstacle.location);
+        }
+        line.bridge |= self.bridges.iter().any(|bridge| bridge.location.l == 0);
+    }
+
+    /// `line` squeezed onto the screen, the same way [`World::resize`]
+    /// squeezes the river.
+    pub(super) fn fitted(&self, line: &TrackLine) -> TrackLine {
+        let right = line.banks.1.min(self.maxc - 1);
+        let left = line.banks.0.min(right.saturating_sub(3));
+        TrackLine {
+            banks: (left, right),
+            island: line
+                .island
+                .filter(|(start, end)| *start > left && *end < right),
+            ..line.clone()
+        }
+    }
+} // end of World implementation.